@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE duels DROP COLUMN IF EXISTS difficulty;
+
+ALTER TABLE duels DROP COLUMN IF EXISTS question_id;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS times_guessed_wrong;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS times_answered_correctly;
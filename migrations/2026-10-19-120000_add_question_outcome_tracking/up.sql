@@ -0,0 +1,11 @@
+-- Your SQL goes here
+ALTER TABLE questions
+ADD COLUMN times_answered_correctly INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE questions
+ADD COLUMN times_guessed_wrong INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE duels
+ADD COLUMN question_id INTEGER REFERENCES questions (id);
+
+ALTER TABLE duels ADD COLUMN difficulty VARCHAR(255);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS questions_status_idx;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS points_paid;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS review_note;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS status;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- questions that already exist were added by the broadcaster or before review existed
+ALTER TABLE questions
+ADD COLUMN status VARCHAR(255) NOT NULL DEFAULT 'approved';
+
+ALTER TABLE questions ADD COLUMN review_note TEXT;
+
+ALTER TABLE questions
+ADD COLUMN points_paid BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX questions_status_idx ON questions (status);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS update_question_votes_modtime ON question_votes;
+
+DROP TABLE IF EXISTS question_votes;
+
+DROP TRIGGER IF EXISTS update_question_reports_modtime ON question_reports;
+
+DROP TABLE IF EXISTS question_reports;
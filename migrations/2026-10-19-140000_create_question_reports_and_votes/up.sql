@@ -0,0 +1,33 @@
+-- Your SQL goes here
+CREATE TABLE question_reports (
+    id SERIAL PRIMARY KEY,
+    question_id INTEGER NOT NULL,
+    duel_id INTEGER,
+    reporter_id VARCHAR(255) NOT NULL,
+    reason TEXT,
+    resolved BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    FOREIGN KEY (question_id) REFERENCES questions (id),
+    FOREIGN KEY (duel_id) REFERENCES duels (id),
+    UNIQUE (question_id, reporter_id)
+);
+
+CREATE TRIGGER update_question_reports_modtime BEFORE
+UPDATE ON question_reports FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
+
+CREATE TABLE question_votes (
+    id SERIAL PRIMARY KEY,
+    question_id INTEGER NOT NULL,
+    voter_id VARCHAR(255) NOT NULL,
+    value SMALLINT NOT NULL CHECK (value IN (-1, 1)),
+    created_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    FOREIGN KEY (question_id) REFERENCES questions (id),
+    UNIQUE (question_id, voter_id)
+);
+
+CREATE TRIGGER update_question_votes_modtime BEFORE
+UPDATE ON question_votes FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE questions DROP COLUMN IF EXISTS source;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS authored_difficulty;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS alternate_answers;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+ALTER TABLE questions
+ADD COLUMN alternate_answers TEXT[] NOT NULL DEFAULT '{}';
+
+-- difficulty the question was authored with, used until there are enough asks to judge it
+ALTER TABLE questions ADD COLUMN authored_difficulty VARCHAR(255);
+
+ALTER TABLE questions ADD COLUMN source TEXT;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- wrong options that came with an imported multiple choice question
+ALTER TABLE questions
+ADD COLUMN incorrect_answers TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE duels DROP COLUMN IF EXISTS challenged_numeric_guess;
+
+ALTER TABLE duels DROP COLUMN IF EXISTS challenger_numeric_guess;
+
+ALTER TABLE questions DROP COLUMN IF EXISTS kind;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- free_text, multiple_choice, true_false or numeric
+ALTER TABLE questions
+ADD COLUMN kind VARCHAR(255) NOT NULL DEFAULT 'free_text';
+
+-- numeric questions are won by the closest guess, so both guesses are kept
+ALTER TABLE duels ADD COLUMN challenger_numeric_guess DOUBLE PRECISION;
+
+ALTER TABLE duels ADD COLUMN challenged_numeric_guess DOUBLE PRECISION;
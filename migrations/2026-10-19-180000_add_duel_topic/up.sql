@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- set when the duel asks a generated question instead of one from the questions table
+ALTER TABLE duels ADD COLUMN topic VARCHAR(255);
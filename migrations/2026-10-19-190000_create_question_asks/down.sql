@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS question_asks_asked_at_idx;
+
+DROP TABLE IF EXISTS question_asks;
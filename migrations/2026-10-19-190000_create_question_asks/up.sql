@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE question_asks (
+    id SERIAL PRIMARY KEY,
+    question_id INTEGER NOT NULL,
+    channel VARCHAR(255) NOT NULL,
+    player_ids TEXT[] NOT NULL DEFAULT '{}',
+    asked_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    FOREIGN KEY (question_id) REFERENCES questions (id)
+);
+
+CREATE INDEX question_asks_asked_at_idx ON question_asks (asked_at);
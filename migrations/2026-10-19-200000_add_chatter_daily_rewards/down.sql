@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE chatters
+DROP COLUMN IF EXISTS daily_streak,
+DROP COLUMN IF EXISTS last_daily_at;
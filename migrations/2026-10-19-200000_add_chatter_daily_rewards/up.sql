@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE chatters
+ADD COLUMN last_daily_at TIMESTAMP,
+ADD COLUMN daily_streak INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS update_redemptions_modtime ON redemptions;
+
+DROP INDEX IF EXISTS redemptions_status_idx;
+
+DROP TABLE IF EXISTS redemptions;
+
+DROP TRIGGER IF EXISTS update_shop_items_modtime ON shop_items;
+
+DROP TABLE IF EXISTS shop_items;
@@ -0,0 +1,38 @@
+-- Your SQL goes here
+CREATE TABLE shop_items (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT NOT NULL,
+    cost BIGINT NOT NULL CHECK (cost >= 0),
+    -- NULL means unlimited
+    stock INTEGER CHECK (stock >= 0),
+    per_user_limit INTEGER CHECK (per_user_limit > 0),
+    -- what buyers have to add to !buy, NULL if nothing
+    input_prompt TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT now() NOT NULL
+);
+
+CREATE TRIGGER update_shop_items_modtime BEFORE
+UPDATE ON shop_items FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
+
+CREATE TABLE redemptions (
+    id SERIAL PRIMARY KEY,
+    item_id INTEGER NOT NULL,
+    twitch_id VARCHAR(255) NOT NULL,
+    username VARCHAR(255) NOT NULL,
+    input TEXT,
+    cost BIGINT NOT NULL,
+    status VARCHAR(255) NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    FOREIGN KEY (item_id) REFERENCES shop_items (id)
+);
+
+CREATE INDEX redemptions_status_idx ON redemptions (status);
+
+CREATE TRIGGER update_redemptions_modtime BEFORE
+UPDATE ON redemptions FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
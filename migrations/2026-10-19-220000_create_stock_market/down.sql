@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+-- stocks and orders may have been made by hand before this migration, so they are
+-- kept along with their rows and only lose the triggers it added.
+DROP TRIGGER IF EXISTS update_holdings_modtime ON holdings;
+
+DROP TABLE IF EXISTS holdings;
+
+DROP TRIGGER IF EXISTS update_orders_modtime ON orders;
+
+DROP TRIGGER IF EXISTS update_stocks_modtime ON stocks;
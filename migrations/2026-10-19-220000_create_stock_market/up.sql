@@ -0,0 +1,56 @@
+-- Your SQL goes here
+-- stocks and orders were made by hand on the original database before there were
+-- migrations for them, so leave them alone where they already exist.
+CREATE TABLE IF NOT EXISTS stocks (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    symbol VARCHAR(255) NOT NULL UNIQUE,
+    ticket_price NUMERIC(12, 2) NOT NULL,
+    future_value NUMERIC(12, 2) NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    updated_at TIMESTAMP DEFAULT now() NOT NULL,
+    roi_percentage NUMERIC(8, 2) NOT NULL DEFAULT 0
+);
+
+DROP TRIGGER IF EXISTS update_stocks_modtime ON stocks;
+
+CREATE TRIGGER update_stocks_modtime BEFORE
+UPDATE ON stocks FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
+
+-- every trade made, positive shares for buys and negative for sells
+CREATE TABLE IF NOT EXISTS orders (
+    id SERIAL PRIMARY KEY,
+    stock_id INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    num_shares INTEGER NOT NULL,
+    strike_price NUMERIC(12, 2) NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    updated_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (stock_id) REFERENCES stocks (id),
+    FOREIGN KEY (owner_id) REFERENCES chatters (id)
+);
+
+DROP TRIGGER IF EXISTS update_orders_modtime ON orders;
+
+CREATE TRIGGER update_orders_modtime BEFORE
+UPDATE ON orders FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
+
+CREATE TABLE holdings (
+    id SERIAL PRIMARY KEY,
+    owner_id INTEGER NOT NULL,
+    stock_id INTEGER NOT NULL,
+    num_shares INTEGER NOT NULL CHECK (num_shares >= 0),
+    -- points paid for the shares still held
+    cost_basis BIGINT NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    updated_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (owner_id) REFERENCES chatters (id),
+    FOREIGN KEY (stock_id) REFERENCES stocks (id),
+    UNIQUE (owner_id, stock_id)
+);
+
+CREATE TRIGGER update_holdings_modtime BEFORE
+UPDATE ON holdings FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS trades_stock_id_created_at_idx;
+
+DROP TABLE IF EXISTS trades;
+
+DROP INDEX IF EXISTS orders_open_idx;
+
+ALTER TABLE orders
+DROP COLUMN IF EXISTS escrow,
+DROP COLUMN IF EXISTS remaining_shares,
+DROP COLUMN IF EXISTS status;
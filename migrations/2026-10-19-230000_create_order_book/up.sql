@@ -0,0 +1,33 @@
+-- Your SQL goes here
+-- limit orders rest on the book until they're filled or cancelled, market orders
+-- from before the book are filled straight away
+ALTER TABLE orders
+ADD COLUMN status VARCHAR(255) NOT NULL DEFAULT 'filled',
+ADD COLUMN remaining_shares INTEGER NOT NULL DEFAULT 0 CHECK (remaining_shares >= 0),
+-- points held back for the rest of a buy order
+ADD COLUMN escrow BIGINT NOT NULL DEFAULT 0 CHECK (escrow >= 0);
+
+CREATE INDEX orders_open_idx ON orders (stock_id)
+WHERE
+    status = 'open';
+
+CREATE TABLE trades (
+    id SERIAL PRIMARY KEY,
+    stock_id INTEGER NOT NULL,
+    buy_order_id INTEGER NOT NULL,
+    sell_order_id INTEGER NOT NULL,
+    buyer_id INTEGER NOT NULL,
+    seller_id INTEGER NOT NULL,
+    num_shares INTEGER NOT NULL CHECK (num_shares > 0),
+    price NUMERIC(12, 2) NOT NULL,
+    -- points the buyer paid the seller
+    value BIGINT NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (stock_id) REFERENCES stocks (id),
+    FOREIGN KEY (buy_order_id) REFERENCES orders (id),
+    FOREIGN KEY (sell_order_id) REFERENCES orders (id),
+    FOREIGN KEY (buyer_id) REFERENCES chatters (id),
+    FOREIGN KEY (seller_id) REFERENCES chatters (id)
+);
+
+CREATE INDEX trades_stock_id_created_at_idx ON trades (stock_id, created_at);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS stock_prices_stock_id_created_at_idx;
+
+DROP TABLE IF EXISTS stock_prices;
+
+DROP INDEX IF EXISTS market_events_pending_idx;
+
+DROP TABLE IF EXISTS market_events;
@@ -0,0 +1,28 @@
+-- Your SQL goes here
+-- what happened on stream since the last tick, the next tick moves prices for it
+CREATE TABLE market_events (
+    id SERIAL PRIMARY KEY,
+    stock_id INTEGER NOT NULL,
+    -- duel, gamble, pump or dump
+    kind VARCHAR(255) NOT NULL,
+    -- points wagered for duels, house profit for gambles, percent for pumps and dumps
+    amount BIGINT NOT NULL,
+    applied_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (stock_id) REFERENCES stocks (id)
+);
+
+CREATE INDEX market_events_pending_idx ON market_events (stock_id)
+WHERE
+    applied_at IS NULL;
+
+-- the price of every stock after every tick
+CREATE TABLE stock_prices (
+    id SERIAL PRIMARY KEY,
+    stock_id INTEGER NOT NULL,
+    price NUMERIC(12, 2) NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (stock_id) REFERENCES stocks (id)
+);
+
+CREATE INDEX stock_prices_stock_id_created_at_idx ON stock_prices (stock_id, created_at);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS update_blackjack_hands_modtime ON blackjack_hands;
+
+DROP INDEX IF EXISTS blackjack_hands_playing_idx;
+
+DROP TABLE IF EXISTS blackjack_hands;
@@ -0,0 +1,29 @@
+-- Your SQL goes here
+-- blackjack hands, saved after every move so a restart doesn't lose the wager
+CREATE TABLE blackjack_hands (
+    id SERIAL PRIMARY KEY,
+    chatter_id INTEGER NOT NULL,
+    -- held back from the chatter's points until the hand is over, doubled included
+    wager BIGINT NOT NULL CHECK (wager > 0),
+    -- cards are numbers from 0 to 51, separated by commas
+    player_cards VARCHAR(255) NOT NULL,
+    dealer_cards VARCHAR(255) NOT NULL,
+    -- what's left of the shuffled deck, the next card last
+    deck VARCHAR(255) NOT NULL,
+    doubled BOOLEAN NOT NULL DEFAULT false,
+    -- playing, blackjack, won, push or lost
+    status VARCHAR(255) NOT NULL DEFAULT 'playing',
+    payout BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    updated_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (chatter_id) REFERENCES chatters (id)
+);
+
+-- one hand at a time per chatter
+CREATE UNIQUE INDEX blackjack_hands_playing_idx ON blackjack_hands (chatter_id)
+WHERE
+    status = 'playing';
+
+CREATE TRIGGER update_blackjack_hands_modtime BEFORE
+UPDATE ON blackjack_hands FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
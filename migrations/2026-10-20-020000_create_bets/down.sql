@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE chatters
+DROP COLUMN IF EXISTS client_seed;
+
+DROP INDEX IF EXISTS bets_chatter_id_idx;
+
+DROP TABLE IF EXISTS bets;
+
+DROP INDEX IF EXISTS casino_seeds_active_idx;
+
+DROP TABLE IF EXISTS casino_seeds;
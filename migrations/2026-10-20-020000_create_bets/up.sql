@@ -0,0 +1,40 @@
+-- Your SQL goes here
+-- server seeds for the casino. Only the hash is shown until the seed is rotated out,
+-- then the seed itself is revealed so every bet made with it can be checked
+CREATE TABLE casino_seeds (
+    id SERIAL PRIMARY KEY,
+    seed VARCHAR(255) NOT NULL,
+    seed_hash VARCHAR(255) NOT NULL,
+    -- bets made with the seed so far, the next bet's nonce
+    nonce INTEGER NOT NULL DEFAULT 0,
+    revealed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT now() NOT NULL
+);
+
+-- one seed in use at a time
+CREATE UNIQUE INDEX casino_seeds_active_idx ON casino_seeds ((revealed_at IS NULL))
+WHERE
+    revealed_at IS NULL;
+
+CREATE TABLE bets (
+    id SERIAL PRIMARY KEY,
+    chatter_id INTEGER NOT NULL,
+    game VARCHAR(255) NOT NULL,
+    wager BIGINT NOT NULL,
+    -- what came after the wager, e.g. red for roulette
+    bet VARCHAR(255) NOT NULL DEFAULT '',
+    seed_id INTEGER NOT NULL,
+    client_seed VARCHAR(255) NOT NULL,
+    nonce INTEGER NOT NULL,
+    payout BIGINT NOT NULL,
+    result TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (chatter_id) REFERENCES chatters (id),
+    FOREIGN KEY (seed_id) REFERENCES casino_seeds (id)
+);
+
+CREATE INDEX bets_chatter_id_idx ON bets (chatter_id);
+
+-- mixed into every bet so the bot can't pick outcomes on its own, set with !seed
+ALTER TABLE chatters
+ADD COLUMN client_seed VARCHAR(255);
@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS bets_created_at_idx;
+
+DELETE FROM bets
+WHERE
+    seed_id IS NULL;
+
+ALTER TABLE bets
+ALTER COLUMN seed_id SET NOT NULL,
+ALTER COLUMN client_seed SET NOT NULL,
+ALTER COLUMN nonce SET NOT NULL;
+
+ALTER TABLE bets
+DROP COLUMN IF EXISTS broadcaster_cut,
+DROP COLUMN IF EXISTS pool_cut;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- where the points a bet lost went
+ALTER TABLE bets
+ADD COLUMN pool_cut BIGINT NOT NULL DEFAULT 0,
+ADD COLUMN broadcaster_cut BIGINT NOT NULL DEFAULT 0;
+
+-- blackjack hands are recorded as bets too, they aren't drawn from the seeds
+ALTER TABLE bets
+ALTER COLUMN seed_id DROP NOT NULL,
+ALTER COLUMN client_seed DROP NOT NULL,
+ALTER COLUMN nonce DROP NOT NULL;
+
+CREATE INDEX bets_created_at_idx ON bets (created_at);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS bets_chatter_id_created_at_idx;
+
+DROP TRIGGER IF EXISTS update_gambling_limits_modtime ON gambling_limits;
+
+DROP TABLE IF EXISTS gambling_limits;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+-- a chatter's self-exclusion and the limits mods set for them, NULL limits mean the
+-- casino's defaults apply
+CREATE TABLE gambling_limits (
+    chatter_id INTEGER PRIMARY KEY,
+    max_wager BIGINT CHECK (max_wager > 0),
+    session_loss_limit BIGINT CHECK (session_loss_limit > 0),
+    daily_loss_limit BIGINT CHECK (daily_loss_limit > 0),
+    excluded_until TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT now() NOT NULL,
+    FOREIGN KEY (chatter_id) REFERENCES chatters (id)
+);
+
+CREATE TRIGGER update_gambling_limits_modtime BEFORE
+UPDATE ON gambling_limits FOR EACH ROW
+EXECUTE FUNCTION update_modified_column ();
+
+CREATE INDEX bets_chatter_id_created_at_idx ON bets (chatter_id, created_at);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS pool_tickets;
+
+ALTER TABLE losers_pool DROP COLUMN IF EXISTS cancelled_at;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+ALTER TABLE losers_pool ADD COLUMN cancelled_at TIMESTAMPTZ;
+
+CREATE TABLE pool_tickets (
+  id SERIAL PRIMARY KEY,
+  pool_id INTEGER NOT NULL REFERENCES losers_pool (id),
+  chatter_id INTEGER NOT NULL REFERENCES chatters (id),
+  tickets BIGINT NOT NULL CHECK (tickets > 0),
+  points BIGINT NOT NULL,
+  refunded BOOLEAN NOT NULL DEFAULT false,
+  created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX pool_tickets_pool_id_idx ON pool_tickets (pool_id);
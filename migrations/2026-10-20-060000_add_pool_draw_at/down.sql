@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE losers_pool DROP COLUMN IF EXISTS draw_at;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE losers_pool ADD COLUMN draw_at TIMESTAMPTZ;
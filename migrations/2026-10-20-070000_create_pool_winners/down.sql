@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS pool_winners;
+
+ALTER TABLE losers_pool DROP COLUMN IF EXISTS broadcaster_cut;
+ALTER TABLE losers_pool DROP COLUMN IF EXISTS rollover;
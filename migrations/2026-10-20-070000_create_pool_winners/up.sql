@@ -0,0 +1,20 @@
+-- Your SQL goes here
+ALTER TABLE losers_pool ADD COLUMN rollover BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE losers_pool ADD COLUMN broadcaster_cut BIGINT NOT NULL DEFAULT 0;
+
+CREATE TABLE pool_winners (
+  id SERIAL PRIMARY KEY,
+  pool_id INTEGER NOT NULL REFERENCES losers_pool (id),
+  chatter_id INTEGER NOT NULL REFERENCES chatters (id),
+  place INTEGER NOT NULL CHECK (place > 0),
+  amount BIGINT NOT NULL,
+  tickets BIGINT NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+  UNIQUE (pool_id, place)
+);
+
+-- winners of pools drawn before there could be more than one
+INSERT INTO pool_winners (pool_id, chatter_id, place, amount, tickets, created_at)
+SELECT losers_pool.id, winner, 1, amount, 0, losers_pool.updated_at
+FROM losers_pool
+JOIN chatters ON chatters.id = losers_pool.winner;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE duels DROP COLUMN IF EXISTS alternate_answers;
+
+ALTER TABLE duels DROP COLUMN IF EXISTS options;
+
+ALTER TABLE duels DROP COLUMN IF EXISTS question_kind;
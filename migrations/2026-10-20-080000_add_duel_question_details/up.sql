@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- what the duelists were asked, so guesses are judged against it even if the
+-- question or the word list changes while the duel is running
+ALTER TABLE duels ADD COLUMN question_kind VARCHAR(255);
+
+ALTER TABLE duels ADD COLUMN options TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE duels ADD COLUMN alternate_answers TEXT[] NOT NULL DEFAULT '{}';
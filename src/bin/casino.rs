@@ -0,0 +1,173 @@
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use duel_bot::casino::stats::{self, Totals};
+use duel_bot::casino::Casino;
+use duel_bot::db;
+
+#[derive(Parser)]
+#[command(about = "Reports on what the casino games won and lost, and simulates payout tables")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Totals per game, with the return gamblers got next to what the game expects
+    Games {
+        /// Only count bets from the last that many days
+        #[arg(long)]
+        days: Option<i64>,
+    },
+    /// What every chatter won or lost, biggest winners first
+    Players {
+        #[arg(long)]
+        days: Option<i64>,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// What the house made per day
+    Profit {
+        #[arg(long, default_value_t = 14)]
+        days: i64,
+    },
+    /// Play a game many times to check its payouts, e.g.
+    /// `simulate dice --payouts "2=0,3-6=0.5,12=5"` to try a table before setting
+    /// CASINO_DICE_PAYOUTS. Blackjack hands hit until 17 like the dealer
+    Simulate {
+        game: String,
+        /// What comes after the wager in !gamble, e.g. red for roulette
+        bet: Vec<String>,
+        #[arg(long, default_value_t = 1_000_000)]
+        plays: u64,
+        #[arg(long, default_value_t = 100)]
+        wager: i64,
+        /// Payouts to try instead of the game's, in CASINO_<GAME>_PAYOUTS format
+        #[arg(long)]
+        payouts: Option<String>,
+        /// Defaults to a random seed
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+}
+
+fn print_totals(name: &str, totals: &Totals, expected: Option<f64>) {
+    println!(
+        "{:<16} {:>7} bets {:>10} wagered {:>10} paid {:>+10} house {:>9} pool {:>9} broadcaster {:>7} returned{}",
+        name,
+        totals.bets,
+        totals.wagered,
+        totals.paid,
+        totals.house_profit(),
+        totals.pool,
+        totals.broadcaster,
+        totals
+            .observed_return()
+            .map_or("-".to_string(), |r| format!("{:.2}%", r * 100.0)),
+        expected.map_or(String::new(), |e| format!(" ({:.2}% expected)", e * 100.0))
+    );
+}
+
+fn main() {
+    dotenv().ok();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Games { days } => {
+            let casino = Casino::from_env();
+            let mut total = Totals::default();
+            for (name, totals) in db::get_bet_totals_by_game(stats::since(days)) {
+                let expected = casino.expected_return(&name);
+                print_totals(&name, &totals, expected);
+                total.merge(&totals);
+            }
+            print_totals("all games", &total, None);
+        }
+        Command::Players { days, limit } => {
+            for (name, totals) in db::get_bet_totals_by_player(stats::since(days), limit) {
+                print_totals(&name, &totals, None);
+            }
+        }
+        Command::Profit { days } => {
+            let bets = db::get_bets_since(stats::since(Some(days)));
+            let mut total = Totals::default();
+            for (day, totals) in stats::by_day(&bets) {
+                print_totals(&day.to_string(), &totals, None);
+                total.merge(&totals);
+            }
+            print_totals("total", &total, None);
+        }
+        Command::Simulate {
+            game,
+            bet,
+            plays,
+            wager,
+            payouts,
+            seed,
+        } => {
+            if let Some(payouts) = payouts {
+                std::env::set_var(format!("CASINO_{}_PAYOUTS", game.to_uppercase()), payouts);
+            }
+            let casino = Casino::from_env();
+            let bet = bet.iter().map(String::as_str).collect::<Vec<&str>>();
+            let mut rng = StdRng::seed_from_u64(seed.unwrap_or_else(rand::random));
+            let (name, simulation) = if game.eq_ignore_ascii_case("blackjack") {
+                let simulation =
+                    stats::simulate_blackjack(&casino.blackjack, wager, plays.max(1), &mut rng);
+                ("blackjack", simulation)
+            } else {
+                match casino.game(&game) {
+                    Some(game) => (
+                        game.name(),
+                        stats::simulate(game, wager, &bet, plays.max(1), &mut rng),
+                    ),
+                    None => {
+                        eprintln!(
+                            "No such game, there's {} and blackjack",
+                            casino
+                                .games()
+                                .map(|game| game.name())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                        std::process::exit(1);
+                    }
+                }
+            };
+            let simulation = match simulation {
+                Ok(simulation) => simulation,
+                Err(err) => {
+                    eprintln!("Couldn't play {}: {}", name, err);
+                    std::process::exit(1);
+                }
+            };
+            let expected = casino
+                .expected_return(name)
+                .expect("every game has an expected return");
+            println!(
+                "{} plays of {} at {} points: {} wagered, {} paid, biggest payout {}",
+                simulation.plays,
+                name,
+                wager,
+                simulation.wagered,
+                simulation.paid,
+                simulation.biggest_payout
+            );
+            println!(
+                "returned {:.3}% ± {:.3}%, expected {:.3}% (house edge {:.3}%)",
+                simulation.observed_return * 100.0,
+                simulation.standard_error * 200.0,
+                expected * 100.0,
+                (1.0 - expected) * 100.0
+            );
+            let off = (simulation.observed_return - expected).abs();
+            if off > 3.0 * simulation.standard_error {
+                println!("the payouts don't match the expected return, check the table");
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use dotenv::dotenv;
+
+use duel_bot::difficulty::Difficulty;
+use duel_bot::generator::{self, Topic};
+use duel_bot::opentdb;
+use duel_bot::question_pack::{self, PackFormat, PackQuestion};
+
+#[derive(Parser)]
+#[command(
+    about = "Import and export question packs in JSON or CSV, or import Open Trivia DB dumps"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+impl From<Format> for PackFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Json => PackFormat::Json,
+            Format::Csv => PackFormat::Csv,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Add the questions in one or more packs, skipping ones that already exist
+    Import {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Pack format, guessed from the file extension if not given
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// Twitch id to record as the submitter, defaults to BROADCASTER_ID
+        #[arg(long)]
+        submitter: Option<i32>,
+        /// Report what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Add questions from files downloaded from the Open Trivia DB api
+    ImportOpentdb {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Put every question in this category instead of mapping Open Trivia DB's
+        #[arg(long)]
+        category: Option<String>,
+        /// Twitch id to record as the submitter, defaults to BROADCASTER_ID
+        #[arg(long)]
+        submitter: Option<i32>,
+        /// Report what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print a generated question. To replay a duel's question pass the duel's
+    /// topic, difficulty and id as the seed
+    Generate {
+        topic: Topic,
+        #[arg(long)]
+        difficulty: Option<Difficulty>,
+        /// Defaults to a random seed
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Write every approved question to a pack
+    Export {
+        file: PathBuf,
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// Only export questions in this category
+        #[arg(long)]
+        category: Option<String>,
+    },
+}
+
+fn pack_format(path: &Path, format: Option<Format>) -> PackFormat {
+    match format.map(PackFormat::from).or(PackFormat::from_path(path)) {
+        Some(format) => format,
+        None => {
+            eprintln!(
+                "Can't tell the format of {}, pass --format json or --format csv",
+                path.display()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn submitter_id(submitter: Option<i32>) -> i32 {
+    submitter.unwrap_or_else(|| {
+        std::env::var("BROADCASTER_ID")
+            .expect("BROADCASTER_ID not set, pass --submitter")
+            .parse()
+            .expect("BROADCASTER_ID must be a number")
+    })
+}
+
+// Imports every file, returning false if any of them couldn't be read or parsed.
+fn import_files(
+    files: &[PathBuf],
+    submitter: i32,
+    dry_run: bool,
+    parse: impl Fn(&Path, &str) -> anyhow::Result<Vec<PackQuestion>>,
+) -> bool {
+    let mut ok = true;
+    for file in files {
+        let contents = match std::fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("Couldn't read {}: {}", file.display(), err);
+                ok = false;
+                continue;
+            }
+        };
+        let questions = match parse(file, &contents) {
+            Ok(questions) => questions,
+            Err(err) => {
+                eprintln!("Couldn't parse {}: {}", file.display(), err);
+                ok = false;
+                continue;
+            }
+        };
+
+        let report = question_pack::import(&questions, submitter, dry_run);
+        println!(
+            "{}: {} imported, {} already present, {} conflicting, {} invalid{}",
+            file.display(),
+            report.imported,
+            report.unchanged,
+            report.conflicts.len(),
+            report.invalid.len(),
+            if dry_run { " (dry run)" } else { "" }
+        );
+        for category in &report.categories_created {
+            println!("  new category: {}", category);
+        }
+        for conflict in &report.conflicts {
+            println!("  conflict: {}", conflict);
+        }
+        for (entry, reason) in &report.invalid {
+            println!("  entry {} skipped: {}", entry, reason);
+        }
+    }
+    ok
+}
+
+fn main() {
+    dotenv().ok();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Import {
+            files,
+            format,
+            submitter,
+            dry_run,
+        } => {
+            let ok = import_files(
+                &files,
+                submitter_id(submitter),
+                dry_run,
+                |file, contents| question_pack::parse(contents, pack_format(file, format)),
+            );
+            if !ok {
+                std::process::exit(1);
+            }
+        }
+        Command::ImportOpentdb {
+            files,
+            category,
+            submitter,
+            dry_run,
+        } => {
+            let ok = import_files(&files, submitter_id(submitter), dry_run, |_, contents| {
+                Ok(opentdb::parse(contents)?
+                    .iter()
+                    .map(|q| opentdb::to_pack_question(q, category.as_deref()))
+                    .collect())
+            });
+            if !ok {
+                std::process::exit(1);
+            }
+        }
+        Command::Generate {
+            topic,
+            difficulty,
+            seed,
+        } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let question = generator::generate(topic, difficulty, seed);
+            println!(
+                "{} – {} ({}, seed {})",
+                question.difficulty, question.category, question.kind, seed
+            );
+            println!("Q: {}", question.prompt(seed));
+            println!("A: {}", question.answer);
+        }
+        Command::Export {
+            file,
+            format,
+            category,
+        } => {
+            let questions = question_pack::export()
+                .into_iter()
+                .filter(|q| match &category {
+                    Some(category) => q.category.eq_ignore_ascii_case(category.trim()),
+                    None => true,
+                })
+                .collect::<Vec<_>>();
+            let contents = question_pack::write(&questions, pack_format(&file, format))
+                .expect("Error writing question pack");
+            std::fs::write(&file, contents).expect("Error writing export file");
+            println!(
+                "Exported {} question(s) to {}",
+                questions.len(),
+                file.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+
+use duel_bot::db;
+use duel_bot::models::Question;
+
+#[derive(Parser)]
+#[command(about = "Review questions submitted by chatters with !addquestion")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every question waiting for review
+    List,
+    /// Approve a pending question so it can be asked in duels
+    Approve { id: i32 },
+    /// Reject a pending question and refund its submitter
+    Reject {
+        id: i32,
+        #[arg(required = true)]
+        reason: Vec<String>,
+    },
+}
+
+fn pending_question(id: i32) -> Question {
+    match db::get_question(id) {
+        Some(question) if question.is_pending() => question,
+        Some(question) => {
+            eprintln!("Question {} is already {}", id, question.status);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("No question with id {}", id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    dotenv().ok();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::List => {
+            let pending = db::get_pending_questions();
+            println!("{} question(s) waiting for review", pending.len());
+            for question in pending {
+                println!("-----------");
+                println!(
+                    "#{} (category {}, submitted by {})",
+                    question.id, question.category_id, question.submitter_id
+                );
+                println!("Q: {}", question.question);
+                println!("A: {}", question.answer);
+            }
+        }
+        Command::Approve { id } => {
+            let mut question = pending_question(id);
+            if !question.approve() {
+                eprintln!("Question {} was already reviewed", question.id);
+                std::process::exit(1);
+            }
+            println!("Approved question {}", question.id);
+        }
+        Command::Reject { id, reason } => {
+            let mut question = pending_question(id);
+            if !question.reject(&reason.join(" ")) {
+                eprintln!("Question {} was already reviewed", question.id);
+                std::process::exit(1);
+            }
+            println!(
+                "Rejected question {}, refunded {} points to {}",
+                question.id, question.points_paid, question.submitter_id
+            );
+        }
+    }
+}
@@ -0,0 +1,430 @@
+// Blackjack against the bot, one deck shuffled per hand. Unlike the !gamble games a
+// hand takes several messages, so every move is saved and the wager is held back
+// until the hand is over. The dealer draws to 16 and stands on all 17s, naturals pay
+// 3 to 2 and a hand nobody moves on for BLACKJACK_TIMEOUT_SECONDS stands on its own.
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
+use crate::casino::{pay, stats, Casino, PayoutTable, Play};
+use crate::db;
+use crate::models::{BlackjackHand, BlackjackStatus};
+
+pub const PAYOUTS: &[(&str, f64)] = &[("blackjack", 2.5), ("win", 2.0), ("push", 1.0)];
+
+// How often a hand played to 17 ends each way that pays, from 200 million simulated
+// hands. The rest are lost.
+const ODDS_TO_17: &[(&str, f64)] = &[
+    ("blackjack", 0.046484),
+    ("win", 0.366044),
+    ("push", 0.094862),
+];
+
+const RANKS: [&str; 13] = [
+    "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K", "A",
+];
+const SUITS: [&str; 4] = ["♠", "♥", "♦", "♣"];
+
+pub fn card_name(card: u8) -> String {
+    format!("{}{}", RANKS[card as usize / 4], SUITS[card as usize % 4])
+}
+
+fn card_value(card: u8) -> u8 {
+    match card / 4 {
+        rank @ 0..=8 => rank + 2,
+        12 => 11,
+        _ => 10,
+    }
+}
+
+// (total, whether an ace is still counted as 11)
+pub fn hand_value(cards: &[u8]) -> (u8, bool) {
+    let mut total = cards.iter().map(|card| card_value(*card)).sum::<u8>();
+    let mut aces = cards.iter().filter(|card| *card / 4 == 12).count();
+    while total > 21 && aces > 0 {
+        total -= 10;
+        aces -= 1;
+    }
+    (total, aces > 0)
+}
+
+pub fn is_blackjack(cards: &[u8]) -> bool {
+    cards.len() == 2 && hand_value(cards).0 == 21
+}
+
+pub fn show(cards: &[u8]) -> String {
+    let names = cards
+        .iter()
+        .map(|card| card_name(*card))
+        .collect::<Vec<_>>();
+    format!("{} ({})", names.join(" "), hand_value(cards).0)
+}
+
+fn encode(cards: &[u8]) -> String {
+    cards
+        .iter()
+        .map(|card| card.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn decode(cards: &str) -> Vec<u8> {
+    cards
+        .split(',')
+        .filter_map(|card| card.parse().ok())
+        .filter(|card| *card < 52)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Hit,
+    Stand,
+    Double,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hand {
+    pub player: Vec<u8>,
+    pub dealer: Vec<u8>,
+    // the next card is the last one
+    pub deck: Vec<u8>,
+    pub wager: i64,
+    pub doubled: bool,
+}
+
+impl Hand {
+    // Shuffles a deck and deals two cards each. The status is only not Playing when
+    // someone was dealt a natural.
+    pub fn deal(wager: i64, rng: &mut dyn RngCore) -> (Hand, BlackjackStatus) {
+        let mut deck = (0..52).collect::<Vec<u8>>();
+        deck.shuffle(rng);
+        let mut hand = Hand {
+            player: vec![],
+            dealer: vec![],
+            deck,
+            wager,
+            doubled: false,
+        };
+        for _ in 0..2 {
+            let card = hand.draw();
+            hand.player.push(card);
+            let card = hand.draw();
+            hand.dealer.push(card);
+        }
+        let status = match (is_blackjack(&hand.player), is_blackjack(&hand.dealer)) {
+            (true, true) => BlackjackStatus::Push,
+            (true, false) => BlackjackStatus::Blackjack,
+            (false, true) => BlackjackStatus::Lost,
+            (false, false) => BlackjackStatus::Playing,
+        };
+        (hand, status)
+    }
+
+    pub fn from_saved(saved: &BlackjackHand) -> Hand {
+        Hand {
+            player: decode(&saved.player_cards),
+            dealer: decode(&saved.dealer_cards),
+            deck: decode(&saved.deck),
+            wager: saved.wager,
+            doubled: saved.doubled,
+        }
+    }
+
+    // (player cards, dealer cards, deck) the way they're saved
+    pub fn encode(&self) -> (String, String, String) {
+        (
+            encode(&self.player),
+            encode(&self.dealer),
+            encode(&self.deck),
+        )
+    }
+
+    pub fn play(&mut self, next: Move) -> Result<BlackjackStatus> {
+        match next {
+            Move::Hit => Ok(self.hit()),
+            Move::Stand => Ok(self.stand()),
+            Move::Double => self.double(),
+        }
+    }
+
+    fn draw(&mut self) -> u8 {
+        // a hand can't get through a whole deck, 11 cards make at least 21 already
+        self.deck.pop().expect("the deck should never run out")
+    }
+
+    pub fn hit(&mut self) -> BlackjackStatus {
+        let card = self.draw();
+        self.player.push(card);
+        match hand_value(&self.player).0 {
+            22.. => BlackjackStatus::Lost,
+            21 => self.stand(),
+            _ => BlackjackStatus::Playing,
+        }
+    }
+
+    // The dealer draws to 16 and stands on all 17s.
+    pub fn stand(&mut self) -> BlackjackStatus {
+        let player = hand_value(&self.player).0;
+        if player > 21 {
+            return BlackjackStatus::Lost;
+        }
+        while hand_value(&self.dealer).0 < 17 {
+            let card = self.draw();
+            self.dealer.push(card);
+        }
+        let dealer = hand_value(&self.dealer).0;
+        if dealer > 21 || player > dealer {
+            BlackjackStatus::Won
+        } else if player == dealer {
+            BlackjackStatus::Push
+        } else {
+            BlackjackStatus::Lost
+        }
+    }
+
+    // Doubles the wager for exactly one more card. Only on the first two cards.
+    pub fn double(&mut self) -> Result<BlackjackStatus> {
+        if self.player.len() != 2 || self.doubled {
+            return Err(anyhow!("you can only double on your first two cards"));
+        }
+        self.wager *= 2;
+        self.doubled = true;
+        let card = self.draw();
+        self.player.push(card);
+        Ok(self.stand())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Blackjack {
+    pub payouts: PayoutTable,
+    pub timeout: Duration,
+}
+
+impl Blackjack {
+    // CASINO_BLACKJACK_PAYOUTS and BLACKJACK_TIMEOUT_SECONDS (default 120).
+    pub fn from_env() -> Blackjack {
+        let timeout = std::env::var("BLACKJACK_TIMEOUT_SECONDS")
+            .map(|s| {
+                s.trim()
+                    .parse()
+                    .expect("BLACKJACK_TIMEOUT_SECONDS should be a number")
+            })
+            .unwrap_or(120);
+        Blackjack {
+            payouts: PayoutTable::from_env("blackjack", PAYOUTS),
+            timeout: Duration::from_secs(timeout),
+        }
+    }
+
+    // What a finished hand pays back, the wager included.
+    pub fn payout(&self, status: BlackjackStatus, wager: i64) -> i64 {
+        let multiplier = match status {
+            BlackjackStatus::Blackjack => self.payouts.get("blackjack"),
+            BlackjackStatus::Won => self.payouts.get("win"),
+            BlackjackStatus::Push => self.payouts.get("push"),
+            BlackjackStatus::Playing | BlackjackStatus::Lost => 0.0,
+        };
+        pay(wager, multiplier)
+    }
+
+    // The average fraction of a wager a hand played to 17 gets back. A player who
+    // plays better gets more.
+    pub fn expected_return(&self) -> f64 {
+        ODDS_TO_17
+            .iter()
+            .map(|(outcome, odds)| odds * self.payouts.get(outcome))
+            .sum()
+    }
+
+    // Plays a whole hand the simple way, hitting until 17 like the dealer, and returns
+    // what it paid. It's what the simulator plays, since blackjack's return depends
+    // on how the hands are played.
+    pub fn play_to_17(&self, wager: i64, rng: &mut dyn RngCore) -> i64 {
+        let (mut hand, mut status) = Hand::deal(wager, rng);
+        while status == BlackjackStatus::Playing {
+            status = if hand_value(&hand.player).0 < 17 {
+                hand.hit()
+            } else {
+                hand.stand()
+            };
+        }
+        self.payout(status, hand.wager)
+    }
+}
+
+// Splits what a finished hand lost and records it with the other bets.
+fn settle(casino: &Casino, saved: &BlackjackHand) {
+    let broadcaster_id = std::env::var("BROADCASTER_ID").expect("BROADCASTER_ID must be set");
+    let settlement = casino.settle_held(&broadcaster_id, saved.wager, saved.payout);
+    let play = Play {
+        payout: saved.payout,
+        result: saved.status.clone(),
+    };
+    let bet = if saved.doubled { "double" } else { "" };
+    stats::record_bet(
+        saved.chatter_id,
+        "blackjack",
+        saved.wager,
+        bet,
+        None,
+        &play,
+        &settlement,
+    );
+}
+
+// Deals a new hand and takes the wager. None if the chatter can't cover the wager or
+// already has a hand going.
+pub fn start(
+    casino: &Casino,
+    chatter_id: i32,
+    wager: i64,
+    rng: &mut dyn RngCore,
+) -> Option<(BlackjackHand, Hand)> {
+    let (hand, status) = Hand::deal(wager, rng);
+    let payout = casino.blackjack.payout(status, wager);
+    let saved = db::start_blackjack_hand(chatter_id, &hand, status, payout)?;
+    if !saved.is_playing() {
+        settle(casino, &saved);
+    }
+    Some((saved, hand))
+}
+
+// Saves a move and pays the hand out when it's over. None if the hand was already
+// moved or over, e.g. the timeout stood for the player first, or a double couldn't be
+// covered.
+pub fn save(
+    casino: &Casino,
+    saved: &BlackjackHand,
+    hand: &Hand,
+    status: BlackjackStatus,
+) -> Option<BlackjackHand> {
+    let payout = casino.blackjack.payout(status, hand.wager);
+    let extra_wager = hand.wager - saved.wager;
+    let saved = db::update_blackjack_hand(saved, hand, status, payout, extra_wager)?;
+    if !saved.is_playing() {
+        settle(casino, &saved);
+    }
+    Some(saved)
+}
+
+// Stands every hand that's been left alone for longer than the timeout.
+pub fn stand_stale_hands(casino: &Casino) -> anyhow::Result<()> {
+    for saved in db::get_stale_blackjack_hands(casino.blackjack.timeout)? {
+        let mut hand = Hand::from_saved(&saved);
+        let status = hand.stand();
+        if let Some(saved) = save(casino, &saved, &hand, status) {
+            println!(
+                "Stood blackjack hand {} after the timeout: {}",
+                saved.id, saved.status
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn run(casino: Casino) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(10));
+    loop {
+        ticker.tick().await;
+        if let Err(err) = stand_stale_hands(&casino) {
+            println!(
+                "An error occurred while standing stale blackjack hands: {}",
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cards by rank, the suit doesn't matter
+    fn cards(ranks: &[&str]) -> Vec<u8> {
+        ranks
+            .iter()
+            .map(|rank| RANKS.iter().position(|r| r == rank).unwrap() as u8 * 4)
+            .collect()
+    }
+
+    fn hand(player: &[&str], dealer: &[&str], next: &[&str]) -> Hand {
+        let mut deck = cards(next);
+        deck.reverse();
+        Hand {
+            player: cards(player),
+            dealer: cards(dealer),
+            deck,
+            wager: 100,
+            doubled: false,
+        }
+    }
+
+    #[test]
+    fn test_hand_value_counts_aces_as_one_when_it_has_to() {
+        assert_eq!((21, true), hand_value(&cards(&["A", "K"])));
+        assert_eq!((12, false), hand_value(&cards(&["A", "A", "K"])));
+        assert_eq!((13, true), hand_value(&cards(&["A", "A", "A"])));
+        assert_eq!((17, true), hand_value(&cards(&["A", "6"])));
+        assert_eq!((22, false), hand_value(&cards(&["K", "Q", "2"])));
+        assert!(is_blackjack(&cards(&["J", "A"])));
+        assert!(!is_blackjack(&cards(&["7", "7", "7"])));
+    }
+
+    #[test]
+    fn test_dealer_stands_on_soft_17() {
+        let mut soft = hand(&["10", "8"], &["A", "6"], &["5"]);
+        assert_eq!(BlackjackStatus::Won, soft.stand());
+        assert_eq!(2, soft.dealer.len());
+
+        let mut bust = hand(&["10", "8"], &["10", "6"], &["K"]);
+        assert_eq!(BlackjackStatus::Lost, bust.hit());
+        let mut draws = hand(&["10", "8"], &["10", "6"], &["K"]);
+        assert_eq!(BlackjackStatus::Won, draws.stand());
+        let mut push = hand(&["10", "7"], &["10", "5"], &["2"]);
+        assert_eq!(BlackjackStatus::Push, push.stand());
+    }
+
+    #[test]
+    fn test_hitting_to_21_stands() {
+        let mut hand = hand(&["10", "5"], &["10", "8"], &["6"]);
+        assert_eq!(BlackjackStatus::Won, hand.hit());
+        assert_eq!(21, hand_value(&hand.player).0);
+    }
+
+    #[test]
+    fn test_double() {
+        let mut doubled = hand(&["5", "6"], &["10", "7"], &["K"]);
+        assert_eq!(BlackjackStatus::Won, doubled.double().unwrap());
+        assert_eq!(200, doubled.wager);
+        assert!(doubled.double().is_err());
+
+        let mut three_cards = hand(&["2", "3", "4"], &["10", "7"], &["K"]);
+        assert!(three_cards.double().is_err());
+    }
+
+    #[test]
+    fn test_saved_hands_round_trip() {
+        let hand = hand(&["A", "K"], &["2", "3"], &["4", "5"]);
+        let (player, dealer, deck) = hand.encode();
+        assert_eq!(hand.player, decode(&player));
+        assert_eq!(hand.dealer, decode(&dealer));
+        assert_eq!(hand.deck, decode(&deck));
+        assert_eq!("A♠ K♠ (21)", show(&hand.player));
+    }
+
+    #[test]
+    fn test_payouts() {
+        let blackjack = Blackjack {
+            payouts: PayoutTable::new(PAYOUTS),
+            timeout: Duration::from_secs(120),
+        };
+        assert_eq!(250, blackjack.payout(BlackjackStatus::Blackjack, 100));
+        assert_eq!(200, blackjack.payout(BlackjackStatus::Won, 100));
+        assert_eq!(100, blackjack.payout(BlackjackStatus::Push, 100));
+        assert_eq!(0, blackjack.payout(BlackjackStatus::Lost, 100));
+    }
+}
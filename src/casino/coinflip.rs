@@ -0,0 +1,64 @@
+// Call heads or tails, a right call pays the "win" multiplier.
+use anyhow::{anyhow, Result};
+use rand::{Rng, RngCore};
+
+use crate::casino::{pay, CasinoGame, PayoutTable, Play};
+
+pub const PAYOUTS: &[(&str, f64)] = &[("win", 1.96)];
+
+#[derive(Debug, Clone)]
+pub struct Coinflip {
+    pub payouts: PayoutTable,
+}
+
+impl Coinflip {
+    pub fn from_env() -> Coinflip {
+        Coinflip {
+            payouts: PayoutTable::from_env("coinflip", PAYOUTS),
+        }
+    }
+}
+
+fn parse_call(bet: &[&str]) -> Result<&'static str> {
+    match bet.first().map(|b| b.to_lowercase()).as_deref() {
+        None | Some("heads") | Some("h") => Ok("heads"),
+        Some("tails") | Some("t") => Ok("tails"),
+        Some(other) => Err(anyhow!("{} isn't heads or tails", other)),
+    }
+}
+
+impl CasinoGame for Coinflip {
+    fn name(&self) -> &'static str {
+        "coinflip"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["coin", "flip"]
+    }
+
+    fn bet_usage(&self) -> Option<&'static str> {
+        Some("[heads|tails]")
+    }
+
+    fn check_bet(&self, bet: &[&str]) -> Result<()> {
+        parse_call(bet).map(|_| ())
+    }
+
+    fn play(&self, wager: i64, bet: &[&str], rng: &mut dyn RngCore) -> Result<Play> {
+        let call = parse_call(bet)?;
+        let landed = if rng.gen_bool(0.5) { "heads" } else { "tails" };
+        let multiplier = if landed == call {
+            self.payouts.get("win")
+        } else {
+            0.0
+        };
+        Ok(Play {
+            payout: pay(wager, multiplier),
+            result: format!("You called {} and it landed on {}!", call, landed),
+        })
+    }
+
+    fn expected_return(&self) -> f64 {
+        self.payouts.get("win") / 2.0
+    }
+}
@@ -0,0 +1,63 @@
+// Two dice, paid by their sum. The defaults are the payouts !gamble has always had.
+use anyhow::Result;
+use rand::{Rng, RngCore};
+
+use crate::casino::{pay, CasinoGame, PayoutTable, Play};
+
+pub const PAYOUTS: &[(&str, f64)] = &[
+    ("2", 0.0),
+    ("3", 0.5),
+    ("4", 0.5),
+    ("5", 0.5),
+    ("6", 0.5),
+    ("7", 0.75),
+    ("8", 2.0),
+    ("9", 3.0),
+    ("10", 3.0),
+    ("11", 3.0),
+    ("12", 5.0),
+];
+
+#[derive(Debug, Clone)]
+pub struct Dice {
+    pub payouts: PayoutTable,
+}
+
+impl Dice {
+    pub fn from_env() -> Dice {
+        Dice {
+            payouts: PayoutTable::from_env("dice", PAYOUTS),
+        }
+    }
+}
+
+impl CasinoGame for Dice {
+    fn name(&self) -> &'static str {
+        "dice"
+    }
+
+    fn play(&self, wager: i64, _bet: &[&str], rng: &mut dyn RngCore) -> Result<Play> {
+        let roll1 = rng.gen_range(1..7);
+        let roll2 = rng.gen_range(1..7);
+        let multiplier = self.payouts.get(&(roll1 + roll2).to_string());
+        let result = if roll1 + roll2 == 2 {
+            "Snake Eyes!".to_string()
+        } else {
+            format!("You rolled a {} and a {}!", roll1, roll2)
+        };
+        Ok(Play {
+            payout: pay(wager, multiplier),
+            result,
+        })
+    }
+
+    fn expected_return(&self) -> f64 {
+        let mut total = 0.0;
+        for roll1 in 1..7 {
+            for roll2 in 1..7 {
+                total += self.payouts.get(&(roll1 + roll2).to_string());
+            }
+        }
+        total / 36.0
+    }
+}
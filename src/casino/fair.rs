@@ -0,0 +1,268 @@
+// Provably fair randomness for !gamble. A bet's randomness is
+// HMAC-SHA256(server seed, "<client seed>:<nonce>:<round>") with the round counting
+// up for as many bytes as the game needs. Only the server seed's hash is shown while
+// it's in use, and the seed itself is revealed when a mod rotates it, so every bet can
+// be replayed with !verify: the hash shows the seed wasn't swapped afterwards and the
+// client seed, which chatters pick with !seed, that the bot couldn't pick outcomes.
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::casino::{CasinoGame, Play};
+use crate::db;
+use crate::models::{Bet, CasinoSeed, Chatter};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const MAX_CLIENT_SEED_LENGTH: usize = 64;
+
+pub fn new_server_seed(rng: &mut dyn RngCore) -> String {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    hex::encode(seed)
+}
+
+pub fn hash_seed(seed: &str) -> String {
+    hex::encode(Sha256::digest(seed.as_bytes()))
+}
+
+// Chatters who never set a client seed use their name.
+pub fn client_seed(chatter: &Chatter) -> String {
+    chatter
+        .client_seed
+        .clone()
+        .unwrap_or_else(|| chatter.username.to_lowercase())
+}
+
+// "rotate" is how mods rotate the server seed, so it can't be a client seed.
+pub fn valid_client_seed(seed: &str) -> bool {
+    !seed.is_empty()
+        && seed.len() <= MAX_CLIENT_SEED_LENGTH
+        && seed.chars().all(|c| c.is_ascii_graphic())
+        && !seed.eq_ignore_ascii_case("rotate")
+}
+
+#[derive(Debug, Clone)]
+pub struct FairRng {
+    mac: HmacSha256,
+    prefix: String,
+    round: u64,
+    block: [u8; 32],
+    used: usize,
+}
+
+impl FairRng {
+    pub fn new(server_seed: &str, client_seed: &str, nonce: i32) -> FairRng {
+        FairRng {
+            mac: HmacSha256::new_from_slice(server_seed.as_bytes())
+                .expect("HMAC takes keys of any length"),
+            prefix: format!("{}:{}", client_seed, nonce),
+            round: 0,
+            block: [0; 32],
+            // nothing drawn yet, the first byte asked for makes the first block
+            used: 32,
+        }
+    }
+
+    fn next_block(&mut self) {
+        let mut mac = self.mac.clone();
+        mac.update(format!("{}:{}", self.prefix, self.round).as_bytes());
+        self.block.copy_from_slice(&mac.finalize().into_bytes());
+        self.round += 1;
+        self.used = 0;
+    }
+}
+
+impl RngCore for FairRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.used == self.block.len() {
+                self.next_block();
+            }
+            *byte = self.block[self.used];
+            self.used += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// The seed bets are made with right now, started the first time it's needed.
+pub fn active_seed() -> CasinoSeed {
+    db::get_active_casino_seed().unwrap_or_else(|| {
+        let seed = new_server_seed(&mut rand::thread_rng());
+        db::create_casino_seed(&seed, &hash_seed(&seed))
+    })
+}
+
+// Reveals the seed in use and starts a new one. Returns the revealed seed.
+pub fn rotate_seed() -> Option<CasinoSeed> {
+    let seed = new_server_seed(&mut rand::thread_rng());
+    db::rotate_casino_seed(&seed, &hash_seed(&seed))
+}
+
+// The seeds a bet was drawn from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draw {
+    pub seed_id: i32,
+    pub client_seed: String,
+    pub nonce: i32,
+}
+
+// Plays a bet with the next nonce of the active seed. Bets the game won't take
+// don't use up a nonce.
+pub fn play(
+    game: &dyn CasinoGame,
+    chatter: &Chatter,
+    wager: i64,
+    bet: &[&str],
+) -> Result<(Play, Draw)> {
+    game.check_bet(bet)?;
+    let seed = active_seed();
+    let nonce = db::take_casino_nonce(seed.id).ok_or_else(|| anyhow!("the seed was rotated"))?;
+    let client_seed = client_seed(chatter);
+    let play = game.play(
+        wager,
+        bet,
+        &mut FairRng::new(&seed.seed, &client_seed, nonce),
+    )?;
+    let draw = Draw {
+        seed_id: seed.id,
+        client_seed,
+        nonce,
+    };
+    Ok((play, draw))
+}
+
+// Plays a recorded bet again with its seeds.
+pub fn replay(game: &dyn CasinoGame, bet: &Bet, seed: &CasinoSeed) -> Result<Play> {
+    let (client_seed, nonce) = match (&bet.client_seed, bet.nonce) {
+        (Some(client_seed), Some(nonce)) => (client_seed, nonce),
+        _ => return Err(anyhow!("bet #{} wasn't drawn from the seeds", bet.id)),
+    };
+    let args = bet.bet.split_whitespace().collect::<Vec<&str>>();
+    game.play(
+        bet.wager,
+        &args,
+        &mut FairRng::new(&seed.seed, client_seed, nonce),
+    )
+}
+
+// Whether a replayed bet matches the record. Only the draw is checked, the payout
+// comes from the payout table the bet was made under, which may have changed since.
+pub fn checks_out(play: &Play, bet: &Bet, seed: &CasinoSeed) -> bool {
+    hash_seed(&seed.seed) == seed.seed_hash && play.result == bet.result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::casino::Casino;
+    use rand::Rng;
+
+    #[test]
+    fn test_hash_seed() {
+        assert_eq!(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            hash_seed("")
+        );
+        assert_eq!(64, new_server_seed(&mut rand::thread_rng()).len());
+    }
+
+    #[test]
+    fn test_fair_rng_is_the_same_for_the_same_seeds() {
+        let draws = |client_seed: &str, nonce: i32| {
+            let mut rng = FairRng::new("server", client_seed, nonce);
+            (0..20)
+                .map(|_| rng.gen_range(0..1000))
+                .collect::<Vec<u32>>()
+        };
+        assert_eq!(draws("chatter", 1), draws("chatter", 1));
+        assert_ne!(draws("chatter", 1), draws("chatter", 2));
+        assert_ne!(draws("chatter", 1), draws("someone", 1));
+
+        // the first block is the HMAC of round 0
+        let mut mac = HmacSha256::new_from_slice(b"server").unwrap();
+        mac.update(b"chatter:1:0");
+        let mut bytes = [0u8; 40];
+        FairRng::new("server", "chatter", 1).fill_bytes(&mut bytes);
+        assert_eq!(&mac.finalize().into_bytes()[..], &bytes[..32]);
+    }
+
+    #[test]
+    fn test_replaying_a_bet_gives_the_same_result() {
+        let casino = Casino::from_env();
+        let game = casino.game("roulette").unwrap();
+        let seed = CasinoSeed {
+            id: 1,
+            seed: "server".to_string(),
+            seed_hash: hash_seed("server"),
+            nonce: 3,
+            revealed_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        let play = game
+            .play(100, &["red"], &mut FairRng::new("server", "chatter", 3))
+            .unwrap();
+        let bet = Bet {
+            id: 1,
+            chatter_id: 1,
+            game: "roulette".to_string(),
+            wager: 100,
+            bet: "red".to_string(),
+            seed_id: Some(1),
+            client_seed: Some("chatter".to_string()),
+            nonce: Some(3),
+            payout: play.payout,
+            result: play.result.clone(),
+            created_at: seed.created_at,
+            pool_cut: 0,
+            broadcaster_cut: 0,
+        };
+        let replayed = replay(game, &bet, &seed).unwrap();
+        assert_eq!(play, replayed);
+        assert!(checks_out(&replayed, &bet, &seed));
+
+        // paid under an older payout table
+        let old_table = Bet {
+            payout: play.payout * 2,
+            ..bet.clone()
+        };
+        assert!(checks_out(&replayed, &old_table, &seed));
+        let other_result = Bet {
+            result: "something else".to_string(),
+            ..bet.clone()
+        };
+        assert!(!checks_out(&replayed, &other_result, &seed));
+        let wrong_seed = CasinoSeed {
+            seed: "not the server seed".to_string(),
+            ..seed.clone()
+        };
+        assert!(!checks_out(&replayed, &bet, &wrong_seed));
+    }
+
+    #[test]
+    fn test_valid_client_seed() {
+        assert!(valid_client_seed("lucky7"));
+        assert!(!valid_client_seed(""));
+        assert!(!valid_client_seed("two words"));
+        assert!(!valid_client_seed(&"a".repeat(65)));
+        assert!(!valid_client_seed("Rotate"));
+    }
+}
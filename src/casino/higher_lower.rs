@@ -0,0 +1,111 @@
+// Call whether the second card off the deck beats the first, aces high and ties
+// losing. The payout is fair for the first card's odds, cut to "return" of that, and
+// when the first card makes the call impossible, like higher than an ace, the wager
+// is handed back.
+use anyhow::{anyhow, Result};
+use rand::{Rng, RngCore};
+
+use crate::casino::{pay, CasinoGame, PayoutTable, Play};
+
+pub const PAYOUTS: &[(&str, f64)] = &[("return", 0.95)];
+
+const RANKS: [&str; 13] = [
+    "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K", "A",
+];
+const SUITS: [&str; 4] = ["♠", "♥", "♦", "♣"];
+
+fn card_name(card: usize) -> String {
+    format!("{}{}", RANKS[card / 4], SUITS[card % 4])
+}
+
+// Chance the second card wins the call, after the first card came out.
+fn win_chance(first_rank: usize, higher: bool) -> f64 {
+    let winning_ranks = if higher {
+        RANKS.len() - 1 - first_rank
+    } else {
+        first_rank
+    };
+    (winning_ranks * 4) as f64 / 51.0
+}
+
+#[derive(Debug, Clone)]
+pub struct HigherLower {
+    pub payouts: PayoutTable,
+}
+
+impl HigherLower {
+    pub fn from_env() -> HigherLower {
+        HigherLower {
+            payouts: PayoutTable::from_env("higherlower", PAYOUTS),
+        }
+    }
+}
+
+// True for higher.
+fn parse_call(bet: &[&str]) -> Result<bool> {
+    match bet.first().map(|b| b.to_lowercase()).as_deref() {
+        Some("higher") | Some("hi") => Ok(true),
+        Some("lower") | Some("lo") => Ok(false),
+        _ => Err(anyhow!("call higher or lower")),
+    }
+}
+
+impl CasinoGame for HigherLower {
+    fn name(&self) -> &'static str {
+        "higherlower"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["hilo"]
+    }
+
+    fn bet_usage(&self) -> Option<&'static str> {
+        Some("<higher|lower>")
+    }
+
+    fn check_bet(&self, bet: &[&str]) -> Result<()> {
+        parse_call(bet).map(|_| ())
+    }
+
+    fn play(&self, wager: i64, bet: &[&str], rng: &mut dyn RngCore) -> Result<Play> {
+        let higher = parse_call(bet)?;
+        let first = rng.gen_range(0..52);
+        let mut second = rng.gen_range(0..51);
+        if second >= first {
+            second += 1;
+        }
+        let (first_rank, second_rank) = (first / 4, second / 4);
+        let result = format!("{} then {}!", card_name(first), card_name(second));
+
+        let chance = win_chance(first_rank, higher);
+        if chance == 0.0 {
+            return Ok(Play {
+                payout: wager,
+                result: format!(
+                    "{} Nothing can win that call, you get your wager back.",
+                    result
+                ),
+            });
+        }
+        let won = if higher {
+            second_rank > first_rank
+        } else {
+            second_rank < first_rank
+        };
+        let multiplier = if won {
+            self.payouts.get("return") / chance
+        } else {
+            0.0
+        };
+        Ok(Play {
+            payout: pay(wager, multiplier),
+            result,
+        })
+    }
+
+    fn expected_return(&self) -> f64 {
+        // the same for either call, the deck is symmetric
+        let impossible = 1.0 / RANKS.len() as f64;
+        self.payouts.get("return") * (1.0 - impossible) + impossible
+    }
+}
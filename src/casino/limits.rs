@@ -0,0 +1,315 @@
+// Responsible gambling. Every wager, in every game, is checked against the chatter's
+// self-exclusion, the most a single bet can be and how much they've lost this session
+// and over the last day. The defaults come from the environment and mods can override
+// them per chatter with !setlimit. A session is bets with no more than
+// CASINO_SESSION_GAP_MINUTES between them.
+use chrono::{Duration, NaiveDateTime};
+
+use crate::casino::WagerError;
+use crate::models::GamblingLimit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    Wager,
+    Session,
+    Daily,
+}
+
+impl LimitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitKind::Wager => "wager",
+            LimitKind::Session => "session",
+            LimitKind::Daily => "daily",
+        }
+    }
+}
+
+impl std::str::FromStr for LimitKind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wager" | "bet" => Ok(LimitKind::Wager),
+            "session" => Ok(LimitKind::Session),
+            "daily" | "day" => Ok(LimitKind::Daily),
+            _ => Err(anyhow::anyhow!("Unknown gambling limit {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_wager: Option<i64>,
+    pub session_loss: Option<i64>,
+    pub daily_loss: Option<i64>,
+    pub session_gap: Duration,
+}
+
+fn env_limit(name: &str) -> Option<i64> {
+    std::env::var(name).ok().map(|limit| {
+        limit
+            .trim()
+            .parse()
+            .ok()
+            .filter(|limit| *limit > 0)
+            .unwrap_or_else(|| panic!("{} should be a number", name))
+    })
+}
+
+impl Limits {
+    // CASINO_MAX_WAGER, CASINO_SESSION_LOSS_LIMIT and CASINO_DAILY_LOSS_LIMIT, all
+    // unlimited by default, and CASINO_SESSION_GAP_MINUTES (default 30).
+    pub fn from_env() -> Limits {
+        let session_gap = std::env::var("CASINO_SESSION_GAP_MINUTES")
+            .map(|m| {
+                m.trim()
+                    .parse()
+                    .expect("CASINO_SESSION_GAP_MINUTES should be a number")
+            })
+            .unwrap_or(30);
+        Limits {
+            max_wager: env_limit("CASINO_MAX_WAGER"),
+            session_loss: env_limit("CASINO_SESSION_LOSS_LIMIT"),
+            daily_loss: env_limit("CASINO_DAILY_LOSS_LIMIT"),
+            session_gap: Duration::minutes(session_gap),
+        }
+    }
+
+    // The limits with what mods set for the chatter.
+    pub fn for_chatter(&self, overrides: Option<&GamblingLimit>) -> Limits {
+        match overrides {
+            Some(overrides) => Limits {
+                max_wager: overrides.max_wager.or(self.max_wager),
+                session_loss: overrides.session_loss_limit.or(self.session_loss),
+                daily_loss: overrides.daily_loss_limit.or(self.daily_loss),
+                session_gap: self.session_gap,
+            },
+            None => *self,
+        }
+    }
+
+    pub fn get(&self, kind: LimitKind) -> Option<i64> {
+        match kind {
+            LimitKind::Wager => self.max_wager,
+            LimitKind::Session => self.session_loss,
+            LimitKind::Daily => self.daily_loss,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Losses {
+    pub session: i64,
+    pub day: i64,
+}
+
+// What a chatter lost, wins taken off, from (when, what they won) of their bets over
+// the last day, newest first.
+pub fn losses(bets: &[(NaiveDateTime, i64)], now: NaiveDateTime, gap: Duration) -> Losses {
+    let day = bets
+        .iter()
+        .filter(|(at, _)| now - *at < Duration::days(1))
+        .map(|(_, net)| net)
+        .sum::<i64>();
+    let mut session = 0;
+    let mut last = now;
+    for (at, net) in bets {
+        if last - *at > gap {
+            break;
+        }
+        session += net;
+        last = *at;
+    }
+    Losses {
+        session: (-session).max(0),
+        day: (-day).max(0),
+    }
+}
+
+// A blackjack hand that's still being played has its wager held back but isn't a
+// bet yet, so it's added as lost just now until it's settled.
+pub fn with_open_wager(
+    bets: &[(NaiveDateTime, i64)],
+    open_wager: Option<i64>,
+    now: NaiveDateTime,
+) -> Vec<(NaiveDateTime, i64)> {
+    open_wager
+        .map(|wager| (now, -wager))
+        .into_iter()
+        .chain(bets.iter().copied())
+        .collect()
+}
+
+// Whether a chatter may wager that much on top of what they've lost, so a limit is
+// never passed even if they lose the whole wager.
+pub fn check(
+    limits: &Limits,
+    losses: &Losses,
+    excluded_until: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+    wager: i64,
+) -> Result<(), WagerError> {
+    if let Some(until) = excluded_until.filter(|until| *until > now) {
+        return Err(WagerError::Excluded(until));
+    }
+    if let Some(max) = limits.max_wager.filter(|max| wager > *max) {
+        return Err(WagerError::TooBig(max));
+    }
+    if let Some(limit) = limits
+        .session_loss
+        .filter(|limit| losses.session + wager > *limit)
+    {
+        return Err(WagerError::SessionLossLimit {
+            limit,
+            lost: losses.session,
+        });
+    }
+    if let Some(limit) = limits
+        .daily_loss
+        .filter(|limit| losses.day + wager > *limit)
+    {
+        return Err(WagerError::DailyLossLimit {
+            limit,
+            lost: losses.day,
+        });
+    }
+    Ok(())
+}
+
+// The longest break !selfexclude takes, longer ones are cut down to it.
+pub fn max_exclusion() -> Duration {
+    Duration::weeks(5 * 52)
+}
+
+// Durations like 30m, 12h, 7d or 2w, at most max_exclusion.
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let duration = duration.trim().to_lowercase();
+    let split = duration.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = duration.split_at(split);
+    let amount = amount.parse::<i64>().ok().filter(|a| *a > 0)?;
+    let unit = match unit {
+        "m" | "min" | "mins" | "minutes" => Duration::minutes(1),
+        "h" | "hour" | "hours" => Duration::hours(1),
+        "d" | "day" | "days" => Duration::days(1),
+        "w" | "week" | "weeks" => Duration::weeks(1),
+        _ => return None,
+    };
+    let most = max_exclusion().num_minutes() / unit.num_minutes();
+    Some(unit * amount.min(most) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> NaiveDateTime {
+        chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc() + Duration::minutes(minutes)
+    }
+
+    fn limits() -> Limits {
+        Limits {
+            max_wager: Some(500),
+            session_loss: Some(1000),
+            daily_loss: Some(2000),
+            session_gap: Duration::minutes(30),
+        }
+    }
+
+    #[test]
+    fn test_losses() {
+        let now = at(2000);
+        // newest first: this session lost 150, earlier today won 100, yesterday's loss
+        // doesn't count
+        let bets = [
+            (at(1990), -100),
+            (at(1970), 50),
+            (at(1950), -100),
+            (at(1800), 100),
+            (at(100), -5000),
+        ];
+        assert_eq!(
+            Losses {
+                session: 150,
+                day: 50
+            },
+            losses(&bets, now, Duration::minutes(30))
+        );
+        // the session is over once the last bet is too long ago
+        assert_eq!(0, losses(&bets, at(2100), Duration::minutes(30)).session);
+        assert_eq!(Losses::default(), losses(&[], now, Duration::minutes(30)));
+    }
+
+    #[test]
+    fn test_open_hand_counts_as_lost() {
+        let now = at(2000);
+        let bets = [(at(1990), -100), (at(1800), 100)];
+        let open = with_open_wager(&bets, Some(200), now);
+        assert_eq!(
+            Losses {
+                session: 300,
+                day: 200
+            },
+            losses(&open, now, Duration::minutes(30))
+        );
+        // a hand started long after the last bet is a session of its own
+        let open = with_open_wager(&bets, Some(200), at(2100));
+        assert_eq!(200, losses(&open, at(2100), Duration::minutes(30)).session);
+        assert_eq!(bets.to_vec(), with_open_wager(&bets, None, now));
+    }
+
+    #[test]
+    fn test_check() {
+        let now = at(0);
+        let lost = |session, day| Losses { session, day };
+        assert_eq!(Ok(()), check(&limits(), &lost(500, 1500), None, now, 500));
+        assert_eq!(
+            Err(WagerError::TooBig(500)),
+            check(&limits(), &lost(0, 0), None, now, 501)
+        );
+        assert_eq!(
+            Err(WagerError::SessionLossLimit {
+                limit: 1000,
+                lost: 600
+            }),
+            check(&limits(), &lost(600, 600), None, now, 500)
+        );
+        assert_eq!(
+            Err(WagerError::DailyLossLimit {
+                limit: 2000,
+                lost: 1600
+            }),
+            check(&limits(), &lost(0, 1600), None, now, 500)
+        );
+        assert_eq!(
+            Err(WagerError::Excluded(at(60))),
+            check(&limits(), &lost(0, 0), Some(at(60)), now, 1)
+        );
+        assert_eq!(Ok(()), check(&limits(), &lost(0, 0), Some(at(-60)), now, 1));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = GamblingLimit {
+            max_wager: Some(50),
+            daily_loss_limit: Some(100),
+            ..Default::default()
+        };
+        let limits = limits().for_chatter(Some(&overrides));
+        assert_eq!(Some(50), limits.get(LimitKind::Wager));
+        assert_eq!(Some(1000), limits.get(LimitKind::Session));
+        assert_eq!(Some(100), limits.get(LimitKind::Daily));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Some(Duration::minutes(30)), parse_duration("30m"));
+        assert_eq!(Some(Duration::hours(12)), parse_duration("12h"));
+        assert_eq!(Some(Duration::days(7)), parse_duration("7d"));
+        assert_eq!(Some(Duration::weeks(2)), parse_duration("2W"));
+        assert_eq!(None, parse_duration("0d"));
+        assert_eq!(None, parse_duration("7"));
+        assert_eq!(None, parse_duration("forever"));
+        assert_eq!(Some(max_exclusion()), parse_duration("999999999d"));
+        assert_eq!(None, parse_duration("99999999999999999999m"));
+    }
+}
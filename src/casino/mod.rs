@@ -0,0 +1,433 @@
+// Games chatters can !gamble their points on. Every game pays out a multiple of the
+// wager from a payout table, which can be changed with CASINO_<GAME>_PAYOUTS, e.g.
+// `CASINO_DICE_PAYOUTS="2=0,3-6=0.5,12=5"`. Whatever gamblers lose is split between
+// the losers pool and the broadcaster.
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use chrono::NaiveDateTime;
+use rand::RngCore;
+
+use crate::chatter;
+use crate::db;
+use crate::market;
+use crate::models::{Chatter, MarketEventKind};
+
+pub mod blackjack;
+pub mod coinflip;
+pub mod dice;
+pub mod fair;
+pub mod higher_lower;
+pub mod limits;
+pub mod roulette;
+pub mod slots;
+pub mod stats;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Play {
+    // what the gambler gets back, wager included, 0 when they lose it all
+    pub payout: i64,
+    // what happened, e.g. "You rolled a 3 and a 4!"
+    pub result: String,
+}
+
+pub trait CasinoGame: fmt::Debug + Send {
+    fn name(&self) -> &'static str;
+
+    // Other names !gamble accepts for the game.
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    // What the bet after the wager looks like, if the game takes one.
+    fn bet_usage(&self) -> Option<&'static str> {
+        None
+    }
+
+    // Whether play would take the bet, checked before anything is drawn for it.
+    fn check_bet(&self, _bet: &[&str]) -> Result<()> {
+        Ok(())
+    }
+
+    fn play(&self, wager: i64, bet: &[&str], rng: &mut dyn RngCore) -> Result<Play>;
+
+    // The average fraction of a wager a gambler gets back.
+    fn expected_return(&self) -> f64;
+
+    fn house_edge(&self) -> f64 {
+        1.0 - self.expected_return()
+    }
+}
+
+// The payout for a multiplier, rounded down.
+pub fn pay(wager: i64, multiplier: f64) -> i64 {
+    (wager as f64 * multiplier).floor() as i64
+}
+
+// Multipliers by outcome, e.g. the sum of the dice or the slot symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayoutTable {
+    payouts: Vec<(String, f64)>,
+}
+
+impl PayoutTable {
+    pub fn new(defaults: &[(&str, f64)]) -> PayoutTable {
+        PayoutTable {
+            payouts: defaults
+                .iter()
+                .map(|(key, multiplier)| (key.to_string(), *multiplier))
+                .collect(),
+        }
+    }
+
+    // Reads CASINO_<GAME>_PAYOUTS over the defaults.
+    pub fn from_env(game: &str, defaults: &[(&str, f64)]) -> PayoutTable {
+        let name = format!("CASINO_{}_PAYOUTS", game.to_uppercase());
+        let table = PayoutTable::new(defaults);
+        match std::env::var(&name) {
+            Ok(overrides) => table.with_overrides(&overrides).unwrap_or_else(|err| {
+                panic!("{} should look like key=multiplier,...: {}", name, err)
+            }),
+            Err(_) => table,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> f64 {
+        self.payouts
+            .iter()
+            .find(|(k, _)| k == key)
+            .map_or(0.0, |(_, multiplier)| *multiplier)
+    }
+
+    // Overrides look like `key=multiplier,...`, numeric keys can be given as a range
+    // like `3-6=0.5`.
+    pub fn with_overrides(mut self, overrides: &str) -> Result<PayoutTable> {
+        for pair in overrides.split(',').filter(|p| !p.trim().is_empty()) {
+            let (key, multiplier) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("{} has no multiplier", pair))?;
+            let multiplier = multiplier
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|m| *m >= 0.0)
+                .ok_or_else(|| anyhow!("{} isn't a multiplier", multiplier))?;
+            let key = key.trim().to_lowercase();
+            let keys = match key.split_once('-').map(|(a, b)| (a.parse(), b.parse())) {
+                Some((Ok(low), Ok(high))) => (low..=high).map(|k: i64| k.to_string()).collect(),
+                _ => vec![key],
+            };
+            for key in keys {
+                match self.payouts.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, m)) => *m = multiplier,
+                    None => return Err(anyhow!("there's no {} payout", key)),
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WagerError {
+    NotPositive,
+    TooBig(i64),
+    NotEnoughPoints { wager: i64, balance: i64 },
+    Excluded(NaiveDateTime),
+    SessionLossLimit { limit: i64, lost: i64 },
+    DailyLossLimit { limit: i64, lost: i64 },
+}
+
+impl fmt::Display for WagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WagerError::NotPositive => write!(f, "wagers have to be at least 1 point"),
+            WagerError::TooBig(max) => write!(f, "the most you can wager is {} points", max),
+            WagerError::NotEnoughPoints { wager, balance } => write!(
+                f,
+                "you can't wager {} points, you only have {}",
+                wager, balance
+            ),
+            WagerError::Excluded(until) => write!(
+                f,
+                "you're taking a break from gambling until {} UTC",
+                until.format("%Y-%m-%d %H:%M")
+            ),
+            WagerError::SessionLossLimit { limit, lost } => write!(
+                f,
+                "that could take you past your {} point loss limit for this session, you've lost {}",
+                limit, lost
+            ),
+            WagerError::DailyLossLimit { limit, lost } => write!(
+                f,
+                "that could take you past your {} point daily loss limit, you've lost {} today",
+                limit, lost
+            ),
+        }
+    }
+}
+
+pub fn validate_wager(wager: i64, balance: i64) -> Result<(), WagerError> {
+    if wager <= 0 {
+        return Err(WagerError::NotPositive);
+    }
+    if wager > balance {
+        return Err(WagerError::NotEnoughPoints { wager, balance });
+    }
+    Ok(())
+}
+
+// (to the losers pool, to the broadcaster) out of what a gambler lost.
+pub fn split_loss(loss: i64, pool_percent: i64) -> (i64, i64) {
+    let pool = loss * pool_percent / 100;
+    (pool, loss - pool)
+}
+
+// Where the points of a settled play went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settlement {
+    // what the gambler won, negative when they lost
+    pub net: i64,
+    pub pool: i64,
+    pub broadcaster: i64,
+}
+
+#[derive(Debug)]
+pub struct Casino {
+    games: Vec<Box<dyn CasinoGame>>,
+    // percent of what gamblers lose that goes to the losers pool
+    pub pool_percent: i64,
+    pub limits: limits::Limits,
+    pub blackjack: blackjack::Blackjack,
+}
+
+impl Casino {
+    // CASINO_POOL_PERCENT (default 50), the gambling limits and each game's payout
+    // table.
+    pub fn from_env() -> Casino {
+        let pool_percent = std::env::var("CASINO_POOL_PERCENT")
+            .map(|p| {
+                p.trim()
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|p| (0..=100).contains(p))
+                    .expect("CASINO_POOL_PERCENT should be a number from 0 to 100")
+            })
+            .unwrap_or(50);
+        Casino {
+            games: vec![
+                Box::new(dice::Dice::from_env()),
+                Box::new(coinflip::Coinflip::from_env()),
+                Box::new(slots::Slots::from_env()),
+                Box::new(roulette::Roulette::from_env()),
+                Box::new(higher_lower::HigherLower::from_env()),
+            ],
+            pool_percent,
+            limits: limits::Limits::from_env(),
+            blackjack: blackjack::Blackjack::from_env(),
+        }
+    }
+
+    pub fn games(&self) -> impl Iterator<Item = &dyn CasinoGame> {
+        self.games.iter().map(|game| game.as_ref())
+    }
+
+    pub fn game(&self, name: &str) -> Option<&dyn CasinoGame> {
+        let name = name.to_lowercase();
+        self.games()
+            .find(|game| game.name() == name || game.aliases().contains(&name.as_str()))
+    }
+
+    // What a game pays back on average, blackjack included.
+    pub fn expected_return(&self, name: &str) -> Option<f64> {
+        if name.eq_ignore_ascii_case("blackjack") {
+            return Some(self.blackjack.expected_return());
+        }
+        self.game(name).map(|game| game.expected_return())
+    }
+
+    // Checks a wager against the chatter's balance, self-exclusion and limits. Every
+    // game goes through here before taking a wager.
+    pub fn check_wager(&self, chatter: &Chatter, wager: i64) -> Result<(), WagerError> {
+        validate_wager(wager, chatter.points)?;
+        let overrides = db::get_gambling_limit(chatter.id);
+        let limits = self.limits.for_chatter(overrides.as_ref());
+        let now = chrono::Utc::now().naive_utc();
+        let losses = self.losses(chatter.id, &limits, now);
+        let excluded_until = overrides.and_then(|overrides| overrides.excluded_until);
+        limits::check(&limits, &losses, excluded_until, now, wager)
+    }
+
+    // What the chatter lost this session and over the last day, with the wager of a
+    // blackjack hand they're still playing counted as lost.
+    pub fn losses(
+        &self,
+        chatter_id: i32,
+        limits: &limits::Limits,
+        now: chrono::NaiveDateTime,
+    ) -> limits::Losses {
+        let bets = db::get_bet_results_since(chatter_id, now - chrono::Duration::days(1));
+        let open_wager = db::get_blackjack_hand(chatter_id).map(|hand| hand.wager);
+        limits::losses(
+            &limits::with_open_wager(&bets, open_wager, now),
+            now,
+            limits.session_gap,
+        )
+    }
+
+    // Moves the points for a play.
+    pub fn settle(
+        &self,
+        twitch_id: &str,
+        broadcaster_id: &str,
+        wager: i64,
+        payout: i64,
+    ) -> Settlement {
+        let net = payout - wager;
+        if net > 0 {
+            chatter::add_points(twitch_id, net);
+        } else if net < 0 {
+            chatter::subtract_points(twitch_id, -net);
+        }
+        self.collect(broadcaster_id, net)
+    }
+
+    // settle for games that held the wager back and already paid the gambler out,
+    // like blackjack.
+    pub fn settle_held(&self, broadcaster_id: &str, wager: i64, payout: i64) -> Settlement {
+        self.collect(broadcaster_id, payout - wager)
+    }
+
+    // Splits what a gambler lost between the losers pool and the broadcaster.
+    fn collect(&self, broadcaster_id: &str, net: i64) -> Settlement {
+        let (pool, broadcaster) = if net < 0 {
+            split_loss(-net, self.pool_percent)
+        } else {
+            (0, 0)
+        };
+        if net < 0 {
+            db::add_pool_points(pool);
+            chatter::add_points(broadcaster_id, broadcaster);
+        }
+        // what the gambler lost is what the house made
+        market::record_event(market::GAMBLE_INDEX, MarketEventKind::Gamble, -net);
+        Settlement {
+            net,
+            pool,
+            broadcaster,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_payout_table_overrides() {
+        let table = PayoutTable::new(&[("2", 0.0), ("3", 0.5), ("4", 0.5), ("win", 2.0)])
+            .with_overrides("2-3=1, WIN=1.5")
+            .unwrap();
+        assert_eq!(
+            (1.0, 1.0, 0.5, 1.5),
+            (
+                table.get("2"),
+                table.get("3"),
+                table.get("4"),
+                table.get("win")
+            )
+        );
+        assert_eq!(0.0, table.get("lose"));
+        assert!(table.clone().with_overrides("lose=2").is_err());
+        assert!(table.clone().with_overrides("win=-1").is_err());
+        assert!(table.with_overrides("win").is_err());
+    }
+
+    #[test]
+    fn test_validate_wager() {
+        assert_eq!(Ok(()), validate_wager(100, 100));
+        assert_eq!(Err(WagerError::NotPositive), validate_wager(0, 100));
+        assert_eq!(
+            Err(WagerError::NotEnoughPoints {
+                wager: 101,
+                balance: 100
+            }),
+            validate_wager(101, 100)
+        );
+    }
+
+    #[test]
+    fn test_split_loss() {
+        assert_eq!((50, 51), split_loss(101, 50));
+        assert_eq!((0, 10), split_loss(10, 0));
+    }
+
+    #[test]
+    fn test_games_pay_what_they_expect_to() {
+        // plays every game a lot and checks the house edge they report is right
+        let casino = Casino::from_env();
+        let mut rng = StdRng::seed_from_u64(7);
+        let bets: &[(&str, &[&str])] = &[
+            ("dice", &[]),
+            ("coinflip", &["tails"]),
+            ("slots", &[]),
+            ("roulette", &["red"]),
+            ("roulette", &["17"]),
+            ("higherlower", &["higher"]),
+        ];
+        for (name, bet) in bets {
+            let game = casino.game(name).unwrap();
+            let plays = 200_000;
+            let paid = (0..plays)
+                .map(|_| game.play(1000, bet, &mut rng).unwrap().payout)
+                .sum::<i64>();
+            let observed = paid as f64 / (plays * 1000) as f64;
+            let tolerance = if *bet == ["17"] { 0.1 } else { 0.02 };
+            assert!(
+                (observed - game.expected_return()).abs() < tolerance,
+                "{} {:?}: expected {}, got {}",
+                name,
+                bet,
+                game.expected_return(),
+                observed
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_bet_agrees_with_play() {
+        let casino = Casino::from_env();
+        let mut rng = StdRng::seed_from_u64(7);
+        let bets: &[&[&str]] = &[
+            &[],
+            &["tails"],
+            &["red"],
+            &["higher"],
+            &["37"],
+            &["sideways"],
+        ];
+        for game in casino.games() {
+            for bet in bets {
+                assert_eq!(
+                    game.play(100, bet, &mut rng).is_ok(),
+                    game.check_bet(bet).is_ok(),
+                    "{} {:?}",
+                    game.name(),
+                    bet
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_names() {
+        let casino = Casino::from_env();
+        for game in casino.games() {
+            assert_eq!(game.name(), casino.game(game.name()).unwrap().name());
+        }
+        assert_eq!("higherlower", casino.game("HiLo").unwrap().name());
+        assert!(casino.game("poker").is_none());
+    }
+}
@@ -0,0 +1,161 @@
+// European roulette, one zero. Bets are a color, odd or even, low (1-18) or high
+// (19-36), which pay "even_money", a dozen, which pays "dozen", or a single number,
+// which pays "straight".
+use anyhow::{anyhow, Result};
+use rand::{Rng, RngCore};
+
+use crate::casino::{pay, CasinoGame, PayoutTable, Play};
+
+pub const PAYOUTS: &[(&str, f64)] = &[("even_money", 2.0), ("dozen", 3.0), ("straight", 36.0)];
+
+const RED: [u32; 18] = [
+    1, 3, 5, 7, 9, 12, 14, 16, 18, 19, 21, 23, 25, 27, 30, 32, 34, 36,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bet {
+    Red,
+    Black,
+    Odd,
+    Even,
+    Low,
+    High,
+    // 1st, 2nd or 3rd dozen
+    Dozen(u32),
+    Straight(u32),
+}
+
+impl Bet {
+    pub fn parse(bet: &str) -> Option<Bet> {
+        match bet.to_lowercase().as_str() {
+            "red" => Some(Bet::Red),
+            "black" => Some(Bet::Black),
+            "odd" => Some(Bet::Odd),
+            "even" => Some(Bet::Even),
+            "low" => Some(Bet::Low),
+            "high" => Some(Bet::High),
+            "1st" => Some(Bet::Dozen(1)),
+            "2nd" => Some(Bet::Dozen(2)),
+            "3rd" => Some(Bet::Dozen(3)),
+            number => number
+                .parse::<u32>()
+                .ok()
+                .filter(|n| *n <= 36)
+                .map(Bet::Straight),
+        }
+    }
+
+    pub fn wins(&self, number: u32) -> bool {
+        let red = RED.contains(&number);
+        match self {
+            Bet::Straight(n) => *n == number,
+            // everything else loses on zero
+            _ if number == 0 => false,
+            Bet::Red => red,
+            Bet::Black => !red,
+            Bet::Odd => !number.is_multiple_of(2),
+            Bet::Even => number.is_multiple_of(2),
+            Bet::Low => number <= 18,
+            Bet::High => number >= 19,
+            Bet::Dozen(dozen) => (number - 1) / 12 + 1 == *dozen,
+        }
+    }
+
+    fn payout_key(&self) -> &'static str {
+        match self {
+            Bet::Straight(_) => "straight",
+            Bet::Dozen(_) => "dozen",
+            _ => "even_money",
+        }
+    }
+}
+
+fn color(number: u32) -> &'static str {
+    if number == 0 {
+        "🟢"
+    } else if RED.contains(&number) {
+        "🔴"
+    } else {
+        "⚫"
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Roulette {
+    pub payouts: PayoutTable,
+}
+
+impl Roulette {
+    pub fn from_env() -> Roulette {
+        Roulette {
+            payouts: PayoutTable::from_env("roulette", PAYOUTS),
+        }
+    }
+
+    pub fn expected_return_of(&self, bet: Bet) -> f64 {
+        let wins = (0..=36).filter(|n| bet.wins(*n)).count() as f64;
+        wins / 37.0 * self.payouts.get(bet.payout_key())
+    }
+}
+
+fn parse_bet(bet: &[&str]) -> Result<Bet> {
+    bet.first().and_then(|b| Bet::parse(b)).ok_or_else(|| {
+        anyhow!("bet on red, black, odd, even, low, high, 1st, 2nd, 3rd or a number from 0 to 36")
+    })
+}
+
+impl CasinoGame for Roulette {
+    fn name(&self) -> &'static str {
+        "roulette"
+    }
+
+    fn bet_usage(&self) -> Option<&'static str> {
+        Some("<red|black|odd|even|low|high|1st|2nd|3rd|0-36>")
+    }
+
+    fn check_bet(&self, bet: &[&str]) -> Result<()> {
+        parse_bet(bet).map(|_| ())
+    }
+
+    fn play(&self, wager: i64, bet: &[&str], rng: &mut dyn RngCore) -> Result<Play> {
+        let bet = parse_bet(bet)?;
+        let number = rng.gen_range(0..=36);
+        let multiplier = if bet.wins(number) {
+            self.payouts.get(bet.payout_key())
+        } else {
+            0.0
+        };
+        Ok(Play {
+            payout: pay(wager, multiplier),
+            result: format!("The ball landed on {} {}!", color(number), number),
+        })
+    }
+
+    // For the best paying bet.
+    fn expected_return(&self) -> f64 {
+        [Bet::Red, Bet::Dozen(1), Bet::Straight(0)]
+            .iter()
+            .map(|bet| self.expected_return_of(*bet))
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bets() {
+        assert!(Bet::Red.wins(1) && !Bet::Red.wins(2) && !Bet::Red.wins(0));
+        assert!(Bet::Black.wins(2) && !Bet::Black.wins(0));
+        assert!(Bet::Dozen(3).wins(36) && !Bet::Dozen(3).wins(24));
+        assert!(Bet::Straight(0).wins(0));
+        assert_eq!(Some(Bet::Dozen(2)), Bet::parse("2nd"));
+        assert_eq!(None, Bet::parse("37"));
+        assert_eq!(None, Bet::parse("green"));
+        let roulette = Roulette {
+            payouts: PayoutTable::new(PAYOUTS),
+        };
+        assert!((roulette.house_edge() - 1.0 / 37.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,99 @@
+// Three reels. Three of a kind pays that symbol's multiplier and two cherries pay
+// "cherry2". Rarer symbols pay more.
+use anyhow::Result;
+use rand::{Rng, RngCore};
+
+use crate::casino::{pay, CasinoGame, PayoutTable, Play};
+
+// (payout key, symbol, how often it comes up on a reel)
+pub const SYMBOLS: &[(&str, &str, u32)] = &[
+    ("cherry", "🍒", 5),
+    ("lemon", "🍋", 4),
+    ("bell", "🔔", 3),
+    ("star", "⭐", 2),
+    ("seven", "7️⃣", 1),
+];
+
+pub const PAYOUTS: &[(&str, f64)] = &[
+    ("cherry", 5.0),
+    ("lemon", 10.0),
+    ("bell", 20.0),
+    ("star", 40.0),
+    ("seven", 50.0),
+    ("cherry2", 1.5),
+];
+
+#[derive(Debug, Clone)]
+pub struct Slots {
+    pub payouts: PayoutTable,
+}
+
+impl Slots {
+    pub fn from_env() -> Slots {
+        Slots {
+            payouts: PayoutTable::from_env("slots", PAYOUTS),
+        }
+    }
+
+    fn multiplier(&self, reels: [usize; 3]) -> f64 {
+        let cherries = reels.iter().filter(|r| **r == 0).count();
+        if reels[0] == reels[1] && reels[1] == reels[2] {
+            self.payouts.get(SYMBOLS[reels[0]].0)
+        } else if cherries == 2 {
+            self.payouts.get("cherry2")
+        } else {
+            0.0
+        }
+    }
+}
+
+fn total_weight() -> u32 {
+    SYMBOLS.iter().map(|(_, _, weight)| weight).sum()
+}
+
+fn spin(rng: &mut dyn RngCore) -> usize {
+    let mut roll = rng.gen_range(0..total_weight());
+    for (i, (_, _, weight)) in SYMBOLS.iter().enumerate() {
+        if roll < *weight {
+            return i;
+        }
+        roll -= weight;
+    }
+    unreachable!("The roll is less than the total weight")
+}
+
+impl CasinoGame for Slots {
+    fn name(&self) -> &'static str {
+        "slots"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["slot"]
+    }
+
+    fn play(&self, wager: i64, _bet: &[&str], rng: &mut dyn RngCore) -> Result<Play> {
+        let reels = [spin(rng), spin(rng), spin(rng)];
+        let result = format!(
+            "[ {} | {} | {} ]",
+            SYMBOLS[reels[0]].1, SYMBOLS[reels[1]].1, SYMBOLS[reels[2]].1
+        );
+        Ok(Play {
+            payout: pay(wager, self.multiplier(reels)),
+            result,
+        })
+    }
+
+    fn expected_return(&self) -> f64 {
+        let total = total_weight() as f64;
+        let mut expected = 0.0;
+        for (a, (_, _, weight_a)) in SYMBOLS.iter().enumerate() {
+            for (b, (_, _, weight_b)) in SYMBOLS.iter().enumerate() {
+                for (c, (_, _, weight_c)) in SYMBOLS.iter().enumerate() {
+                    let chance = (weight_a * weight_b * weight_c) as f64 / (total * total * total);
+                    expected += chance * self.multiplier([a, b, c]);
+                }
+            }
+        }
+        expected
+    }
+}
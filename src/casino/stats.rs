@@ -0,0 +1,248 @@
+// Every wager is recorded as a bet with where its points went, so we can tell whether
+// the casino is draining or inflating the economy. The totals back !casinostats,
+// !gamblestats and `cargo run --bin casino`, which can also simulate a game to check a
+// payout table before it's deployed.
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use rand::RngCore;
+
+use crate::casino::blackjack::Blackjack;
+use crate::casino::fair::Draw;
+use crate::casino::{CasinoGame, Play, Settlement};
+use crate::db;
+use crate::models::{Bet, NewBet};
+
+pub fn record_bet(
+    chatter_id: i32,
+    game: &str,
+    wager: i64,
+    bet: &str,
+    draw: Option<&Draw>,
+    play: &Play,
+    settlement: &Settlement,
+) -> Bet {
+    db::create_bet(&NewBet {
+        chatter_id,
+        game,
+        wager,
+        bet,
+        seed_id: draw.map(|draw| draw.seed_id),
+        client_seed: draw.map(|draw| draw.client_seed.as_str()),
+        nonce: draw.map(|draw| draw.nonce),
+        payout: play.payout,
+        result: &play.result,
+        pool_cut: settlement.pool,
+        broadcaster_cut: settlement.broadcaster,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub bets: i64,
+    pub wagered: i64,
+    // paid back to gamblers, wagers included
+    pub paid: i64,
+    pub pool: i64,
+    pub broadcaster: i64,
+}
+
+impl Totals {
+    pub fn add(&mut self, bet: &Bet) {
+        self.bets += 1;
+        self.wagered += bet.wager;
+        self.paid += bet.payout;
+        self.pool += bet.pool_cut;
+        self.broadcaster += bet.broadcaster_cut;
+    }
+
+    pub fn merge(&mut self, other: &Totals) {
+        self.bets += other.bets;
+        self.wagered += other.wagered;
+        self.paid += other.paid;
+        self.pool += other.pool;
+        self.broadcaster += other.broadcaster;
+    }
+
+    // What gamblers won, negative when they lost.
+    pub fn net(&self) -> i64 {
+        self.paid - self.wagered
+    }
+
+    pub fn house_profit(&self) -> i64 {
+        -self.net()
+    }
+
+    // The fraction of wagers paid back, None before anything was wagered.
+    pub fn observed_return(&self) -> Option<f64> {
+        (self.wagered > 0).then(|| self.paid as f64 / self.wagered as f64)
+    }
+}
+
+// Totals per day, oldest first.
+pub fn by_day(bets: &[Bet]) -> Vec<(NaiveDate, Totals)> {
+    let mut days: Vec<(NaiveDate, Totals)> = vec![];
+    for bet in bets {
+        let day = bet.created_at.date();
+        match days.iter_mut().find(|(d, _)| *d == day) {
+            Some((_, totals)) => totals.add(bet),
+            None => {
+                let mut totals = Totals::default();
+                totals.add(bet);
+                days.push((day, totals));
+            }
+        }
+    }
+    days.sort_by_key(|(day, _)| *day);
+    days
+}
+
+// The start of the last that many days, or of time.
+pub fn since(days: Option<i64>) -> NaiveDateTime {
+    match days {
+        Some(days) => chrono::Utc::now().naive_utc() - chrono::Duration::days(days),
+        None => chrono::DateTime::UNIX_EPOCH.naive_utc(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simulation {
+    pub plays: u64,
+    pub wagered: i64,
+    pub paid: i64,
+    pub biggest_payout: i64,
+    pub observed_return: f64,
+    // of the observed return, from how much single plays varied
+    pub standard_error: f64,
+}
+
+// Plays a game over and over and compares what it paid with what it should have.
+pub fn simulate(
+    game: &dyn CasinoGame,
+    wager: i64,
+    bet: &[&str],
+    plays: u64,
+    rng: &mut dyn RngCore,
+) -> Result<Simulation> {
+    simulate_payouts(wager, plays, || Ok(game.play(wager, bet, rng)?.payout))
+}
+
+// Blackjack isn't a !gamble game, its hands are played out by Blackjack::play_to_17.
+pub fn simulate_blackjack(
+    blackjack: &Blackjack,
+    wager: i64,
+    plays: u64,
+    rng: &mut dyn RngCore,
+) -> Result<Simulation> {
+    simulate_payouts(wager, plays, || Ok(blackjack.play_to_17(wager, rng)))
+}
+
+fn simulate_payouts(
+    wager: i64,
+    plays: u64,
+    mut play: impl FnMut() -> Result<i64>,
+) -> Result<Simulation> {
+    if wager <= 0 {
+        return Err(anyhow!("the wager should be at least 1 point"));
+    }
+    let mut paid = 0;
+    let mut biggest_payout = 0;
+    let mut sum_squares = 0.0;
+    for _ in 0..plays {
+        let payout = play()?;
+        paid += payout;
+        biggest_payout = biggest_payout.max(payout);
+        sum_squares += (payout as f64 / wager as f64).powi(2);
+    }
+    let wagered = wager * plays as i64;
+    let observed_return = paid as f64 / wagered as f64;
+    let variance = (sum_squares / plays as f64 - observed_return.powi(2)).max(0.0);
+    Ok(Simulation {
+        plays,
+        wagered,
+        paid,
+        biggest_payout,
+        observed_return,
+        standard_error: (variance / plays as f64).sqrt(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::casino::Casino;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn bet(day: u32, wager: i64, payout: i64) -> Bet {
+        Bet {
+            id: 1,
+            chatter_id: 1,
+            game: "dice".to_string(),
+            wager,
+            bet: String::new(),
+            seed_id: None,
+            client_seed: None,
+            nonce: None,
+            payout,
+            result: String::new(),
+            created_at: NaiveDate::from_ymd_opt(2026, 10, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            pool_cut: (wager - payout).max(0) / 2,
+            broadcaster_cut: (wager - payout).max(0) - (wager - payout).max(0) / 2,
+        }
+    }
+
+    #[test]
+    fn test_totals() {
+        let mut totals = Totals::default();
+        assert_eq!(None, totals.observed_return());
+        for b in [bet(1, 100, 0), bet(1, 100, 300), bet(1, 100, 50)] {
+            totals.add(&b);
+        }
+        assert_eq!(3, totals.bets);
+        assert_eq!(50, totals.net());
+        assert_eq!(-50, totals.house_profit());
+        assert_eq!(150, totals.pool + totals.broadcaster);
+        assert_eq!(Some(350.0 / 300.0), totals.observed_return());
+    }
+
+    #[test]
+    fn test_by_day() {
+        let days = by_day(&[bet(3, 100, 0), bet(1, 100, 200), bet(3, 50, 0)]);
+        assert_eq!(2, days.len());
+        assert_eq!(NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(), days[0].0);
+        assert_eq!(-100, days[0].1.house_profit());
+        assert_eq!(150, days[1].1.house_profit());
+    }
+
+    #[test]
+    fn test_simulate_lands_near_the_expected_return() {
+        let casino = Casino::from_env();
+        let game = casino.game("coinflip").unwrap();
+        let simulation = simulate(game, 100, &[], 50_000, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(5_000_000, simulation.wagered);
+        assert_eq!(196, simulation.biggest_payout);
+        assert!(simulation.standard_error > 0.0 && simulation.standard_error < 0.01);
+        assert!(
+            (simulation.observed_return - game.expected_return()).abs()
+                < 4.0 * simulation.standard_error
+        );
+        assert!(simulate(game, 100, &["edge"], 1, &mut StdRng::seed_from_u64(1)).is_err());
+        assert!(simulate(game, 0, &[], 1, &mut StdRng::seed_from_u64(1)).is_err());
+    }
+
+    #[test]
+    fn test_simulate_blackjack_lands_near_the_expected_return() {
+        let casino = Casino::from_env();
+        let mut rng = StdRng::seed_from_u64(1);
+        let simulation = simulate_blackjack(&casino.blackjack, 100, 50_000, &mut rng).unwrap();
+        assert_eq!(250, simulation.biggest_payout);
+        assert!(
+            (simulation.observed_return - casino.blackjack.expected_return()).abs()
+                < 4.0 * simulation.standard_error
+        );
+        assert!(simulate_blackjack(&casino.blackjack, -5, 1, &mut rng).is_err());
+    }
+}
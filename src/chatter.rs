@@ -1,14 +1,12 @@
 use std::str::FromStr;
 
-use crate::helpers::overflow_add;
 use anyhow::{anyhow, Result};
 use chrono::TimeZone;
 use log::info;
 
-use crate::db::{
-    self, get_chatter, get_lurker, update_losses, update_lurk_time, update_points, update_wins,
-};
+use crate::db::{self, get_chatter, get_lurker, update_losses, update_lurk_time, update_wins};
 use crate::messaging;
+use crate::watch_time;
 
 #[derive(Debug, Clone)]
 pub struct TwitchUserId(String);
@@ -58,8 +56,11 @@ pub fn unlurk(client: &mut tmi::Client, msg: &tmi::Privmsg<'_>) -> () {
         }
     };
 
-    let new_lurk_time = chatter.lurk_time + time_lurked;
-    db::update_lurk_time(twitch_id, new_lurk_time);
+    // watch time counts lurk time as it goes when it's on
+    if !watch_time::is_enabled() {
+        let new_lurk_time = chatter.lurk_time + time_lurked;
+        db::update_lurk_time(twitch_id, new_lurk_time);
+    }
     db::delete_lurker(twitch_id.to_owned());
 
     // welcome chatter back from lurk
@@ -86,34 +87,15 @@ pub async fn on_new_chatter(client: &mut tmi::Client, msg: &tmi::Privmsg<'_>) ->
         &format!("Welcome, {}! You have been given 1000 points, to gamble and duel with, type !commands to see what you can do.", twitch_name),
     ).await;
 }
-// TODO: Add a saturaton operation for negative overflows crates: ranged_integers, constrained_int, deranged (deranged might be the best one?)
-// TODO: check out: checked_add ie. 5.checked_add(6)
-pub fn add_points(twitch_id: &str, points: i64) -> () {
-    match get_chatter(twitch_id) {
-        Some(chatter) => {
-            let new_points = if (overflow_add(chatter.points, points)) < -1000 {
-                -1000
-            } else {
-                overflow_add(chatter.points, points)
-            };
-            update_points(twitch_id, new_points)
-        }
-        None => info!("No Chatter with id: {} to update!", twitch_id),
+
+pub fn add_points(twitch_id: &str, points: i64) {
+    if !db::add_points(twitch_id, points) {
+        info!("No Chatter with id: {} to update!", twitch_id);
     }
 }
 
-pub fn subtract_points(twitch_id: &str, points: i64) -> () {
-    match get_chatter(twitch_id) {
-        Some(chatter) => {
-            let new_points = if (chatter.points - points) < -1000 {
-                -1000
-            } else {
-                chatter.points - points
-            };
-            update_points(twitch_id, new_points)
-        }
-        None => info!("No Chatter with id: {} to update!", twitch_id),
-    }
+pub fn subtract_points(twitch_id: &str, points: i64) {
+    add_points(twitch_id, -points)
 }
 
 pub fn get_points(twitch_id: &str) -> i64 {
@@ -136,7 +118,7 @@ pub fn add_win(twitch_id: &str) -> () {
     }
 }
 
-pub fn _subtract_win(twitch_id: &str) -> () {
+pub fn subtract_win(twitch_id: &str) {
     match get_chatter(twitch_id) {
         Some(chatter) => {
             let new_wins = chatter.wins - 1;
@@ -156,7 +138,7 @@ pub fn add_loss(twitch_id: &str) -> () {
     }
 }
 
-pub fn _subtract_loss(twitch_id: &str) -> () {
+pub fn subtract_loss(twitch_id: &str) {
     match get_chatter(twitch_id) {
         Some(chatter) => {
             let new_losses = chatter.losses - 1;
@@ -176,6 +158,15 @@ pub fn _add_lurk_time(twitch_id: &str, lurk_time: i32) -> () {
     }
 }
 
+// Mods and the broadcaster can run moderation commands.
+pub fn is_moderator(msg: &tmi::Privmsg<'_>) -> bool {
+    let broadcaster_id = std::env::var("BROADCASTER_ID").expect("BROADCASTER_ID must be set");
+    msg.sender().id() == broadcaster_id
+        || msg
+            .badges()
+            .any(|badge| matches!(badge, tmi::Badge::Moderator | tmi::Badge::Broadcaster))
+}
+
 pub fn get_challenge_to_accept(twitch_id: &str) -> Option<String> {
     let challenges = db::get_challenges(twitch_id);
     match challenges.len() {
@@ -1,19 +1,30 @@
+use crate::casino;
 use crate::chatter;
 use crate::chatter::get_challenge_to_accept;
+use crate::daily;
 use crate::db;
 use crate::db::get_category_by_name;
+use crate::difficulty::Difficulty;
+use crate::generator::{self, Topic};
 use crate::helpers;
+use crate::market;
 use crate::messaging;
 use crate::messaging::{list_with_title, ItemSeparator};
 use crate::models;
-use crate::models::Question;
+use crate::models::{
+    MarketEventKind, Question, QuestionStatus, QUESTION_COST, QUESTION_USE_REWARD,
+};
+use crate::pool;
+use crate::question_type::{self, QuestionType};
+use crate::shop;
 use crate::state::State;
 use anyhow::Result;
 use chrono::TimeZone;
-use rand::Rng;
 use std;
+use std::cmp::Ordering;
 
-// pub mod stock;
+pub mod blackjack;
+pub mod stock;
 
 pub async fn handle_yo_command(
     client: &mut tmi::Client,
@@ -116,41 +127,150 @@ pub async fn handle_points_command(
     messaging::reply_to(client, msg, &reply).await
 }
 
+pub async fn handle_daily_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => return Ok(()),
+    };
+
+    let config = bot_state.daily;
+    let now = chrono::Utc::now().naive_utc();
+    let claim = match daily::claim(&config, chatter.last_daily_at, chatter.daily_streak, now) {
+        Ok(claim) => claim,
+        Err(daily::ClaimError::Cooldown(remaining)) => {
+            let reply = format!(
+                "You already claimed your daily points! Come back in {}.",
+                daily::format_duration(remaining)
+            );
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+
+    chatter::add_points(&chatter.twitch_id, claim.payout);
+    db::update_daily(&chatter.twitch_id, now, claim.streak);
+
+    let mut reply = format!(
+        "You claimed {} point(s)! Day {} of your streak ({}%).",
+        claim.payout,
+        claim.streak,
+        config.multiplier(claim.streak)
+    );
+    if let Some(lost) = claim.lost_streak {
+        reply.push_str(&format!(" Your {} day streak ran out.", lost));
+    }
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_streak_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => return Ok(()),
+    };
+
+    let config = bot_state.daily;
+    let now = chrono::Utc::now().naive_utc();
+    let status = daily::status(&config, chatter.last_daily_at, chatter.daily_streak, now);
+    let reply = match (status.next_claim_in, status.expires_in) {
+        _ if status.streak == 0 => "You don't have a streak, type !daily to start one!".to_string(),
+        (Some(next), _) => format!(
+            "You're on a {} day streak! Your next !daily is in {}.",
+            status.streak,
+            daily::format_duration(next)
+        ),
+        (None, Some(expires)) => format!(
+            "You're on a {} day streak! Type !daily within {} to keep it.",
+            status.streak,
+            daily::format_duration(expires)
+        ),
+        (None, None) => unreachable!("A streak that hasn't run out has time left on it"),
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+// What !commands lists.
+pub const COMMANDS: &[&str] = &[
+    // Point Casino
+    "!gamble",
+    "!seed",
+    "!verify",
+    "!casinostats",
+    "!gamblestats",
+    "!limits",
+    "!selfexclude",
+    "!blackjack",
+    "!hit",
+    "!stand",
+    "!double",
+    // Random stuff
+    "!yo",
+    "!lurk",
+    "!lurktime",
+    "!lurkers",
+    // Git related commands
+    "!github",
+    "!botrepo",
+    // Duel related commands
+    "!listcategories",
+    "!addquestion",
+    "!addcategory",
+    "!review",
+    "!points",
+    "!shop",
+    "!buy",
+    "!stocks",
+    "!sell",
+    "!portfolio",
+    "!bid",
+    "!ask",
+    "!cancel",
+    "!orders",
+    "!book",
+    "!trades",
+    "!chart",
+    "!pump",
+    "!dump",
+    "!addstock",
+    "!daily",
+    "!streak",
+    "!challenge",
+    "!duel",
+    "!accept",
+    "!kda",
+    "!report",
+    "!reports",
+    "!confirmreport",
+    "!dismissreport",
+    "!upvote",
+    "!downvote",
+    "!ranking",
+    "!top3",
+    "!gift",
+    "!setpoints",
+    "!pool",
+    "!ticket",
+    "!selectPoolWinner",
+];
+
 pub async fn handle_commands_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
 ) -> anyhow::Result<(), anyhow::Error> {
-    let commands = vec![
-        // Point Casino
-        "!gamble",
-        // Random stuff
-        "!yo",
-        "!lurk",
-        "!lurktime",
-        "!lurkers",
-        // Git related commands
-        "!github",
-        "!botrepo",
-        // Duel related commands
-        "!listcategories",
-        "!addquestion",
-        "!addcategory",
-        "!points",
-        "!challenge",
-        "!duel",
-        "!accept",
-        "!kda",
-        "!ranking",
-        "!top3",
-        "!gift",
-        "!setpoints",
-        "!pool",
-        "!selectPoolWinner",
-    ];
     messaging::reply_to(
         client,
         msg,
-        &list_with_title("Available commands:", &commands, ItemSeparator::Comma),
+        &list_with_title(
+            "Available commands:",
+            &COMMANDS.to_vec(),
+            ItemSeparator::Comma,
+        ),
     )
     .await
 }
@@ -194,6 +314,7 @@ pub async fn handle_accept_command(
     let mut duel = match bot_state.get_duel(&key.to_lowercase()) {
         Some(mut d) => {
             d.accept_duel();
+            market::record_event(market::DUEL_INDEX, MarketEventKind::Duel, d.points);
             d
         }
         None => {
@@ -201,7 +322,8 @@ pub async fn handle_accept_command(
         }
     };
 
-    duel.ask_question(client, &msg).await;
+    duel.ask_question(client, msg, bot_state.question_source.as_mut())
+        .await;
 
     return Ok(());
 }
@@ -334,6 +456,24 @@ pub async fn handle_duel_command(
         }
     };
 
+    // optional difficulty tier and generated question topic, in either order:
+    // !duel @<user> <points> <easy|medium|hard> <topic>
+    let mut difficulty = None;
+    let mut topic = None;
+    for arg in cmd_iter.by_ref().take(2) {
+        if let Ok(d) = arg.parse::<Difficulty>() {
+            difficulty = Some(d);
+        } else if let Ok(t) = arg.parse::<Topic>() {
+            topic = Some(t);
+        } else {
+            let reply = format!(
+                "Difficulty must be easy, medium or hard, and topic one of: {}.",
+                generator::topic_names().join(", ")
+            );
+            return messaging::send_duel_err(&challenger, client, msg, &reply).await;
+        }
+    }
+
     if cmd_iter.next().is_some() {
         return messaging::send_duel_err(&challenger, client, &msg, "Too many arguments!").await;
     }
@@ -344,6 +484,8 @@ pub async fn handle_duel_command(
         &challenger_chatter.twitch_id,
         &challenged_chatter.twitch_id,
         points,
+        difficulty,
+        topic,
     );
 
     bot_state.save_duel(&curr_duel);
@@ -393,12 +535,29 @@ pub async fn handle_answer_command(
         return Ok(());
     }
 
+    let kind = duel.question_type();
+    if !kind.is_valid_guess(&response, duel.option_count()) {
+        let reply = format!("@{} answer with {}", responder, kind.answer_format());
+        messaging::reply_to(client, msg, &reply).await?;
+        return Ok(());
+    }
+
+    if let (QuestionType::Numeric, Some(answer)) = (
+        kind,
+        duel.answer.as_deref().and_then(question_type::parse_number),
+    ) {
+        let guess = question_type::parse_number(&response).unwrap_or_default();
+        return handle_numeric_answer(client, msg, bot_state, duel, &responder, guess, answer)
+            .await;
+    }
+
     if duel.is_winner(&response) {
         // determine which player owns the current messsage
         // compare twitch id of challenger to the twitch id of the message sender
         // TODO: Add not null constraint on challenger_id and challenged_id
         if responder == duel.challenger {
             duel.award_winner(
+                bot_state.question_source.as_mut(),
                 &responder,
                 duel.challenger_id.clone().unwrap().as_str(),
                 duel.challenged_id.clone().unwrap().as_str(),
@@ -406,13 +565,14 @@ pub async fn handle_answer_command(
             let reply_msg = format!(
                 "Correct! @{} won {} Points & @{} lost {} Points!",
                 responder,
-                duel.points,
+                duel.payout(),
                 duel.challenged,
                 duel.points / 2
             );
             messaging::reply_to(client, &msg, &reply_msg).await?;
         } else if responder == duel.challenged {
             duel.award_winner(
+                bot_state.question_source.as_mut(),
                 &responder,
                 duel.challenged_id.clone().unwrap().as_str(),
                 duel.challenger_id.clone().unwrap().as_str(),
@@ -420,7 +580,7 @@ pub async fn handle_answer_command(
             let reply_msg = format!(
                 "Correct! @{} won {} Points & @{} lost {} Points!",
                 responder,
-                duel.points,
+                duel.payout(),
                 duel.challenger,
                 duel.points / 2
             );
@@ -431,6 +591,7 @@ pub async fn handle_answer_command(
 
         // send message to inform user of point deduction and incorrect guess.
         // max 5 guesses before duel is over, and challenger lose wagered points
+        duel.record_wrong_guess(bot_state.question_source.as_mut());
         if responder == duel.challenger {
             if duel.challenger_guesses > 0 {
                 duel.decrement_challenger_guesses()
@@ -480,6 +641,98 @@ pub async fn handle_answer_command(
     Ok(())
 }
 
+// Numeric questions aren't over on a wrong guess, the closest of the two guesses
+// wins once both duelists have locked one in.
+async fn handle_numeric_answer(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+    mut duel: models::Duel,
+    responder: &str,
+    guess: f64,
+    answer: f64,
+) -> anyhow::Result<(), anyhow::Error> {
+    let is_challenger = responder == duel.challenger;
+    db::set_numeric_guess(duel.id, is_challenger, guess);
+    if is_challenger {
+        duel.decrement_challenger_guesses();
+        duel.challenger_numeric_guess = Some(guess);
+    } else {
+        duel.decrement_challenged_guesses();
+        duel.challenged_numeric_guess = Some(guess);
+    }
+
+    let challenger_wins = if guess == answer {
+        Some(is_challenger)
+    } else {
+        match (duel.challenger_numeric_guess, duel.challenged_numeric_guess) {
+            (Some(challenger_guess), Some(challenged_guess)) => {
+                match question_type::closest(answer, challenger_guess, challenged_guess) {
+                    Ordering::Less => Some(true),
+                    Ordering::Greater => Some(false),
+                    Ordering::Equal => None,
+                }
+            }
+            _ => {
+                let other = if is_challenger {
+                    &duel.challenged
+                } else {
+                    &duel.challenger
+                };
+                let reply = format!(
+                    "@{} locked in {}! Waiting on @{} to guess",
+                    responder, guess, other
+                );
+                messaging::reply_to(client, msg, &reply).await?;
+                return Ok(());
+            }
+        }
+    };
+
+    let reply = match challenger_wins {
+        Some(challenger_wins) => {
+            let (winner, winner_id, loser, loser_id) = if challenger_wins {
+                (
+                    duel.challenger.clone(),
+                    duel.challenger_id.clone().unwrap(),
+                    duel.challenged.clone(),
+                    duel.challenged_id.clone().unwrap(),
+                )
+            } else {
+                (
+                    duel.challenged.clone(),
+                    duel.challenged_id.clone().unwrap(),
+                    duel.challenger.clone(),
+                    duel.challenger_id.clone().unwrap(),
+                )
+            };
+            duel.award_winner(
+                bot_state.question_source.as_mut(),
+                &winner,
+                &winner_id,
+                &loser_id,
+            );
+            format!(
+                "The answer was {}! @{} was closest and won {} Points & @{} lost {} Points!",
+                answer,
+                winner,
+                duel.payout(),
+                loser,
+                duel.points / 2
+            )
+        }
+        None => {
+            duel.complete_duel(bot_state);
+            format!(
+                "The answer was {}! @{} and @{} were just as close, it's a tie!",
+                answer, duel.challenger, duel.challenged
+            )
+        }
+    };
+    messaging::reply_to(client, msg, &reply).await?;
+    Ok(())
+}
+
 pub async fn handle_repeat_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
@@ -654,160 +907,723 @@ pub async fn handle_addquestion_command(
         }
     };
 
-    if chatter.points < 5000 {
+    if chatter.points < QUESTION_COST {
         return messaging::reply_to(
           client,
           &msg,
-          "You don't have enough points to add a question! It costs 5000 points to add a question.",
+          &format!("You don't have enough points to add a question! It costs {} points to add a question.", QUESTION_COST),
       )
       .await;
     }
 
-    Question::new(question, answer, &category, &chatter);
-    chatter::subtract_points(&chatter.twitch_id, 5000);
+    // mods don't need a second opinion, everyone else waits in the review queue
+    let status = if chatter::is_moderator(msg) {
+        QuestionStatus::Approved
+    } else {
+        QuestionStatus::Pending
+    };
+    let question = Question::new(question, answer, &category, &chatter, status, QUESTION_COST);
+    chatter::subtract_points(&chatter.twitch_id, QUESTION_COST);
+
+    if question.is_pending() {
+        let reply = format!(
+            "Question #{} submitted for review! You'll get your {} points back if it's rejected, and {} points every time it's asked.",
+            question.id, QUESTION_COST, QUESTION_USE_REWARD
+        );
+        return messaging::reply_to(client, msg, &reply).await;
+    }
     messaging::reply_to(client, &msg, "Question Added!").await
 }
 
-pub async fn handle_github_command(
+pub async fn handle_review_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
 ) -> anyhow::Result<(), anyhow::Error> {
-    let github_url = "You can check out ToluAfo's projects at https://github.com/tolu-afo";
+    // !review - show the oldest questions waiting for a mod
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can review questions!").await;
+    }
 
-    messaging::reply_to(client, msg, github_url).await
+    let pending = db::get_pending_questions();
+    let entries = pending
+        .iter()
+        .take(3)
+        .map(|q| format!("#{} {} => {}", q.id, q.question, q.answer))
+        .collect::<Vec<String>>();
+
+    let title = format!(
+        "Pending questions ({}), use !approve <id> or !reject <id> <reason>:",
+        pending.len()
+    );
+    messaging::reply_to(
+        client,
+        msg,
+        &list_with_title(&title, &entries, ItemSeparator::Dash),
+    )
+    .await
 }
 
-pub async fn handle_botrepo_command(
+fn parse_pending_question(id: Option<&str>) -> Result<Question, &'static str> {
+    let id = match id.map(|id| id.trim_start_matches('#').parse::<i32>()) {
+        Some(Ok(id)) => id,
+        _ => return Err("Provide a question id, use !review to see pending questions."),
+    };
+    match db::get_question(id) {
+        Some(question) if question.is_pending() => Ok(question),
+        Some(_) => Err("That question has already been reviewed!"),
+        None => Err("Question not found!"),
+    }
+}
+
+pub async fn handle_approve_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
 ) -> anyhow::Result<(), anyhow::Error> {
-    let bot_repo_url = "You can check out my source code at https://github.com/tolu-afo/TTB";
-    messaging::reply_to(client, msg, bot_repo_url).await
+    // !approve <id>
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can approve questions!").await;
+    }
+
+    let mut cmd_iter = msg.text().split_ascii_whitespace();
+    cmd_iter.next();
+    let mut question = match parse_pending_question(cmd_iter.next()) {
+        Ok(question) => question,
+        Err(err) => return messaging::reply_to(client, msg, err).await,
+    };
+
+    if !question.approve() {
+        return messaging::reply_to(client, msg, "That question was already reviewed!").await;
+    }
+    let reply = format!(
+        "Question #{} approved and added to the duel pool!",
+        question.id
+    );
+    messaging::reply_to(client, msg, &reply).await
 }
 
-pub async fn handle_gamble_command(
+pub async fn handle_reject_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
 ) -> anyhow::Result<(), anyhow::Error> {
-    // roll two dice
+    // !reject <id> <reason>
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can reject questions!").await;
+    }
 
-    let mut cmd_iter = msg.text().split(' ');
+    let mut cmd_iter = msg.text().split_ascii_whitespace();
     cmd_iter.next();
-    let wager = match cmd_iter.next() {
-        Some(w) => match w.parse::<i64>() {
-            Ok(w) => w,
-            Err(_) => {
-                return messaging::reply_to(
-                    client,
-                    msg,
-                    "Invalid wager! Format: '!gamble <points>'",
-                )
+    let mut question = match parse_pending_question(cmd_iter.next()) {
+        Ok(question) => question,
+        Err(err) => return messaging::reply_to(client, msg, err).await,
+    };
+
+    let reason = cmd_iter.collect::<Vec<&str>>().join(" ");
+    if reason.is_empty() {
+        return messaging::reply_to(client, msg, "Format: !reject <id> <reason>").await;
+    }
+
+    if !question.reject(&reason) {
+        return messaging::reply_to(client, msg, "That question was already reviewed!").await;
+    }
+    let reply = format!(
+        "Question #{} rejected: {}. Refunded {} points to the submitter.",
+        question.id, reason, question.points_paid
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+// How long after a duel its question can still be reported or voted on.
+const QUESTION_FEEDBACK_WINDOW_MINUTES: i64 = 10;
+
+fn recent_duel_question(twitch_id: &str) -> Option<(models::Duel, Question)> {
+    let since = chrono::Utc::now().naive_utc()
+        - chrono::Duration::minutes(QUESTION_FEEDBACK_WINDOW_MINUTES);
+    let duel = db::get_last_asked_duel(twitch_id, since)?;
+    let question = db::get_question(duel.question_id?)?;
+    Some((duel, question))
+}
+
+pub async fn handle_report_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !report [reason] - flag the question from the last duel
+    let mut cmd_iter = msg.text().split_ascii_whitespace();
+    cmd_iter.next();
+    let reason = cmd_iter.collect::<Vec<&str>>().join(" ");
+
+    let (duel, mut question) = match recent_duel_question(msg.sender().id()) {
+        Some(found) => found,
+        None => {
+            return messaging::reply_to(client, msg, "There's no recent duel question to report!")
                 .await;
-            }
-        },
+        }
+    };
+
+    let reason = if reason.is_empty() {
+        None
+    } else {
+        Some(reason.as_str())
+    };
+    let reply = if question.report(Some(duel.id), msg.sender().id(), reason) {
+        format!(
+            "Thanks! Question #{} has been pulled from rotation until a mod takes a look.",
+            question.id
+        )
+    } else {
+        format!("Thanks! Question #{} has been reported.", question.id)
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_vote_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    upvote: bool,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !upvote / !downvote - rate the question from the last duel
+    let (duel, question) = match recent_duel_question(msg.sender().id()) {
+        Some(found) => found,
+        None => {
+            return messaging::reply_to(client, msg, "There's no recent duel question to vote on!")
+                .await;
+        }
+    };
+
+    if duel.status == "accepted" {
+        return messaging::reply_to(client, msg, "Wait until the duel is over to vote!").await;
+    }
+
+    let (upvotes, downvotes) = question.vote(msg.sender().id(), upvote);
+    let reply = format!(
+        "Vote counted! Question #{} is at +{}/-{}",
+        question.id, upvotes, downvotes
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_reports_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !reports - mods see which questions chat has flagged
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can see reports!").await;
+    }
+
+    let entries = db::get_reported_questions()
+        .iter()
+        .take(3)
+        .filter_map(|(id, count)| db::get_question(*id).map(|q| (q, count)))
+        .map(|(q, count)| {
+            format!(
+                "#{} ({} report(s), {}) {} => {}",
+                q.id, count, q.status, q.question, q.answer
+            )
+        })
+        .collect::<Vec<String>>();
+
+    messaging::reply_to(
+        client,
+        msg,
+        &list_with_title(
+            "Reported questions, use !confirmreport <id> or !dismissreport <id>:",
+            &entries,
+            ItemSeparator::Dash,
+        ),
+    )
+    .await
+}
+
+pub async fn handle_resolve_report_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    confirmed: bool,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !confirmreport <id> / !dismissreport <id>
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can resolve reports!").await;
+    }
+
+    let mut cmd_iter = msg.text().split_ascii_whitespace();
+    cmd_iter.next();
+    let mut question = match cmd_iter
+        .next()
+        .and_then(|id| id.trim_start_matches('#').parse::<i32>().ok())
+        .and_then(db::get_question)
+    {
+        Some(question) => question,
         None => {
             return messaging::reply_to(
                 client,
                 msg,
-                "You need to provide a wager! Format: '!gamble <points>'",
+                "Question not found! Use !reports to see ids.",
             )
             .await;
         }
     };
 
-    let chatter = match db::get_chatter(&msg.sender().id()) {
-        Some(chatter) => chatter,
-        None => {
-            return messaging::reply_to(client, msg, "Chatter not found!").await;
-        }
-    };
+    if db::count_open_reports(question.id) == 0 {
+        return messaging::reply_to(client, msg, "That question has no open reports!").await;
+    }
 
-    if chatter.points < wager {
-        return messaging::reply_to(
-            client,
-            msg,
-            "You don't have enough points to wager that much!",
+    let reply = if confirmed {
+        let voided = question.confirm_reports();
+        format!(
+            "Question #{} retired. {} duel(s) voided and points refunded.",
+            question.id, voided
         )
-        .await;
-    }
+    } else {
+        question.dismiss_reports();
+        format!("Reports on question #{} dismissed.", question.id)
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
 
-    fn dice_roll() -> i32 {
-        let mut rng = rand::thread_rng();
-        rng.gen_range(1..7)
-    }
+pub async fn handle_github_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    let github_url = "You can check out ToluAfo's projects at https://github.com/tolu-afo";
 
-    messaging::reply_to(client, msg, "Rolling the dice!").await?;
+    messaging::reply_to(client, msg, github_url).await
+}
 
-    let roll1 = dice_roll();
-    let roll2 = dice_roll();
+pub async fn handle_botrepo_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    let bot_repo_url = "You can check out my source code at https://github.com/tolu-afo/TTB";
+    messaging::reply_to(client, msg, bot_repo_url).await
+}
 
-    let sum = roll1 + roll2;
+pub async fn handle_gamble_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !gamble <game> <wager> [bet], or !gamble <wager> for dice
+    let casino = &bot_state.casino;
+    let args = msg.text().split_whitespace().skip(1).collect::<Vec<&str>>();
+    let (game, args) = match args.split_first() {
+        Some((wager, _)) if wager.parse::<i64>().is_ok() => (casino.game("dice"), args.as_slice()),
+        Some((name, rest)) => (casino.game(name), rest),
+        None => {
+            let games = casino
+                .games()
+                .map(|game| format!("{} ({:.1}% edge)", game.name(), game.house_edge() * 100.0))
+                .collect::<Vec<String>>();
+            let reply = list_with_title(
+                "🎰 Use !gamble <game> <wager>. Games:",
+                &games,
+                ItemSeparator::Dash,
+            );
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+    let game = match game {
+        Some(game) => game,
+        None => {
+            return messaging::reply_to(client, msg, "No such game! Use !gamble to see them.")
+                .await;
+        }
+    };
+    let usage = format!(
+        "Use !gamble {} <wager> {}",
+        game.name(),
+        game.bet_usage().unwrap_or_default()
+    );
+    let (wager, bet) = match args.split_first() {
+        Some((wager, bet)) => match wager.parse::<i64>() {
+            Ok(wager) => (wager, bet),
+            Err(_) => return messaging::reply_to(client, msg, usage.trim_end()).await,
+        },
+        None => return messaging::reply_to(client, msg, usage.trim_end()).await,
+    };
 
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => {
+            return messaging::reply_to(client, msg, "Chatter not found!").await;
+        }
+    };
+    if let Err(err) = casino.check_wager(&chatter, wager) {
+        return messaging::reply_to(client, msg, &format!("Can't gamble: {}!", err)).await;
+    }
+
+    let (play, draw) = match casino::fair::play(game, &chatter, wager, bet) {
+        Ok(played) => played,
+        Err(err) => {
+            let reply = format!("{}! {}", err, usage.trim_end());
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
     let broadcaster_id = std::env::var("BROADCASTER_ID").expect("BROADCASTER_ID must be set");
+    let settlement = casino.settle(msg.sender().id(), &broadcaster_id, wager, play.payout);
+    let bet = casino::stats::record_bet(
+        chatter.id,
+        game.name(),
+        wager,
+        &bet.join(" "),
+        Some(&draw),
+        &play,
+        &settlement,
+    );
+    let net = settlement.net;
 
-    let reply = match sum {
-        12 => {
-            let points = wager * 4;
-            chatter::add_points(&msg.sender().id(), points);
-            format!(
-                "You rolled a {} and a {}! You win {} points!",
-                roll1, roll2, points
-            )
+    let reply = match net.cmp(&0) {
+        Ordering::Greater => format!("{} You win {} points!", play.result, net),
+        Ordering::Less => format!(
+            "{} You lose {} points! They've been added to the losers pool",
+            play.result, -net
+        ),
+        Ordering::Equal => format!("{} No points won or lost!", play.result),
+    };
+    let reply = format!("{} (bet #{})", reply, bet.id);
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_seed_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !seed shows the seeds, !seed <client seed> changes yours, mods !seed rotate
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "Chatter not found!").await,
+    };
+    match msg.text().split_whitespace().nth(1) {
+        Some(word) if word.eq_ignore_ascii_case("rotate") => {
+            if !chatter::is_moderator(msg) {
+                return messaging::reply_to(client, msg, "Only mods can rotate the server seed!")
+                    .await;
+            }
+            let reply = match casino::fair::rotate_seed() {
+                Some(old) => format!(
+                    "Server seed #{} was {} (hash {}), bets made with it can be checked with !verify now. New seed hash: {}",
+                    old.id,
+                    old.seed,
+                    old.seed_hash,
+                    casino::fair::active_seed().seed_hash
+                ),
+                None => format!(
+                    "New seed hash: {}",
+                    casino::fair::active_seed().seed_hash
+                ),
+            };
+            messaging::reply_to(client, msg, &reply).await
         }
-        11 | 10 | 9 => {
-            let points = wager * 2;
-            chatter::add_points(&msg.sender().id(), points);
-            format!(
-                "You rolled a {} and a {}! You win {} points!",
-                roll1, roll2, points
-            )
+        Some(seed) => {
+            if !casino::fair::valid_client_seed(seed) {
+                let reply = format!(
+                    "Client seeds are one word of up to {} letters!",
+                    casino::fair::MAX_CLIENT_SEED_LENGTH
+                );
+                return messaging::reply_to(client, msg, &reply).await;
+            }
+            db::set_client_seed(msg.sender().id(), seed);
+            let reply = format!("Your client seed is now {}", seed);
+            messaging::reply_to(client, msg, &reply).await
         }
-        8 => {
-            let points = wager;
-            chatter::add_points(&msg.sender().id(), points);
-            format!(
-                "You rolled a {} and a {}! You win {} points!",
-                roll1, roll2, points
-            )
+        None => {
+            let seed = casino::fair::active_seed();
+            let reply = format!(
+                "🎲 Server seed #{} hash: {} ({} bets so far). Your client seed: {} (change it with !seed <word>)",
+                seed.id,
+                seed.seed_hash,
+                seed.nonce,
+                casino::fair::client_seed(&chatter)
+            );
+            messaging::reply_to(client, msg, &reply).await
         }
-        2 => {
-            let points = wager;
-            chatter::subtract_points(&msg.sender().id(), points);
-            db::add_pool_points(points / 2);
-            chatter::add_points(&broadcaster_id, points / 2);
-            format!(
-                "Snake Eyes! You lose {} points! They've been added to the losers pool",
-                points
-            )
+    }
+}
+
+pub async fn handle_verify_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !verify <bet id>
+    let bet = match msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .map(|id| id.trim_start_matches('#'))
+        .and_then(|id| id.parse::<i32>().ok())
+    {
+        Some(id) => db::get_bet(id),
+        None => return messaging::reply_to(client, msg, "Use !verify <bet id>").await,
+    };
+    let bet = match bet {
+        Some(bet) => bet,
+        None => return messaging::reply_to(client, msg, "No such bet!").await,
+    };
+    let seed = match bet.seed_id.and_then(db::get_casino_seed) {
+        Some(seed) => seed,
+        None => {
+            let reply = format!(
+                "Bet #{} was a {} hand, it wasn't drawn from the seeds",
+                bet.id, bet.game
+            );
+            return messaging::reply_to(client, msg, &reply).await;
         }
-        7 => {
-            let points = wager / 4;
-            chatter::subtract_points(&msg.sender().id(), points);
-            db::add_pool_points(points / 2);
-            chatter::add_points(&broadcaster_id, points / 2);
+    };
+    let seeds = format!(
+        "client seed {}, nonce {}, server seed #{} hash {}",
+        bet.client_seed.as_deref().unwrap_or_default(),
+        bet.nonce.unwrap_or_default(),
+        seed.id,
+        seed.seed_hash
+    );
+    if seed.revealed_at.is_none() {
+        let reply = format!(
+            "Bet #{} was made with {}. That seed is still secret, it can be checked once a mod rotates it with !seed rotate",
+            bet.id, seeds
+        );
+        return messaging::reply_to(client, msg, &reply).await;
+    }
+
+    let replayed = bot_state
+        .casino
+        .game(&bet.game)
+        .ok_or_else(|| anyhow::anyhow!("the bot doesn't have {} anymore", bet.game))
+        .and_then(|game| casino::fair::replay(game, &bet, &seed));
+    let reply = match replayed {
+        Ok(play) if casino::fair::checks_out(&play, &bet, &seed) => {
             format!(
-                "You rolled a {} and a {}! You lose {} points! They've been added to the losers pool",
-                roll1, roll2, points
+                "✅ Bet #{} checks out: {} paid {} for {}. Server seed {}, {}",
+                bet.id, play.result, bet.payout, bet.wager, seed.seed, seeds
             )
         }
-        6 | 5 | 4 | 3 => {
-            let points = wager / 2;
-            chatter::subtract_points(&msg.sender().id(), points);
-            db::add_pool_points(points / 2);
-            chatter::add_points(&broadcaster_id, points / 2);
+        Ok(play) => format!(
+            "❌ Bet #{} doesn't check out! It says {} and the seeds say {}",
+            bet.id, bet.result, play.result
+        ),
+        Err(err) => format!("Couldn't replay bet #{}: {}", bet.id, err),
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_casinostats_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !casinostats [days], all time by default
+    let days = msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .and_then(|d| d.parse::<i64>().ok());
+    let games = db::get_bet_totals_by_game(casino::stats::since(days));
+    let mut total = casino::stats::Totals::default();
+    for (_, totals) in &games {
+        total.merge(totals);
+    }
+    let entries = games
+        .iter()
+        .map(|(name, totals)| {
+            let expected = bot_state
+                .casino
+                .expected_return(name)
+                .map(|expected| format!(" ({:.1}% expected)", expected * 100.0))
+                .unwrap_or_default();
             format!(
-                "You rolled a {} and a {}! You lose {} points! They've been added to the losers pool",
-                roll1, roll2, points
+                "{}: {:.1}% returned{}",
+                name,
+                totals.observed_return().unwrap_or_default() * 100.0,
+                expected
             )
-        }
-        _ => format!(
-            "You rolled a {} and a {}! No points won or lost!",
-            roll1, roll2
-        ),
+        })
+        .collect::<Vec<String>>();
+    let title = format!(
+        "🎰 {}: {} bets, the house is {} {} points (losers pool got {}, broadcaster {})",
+        days.map_or("All time".to_string(), |d| format!("Last {} days", d)),
+        total.bets,
+        if total.house_profit() < 0 {
+            "down"
+        } else {
+            "up"
+        },
+        total.house_profit().abs(),
+        total.pool,
+        total.broadcaster
+    );
+    let reply = list_with_title(&title, &entries, ItemSeparator::Dash);
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_gamblestats_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !gamblestats [@user]
+    let chatter = match msg.text().split_whitespace().nth(1) {
+        Some(name) => db::get_chatter_by_username(name.trim_start_matches('@')),
+        None => db::get_chatter(msg.sender().id()),
+    };
+    let chatter = match chatter {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "No chatter with that name!").await,
+    };
+    let totals = db::get_bet_totals_for(chatter.id);
+    let reply = if totals.bets == 0 {
+        format!("{} hasn't gambled yet!", chatter.username)
+    } else {
+        format!(
+            "{} has made {} bets, wagered {} points and is {} {} points overall",
+            chatter.username,
+            totals.bets,
+            totals.wagered,
+            if totals.net() < 0 { "down" } else { "up" },
+            totals.net().abs()
+        )
     };
     messaging::reply_to(client, msg, &reply).await
 }
 
+fn format_limit(limit: Option<i64>) -> String {
+    limit.map_or("none".to_string(), |limit| limit.to_string())
+}
+
+pub async fn handle_selfexclude_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !selfexclude <duration>, e.g. 12h, 7d or 2w
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "Chatter not found!").await,
+    };
+    let duration =
+        match msg
+            .text()
+            .split_whitespace()
+            .nth(1)
+            .and_then(casino::limits::parse_duration)
+        {
+            Some(duration) => duration,
+            None => return messaging::reply_to(
+                client,
+                msg,
+                "Use !selfexclude <duration>, like 12h, 7d or 2w, to take a break from gambling",
+            )
+            .await,
+        };
+    // a break can be made longer, but only a mod can cut it short
+    let until = chrono::Utc::now().naive_utc() + duration;
+    let until = db::get_gambling_limit(chatter.id)
+        .and_then(|limit| limit.excluded_until)
+        .map_or(until, |excluded_until| excluded_until.max(until));
+    db::set_excluded_until(chatter.id, Some(until));
+    let reply = format!(
+        "You can't gamble until {} UTC. Take care! Only a mod can lift this early.",
+        until.format("%Y-%m-%d %H:%M")
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_limits_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !limits [@user]
+    let chatter = match msg.text().split_whitespace().nth(1) {
+        Some(name) => db::get_chatter_by_username(name.trim_start_matches('@')),
+        None => db::get_chatter(msg.sender().id()),
+    };
+    let chatter = match chatter {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "No chatter with that name!").await,
+    };
+    let overrides = db::get_gambling_limit(chatter.id);
+    let limits = bot_state.casino.limits.for_chatter(overrides.as_ref());
+    let now = chrono::Utc::now().naive_utc();
+    let losses = bot_state.casino.losses(chatter.id, &limits, now);
+    let mut reply = format!(
+        "{}'s gambling limits: max wager {}, session losses {} (lost {}), daily losses {} (lost {})",
+        chatter.username,
+        format_limit(limits.max_wager),
+        format_limit(limits.session_loss),
+        losses.session,
+        format_limit(limits.daily_loss),
+        losses.day
+    );
+    if let Some(until) = overrides
+        .and_then(|overrides| overrides.excluded_until)
+        .filter(|until| *until > now)
+    {
+        reply.push_str(&format!(
+            ", excluded until {} UTC",
+            until.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_setlimit_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !setlimit @user <wager|session|daily> <points|default>
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can set gambling limits!").await;
+    }
+    let usage = "Use !setlimit @user <wager|session|daily> <points|default>";
+    let args = msg.text().split_whitespace().skip(1).collect::<Vec<&str>>();
+    let (name, kind, limit) = match args.as_slice() {
+        [name, kind, limit] => (name.trim_start_matches('@'), kind, limit),
+        _ => return messaging::reply_to(client, msg, usage).await,
+    };
+    let kind = match kind.to_lowercase().parse::<casino::limits::LimitKind>() {
+        Ok(kind) => kind,
+        Err(_) => return messaging::reply_to(client, msg, usage).await,
+    };
+    let limit = match *limit {
+        "default" => None,
+        limit => match limit.parse::<i64>().ok().filter(|limit| *limit > 0) {
+            Some(limit) => Some(limit),
+            None => return messaging::reply_to(client, msg, usage).await,
+        },
+    };
+    let chatter = match db::get_chatter_by_username(name) {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "No chatter with that name!").await,
+    };
+    db::set_gambling_limit(chatter.id, kind, limit);
+    let reply = format!(
+        "{}'s {} limit is now {}",
+        chatter.username,
+        kind.as_str(),
+        limit.map_or("the default".to_string(), |limit| limit.to_string())
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_unexclude_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !unexclude @user
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can lift a self-exclusion!").await;
+    }
+    let chatter = match msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .and_then(|name| db::get_chatter_by_username(name.trim_start_matches('@')))
+    {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "Use !unexclude @user").await,
+    };
+    db::set_excluded_until(chatter.id, None);
+    let reply = format!("{} can gamble again", chatter.username);
+    messaging::reply_to(client, msg, &reply).await
+}
+
 pub async fn handle_contribute_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
@@ -1034,6 +1850,30 @@ pub async fn handle_pool_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
 ) -> anyhow::Result<(), anyhow::Error> {
+    // !pool history lists the last pools drawn
+    if msg.text().split_whitespace().nth(1) == Some("history") {
+        let history = db::get_pool_history(5)
+            .into_iter()
+            .map(|(pool, winners)| match winners.len() {
+                0 if pool.cancelled_at.is_some() => {
+                    format!("#{} cancelled at {} points", pool.id, pool.amount)
+                }
+                0 => format!("#{} drawn at {} points", pool.id, pool.amount),
+                _ => format!(
+                    "#{} {} won {} points",
+                    pool.id,
+                    winners.join(", "),
+                    pool.amount - pool.rollover - pool.broadcaster_cut
+                ),
+            })
+            .collect::<Vec<String>>();
+        if history.is_empty() {
+            return messaging::reply_to(client, msg, "No pool has been drawn yet!").await;
+        }
+        let reply_msg = list_with_title("Last pools", &history, ItemSeparator::Dash);
+        return messaging::reply_to(client, msg, &reply_msg).await;
+    }
+
     // get current pool return statement with current pool amount and maybe a relative date.
     let pool = db::get_current_pool();
 
@@ -1043,9 +1883,22 @@ pub async fn handle_pool_command(
             let amount = pool.amount;
             let created_at = pool.created_at;
             let relative_time = helpers::relative_time_string(created_at);
+            let entries = db::get_pool_tickets(pool.id)?;
+            let draw = match pool.draw_at {
+                Some(draw_at) => format!(
+                    " It's drawn in {}.",
+                    pool::duration_words(draw_at - chrono::Utc::now().naive_utc())
+                ),
+                None => String::new(),
+            };
             let reply_msg = format!(
-                "The Loser Pool is currently {} points! It started {}",
-                amount, relative_time
+                "The Loser Pool is currently {} points with {} tickets from {} chatters! It started {}.{} Buy tickets with !ticket <n> for {} points each",
+                amount,
+                pool::total_tickets(&entries),
+                entries.len(),
+                relative_time,
+                draw,
+                pool::ticket_price()
             );
             return messaging::reply_to(client, msg, &reply_msg).await;
         }
@@ -1056,11 +1909,58 @@ pub async fn handle_pool_command(
     }
 }
 
+pub async fn handle_ticket_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !ticket [n]
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "Chatter not found!").await,
+    };
+    let count = match pool::parse_tickets(msg.text().split_whitespace().nth(1)) {
+        Some(count) => count,
+        None => return messaging::reply_to(client, msg, "Use !ticket <n>").await,
+    };
+    let price = pool::ticket_price();
+    let cost = count.saturating_mul(price);
+    // tickets are a gamble too, so the gambling limits apply
+    if let Err(err) = bot_state.casino.check_wager(&chatter, cost) {
+        return messaging::reply_to(client, msg, &format!("Can't buy tickets: {}!", err)).await;
+    }
+
+    let pool = match db::buy_pool_tickets(chatter.id, count, price) {
+        Some(pool) => pool,
+        None => {
+            let reply = format!("You need {} points for {} tickets!", cost, count);
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+    let entries = db::get_pool_tickets(pool.id)?;
+    let total = pool::total_tickets(&entries);
+    let held = entries
+        .iter()
+        .find(|(id, _)| *id == chatter.id)
+        .map_or(count, |(_, tickets)| *tickets);
+    let reply = format!(
+        "🎟️ You bought {} tickets for {} points! You hold {} of {} tickets, a {:.1}% chance at the pool of {} points",
+        count,
+        cost,
+        held,
+        total,
+        pool::odds(held, total),
+        pool.amount
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
 pub async fn handle_pool_draw_command(
     client: &mut tmi::Client,
     msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
 ) -> anyhow::Result<(), anyhow::Error> {
-    //  select a random chatter
+    //  pick a winner weighted by the tickets they hold
     //  add the pool amount to the chatters points
     //  set winner on pool
     //  create a new pool
@@ -1069,28 +1969,246 @@ pub async fn handle_pool_draw_command(
     if msg.sender().id() != broadcaster_id {
         return Ok(());
     }
-    let broadcaster = match db::get_chatter(&broadcaster_id) {
-        Some(chatter) => chatter,
-        None => {
-            return messaging::reply_to(client, msg, "Broadcaster not found!").await;
-        }
+
+    let drawn = pool::draw(&bot_state.pool_payouts, &mut rand::thread_rng())?;
+    let reply_msg = match drawn {
+        pool::Drawn::Won {
+            pool,
+            winners,
+            total,
+        } => pool::won_message(&pool, &winners, total),
+        pool::Drawn::NoTickets(_) => "No one has a ticket yet, the pool keeps growing!".to_string(),
+        pool::Drawn::NoPool => "No pool found!".to_string(),
     };
+    messaging::reply_to(client, msg, &reply_msg).await
+}
+
+pub async fn handle_pool_cancel_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    let broadcaster_id = std::env::var("BROADCASTER_ID").expect("BROADCASTER_ID must be set");
+
+    if msg.sender().id() != broadcaster_id {
+        return Ok(());
+    }
 
     let pool = match db::get_current_pool() {
         Some(pool) => pool,
+        None => return messaging::reply_to(client, msg, "No pool found!").await,
+    };
+    let reply_msg = match db::cancel_pool(pool.id) {
+        Some((pool, refunds)) => {
+            let refunded = refunds.iter().map(|ticket| ticket.points).sum::<i64>();
+            format!(
+                "The pool was cancelled, {} points refunded for {} ticket purchases and {} points carried over to the next pool",
+                refunded,
+                refunds.len(),
+                pool.amount - refunded
+            )
+        }
+        None => "That pool was already drawn!".to_string(),
+    };
+    messaging::reply_to(client, msg, &reply_msg).await
+}
+
+pub async fn handle_shop_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    let entries = db::get_shop_items()
+        .iter()
+        .map(shop::describe)
+        .collect::<Vec<String>>();
+    messaging::reply_to(
+        client,
+        msg,
+        &list_with_title(
+            "Shop, use !buy <item> to redeem:",
+            &entries,
+            ItemSeparator::Dash,
+        ),
+    )
+    .await
+}
+
+pub async fn handle_buy_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !buy <item> [what the item asks for], or !buy <symbol> <shares> for stocks
+    let args = msg.text().split_once(' ').map_or("", |(_, args)| args);
+    if let Some((symbol, market::Shares::Count(shares))) = market::parse_trade(args) {
+        if db::get_shop_item_by_name(&symbol).is_none() {
+            if let Some(stock) = db::get_stock_by_symbol(&symbol) {
+                return stock::handle_buy_stock_command(client, msg, stock, shares).await;
+            }
+        }
+    }
+    let mut cmd_iter = msg.text().splitn(3, ' ');
+    cmd_iter.next();
+    let item = match cmd_iter.next().and_then(db::get_shop_item_by_name) {
+        Some(item) => item,
         None => {
-            return messaging::reply_to(client, msg, "No pool found!").await;
+            return messaging::reply_to(client, msg, "No such item! Use !shop to see the items.")
+                .await;
         }
     };
+    let input = cmd_iter.next().map(str::trim).filter(|i| !i.is_empty());
+
+    let buyer_id = msg.sender().id();
+    let already_bought = db::count_redemptions(item.id, buyer_id);
+    if let Err(err) =
+        shop::check_purchase(&item, chatter::get_points(buyer_id), already_bought, input)
+    {
+        return messaging::reply_to(client, msg, &format!("Can't buy {}: {}!", item.name, err))
+            .await;
+    }
 
-    let winner = db::get_random_chatter(&broadcaster);
-    chatter::add_points(&winner.twitch_id, pool.amount);
-    db::update_pool_winner(pool.id, winner.id);
-    db::create_new_pool();
+    let redemption = match db::create_redemption(&models::NewRedemption {
+        item_id: item.id,
+        twitch_id: buyer_id,
+        username: &msg.sender().name(),
+        input,
+        cost: item.cost,
+    }) {
+        Some(redemption) => redemption,
+        None => {
+            let reply = format!("Can't buy {}: {}!", item.name, shop::PurchaseError::SoldOut);
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+    chatter::subtract_points(buyer_id, item.cost);
 
-    let reply_msg = format!(
-        "@{} has won the pool of {} points!",
-        winner.username, pool.amount
+    let reply = format!(
+        "You bought {} for {} point(s)! A mod will take care of it soon (#{}).",
+        item.name, item.cost, redemption.id
     );
-    messaging::reply_to(client, msg, &reply_msg).await
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_additem_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !additem <name> <cost> [stock=N] [limit=N] <description> [| <what buyers add>]
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can change the shop!").await;
+    }
+
+    let args = msg.text().split_once(' ').map_or("", |(_, args)| args);
+    let item = match shop::parse_item_args(args) {
+        Ok(args) => db::upsert_shop_item(&args.as_new_item()),
+        Err(err) => return messaging::reply_to(client, msg, &err.to_string()).await,
+    };
+    let reply = format!("Shop updated: {}", shop::describe(&item));
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_removeitem_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !removeitem <name> - takes it off sale, past redemptions keep pointing at it
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can change the shop!").await;
+    }
+
+    let reply = match msg.text().split_ascii_whitespace().nth(1) {
+        Some(name) if db::disable_shop_item(name) => format!("{} is no longer for sale.", name),
+        _ => "No such item! Use !shop to see the items.".to_string(),
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_redemptions_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !redemptions - the mod fulfillment queue, oldest first
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can see redemptions!").await;
+    }
+
+    let pending = db::get_pending_redemptions();
+    let entries = pending
+        .iter()
+        .take(3)
+        .map(|r| {
+            let item = db::get_shop_item(r.item_id).map_or("?".to_string(), |i| i.name);
+            match &r.input {
+                Some(input) => format!("#{} {} for @{}: {}", r.id, item, r.username, input),
+                None => format!("#{} {} for @{}", r.id, item, r.username),
+            }
+        })
+        .collect::<Vec<String>>();
+
+    let title = format!(
+        "Pending redemptions ({}), use !fulfill <id> or !refund <id>:",
+        pending.len()
+    );
+    messaging::reply_to(
+        client,
+        msg,
+        &list_with_title(&title, &entries, ItemSeparator::Dash),
+    )
+    .await
+}
+
+pub async fn handle_resolve_redemption_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    fulfilled: bool,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !fulfill <id> / !refund <id>
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can resolve redemptions!").await;
+    }
+
+    let redemption = match msg
+        .text()
+        .split_ascii_whitespace()
+        .nth(1)
+        .and_then(|id| id.trim_start_matches('#').parse::<i32>().ok())
+        .and_then(db::get_redemption)
+    {
+        Some(redemption) if redemption.is_pending() => redemption,
+        Some(_) => {
+            return messaging::reply_to(client, msg, "That redemption was already resolved!").await;
+        }
+        None => {
+            return messaging::reply_to(
+                client,
+                msg,
+                "Redemption not found! Use !redemptions to see ids.",
+            )
+            .await;
+        }
+    };
+
+    let reply = if fulfilled {
+        db::set_redemption_status(redemption.id, models::RedemptionStatus::Fulfilled);
+        format!("Redemption #{} fulfilled.", redemption.id)
+    } else {
+        db::set_redemption_status(redemption.id, models::RedemptionStatus::Refunded);
+        db::restock_shop_item(redemption.item_id);
+        chatter::add_points(&redemption.twitch_id, redemption.cost);
+        format!(
+            "Redemption #{} refunded, @{} got {} point(s) back.",
+            redemption.id, redemption.username, redemption.cost
+        )
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_lists_the_report_commands() {
+        for command in ["!report", "!reports", "!confirmreport", "!dismissreport"] {
+            assert!(COMMANDS.contains(&command), "{} isn't listed", command);
+        }
+    }
 }
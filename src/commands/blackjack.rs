@@ -0,0 +1,123 @@
+use crate::casino::blackjack::{self, Hand, Move};
+use crate::casino::Casino;
+use crate::db;
+use crate::messaging;
+use crate::models::{BlackjackHand, BlackjackStatus};
+use crate::state::State;
+
+fn describe(casino: &Casino, saved: &BlackjackHand, hand: &Hand) -> String {
+    let status = saved
+        .status
+        .parse::<BlackjackStatus>()
+        .unwrap_or(BlackjackStatus::Playing);
+    let player = blackjack::show(&hand.player);
+    if status == BlackjackStatus::Playing {
+        let double = if hand.player.len() == 2 {
+            ", !stand or !double"
+        } else {
+            " or !stand"
+        };
+        return format!(
+            "🃏 You: {} Dealer: {} ? — !hit{} (you have {}s)",
+            player,
+            blackjack::card_name(hand.dealer[0]),
+            double,
+            casino.blackjack.timeout.as_secs()
+        );
+    }
+
+    let net = saved.payout - saved.wager;
+    let outcome = match status {
+        BlackjackStatus::Blackjack => format!("Blackjack! You win {} points!", net),
+        BlackjackStatus::Won => format!("You win {} points!", net),
+        BlackjackStatus::Push => format!("Push, your {} points are back!", saved.wager),
+        _ if blackjack::hand_value(&hand.player).0 > 21 => {
+            format!("Bust! You lose {} points!", -net)
+        }
+        _ => format!("You lose {} points!", -net),
+    };
+    format!(
+        "🃏 You: {} Dealer: {} {}",
+        player,
+        blackjack::show(&hand.dealer),
+        outcome
+    )
+}
+
+pub async fn handle_blackjack_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !blackjack <wager>
+    let casino = &bot_state.casino;
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => return messaging::reply_to(client, msg, "Chatter not found!").await,
+    };
+    if let Some(saved) = db::get_blackjack_hand(chatter.id) {
+        let reply = describe(casino, &saved, &Hand::from_saved(&saved));
+        return messaging::reply_to(client, msg, &reply).await;
+    }
+
+    let wager = match msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .and_then(|w| w.parse::<i64>().ok())
+    {
+        Some(wager) => wager,
+        None => return messaging::reply_to(client, msg, "Use !blackjack <wager>").await,
+    };
+    if let Err(err) = casino.check_wager(&chatter, wager) {
+        return messaging::reply_to(client, msg, &format!("Can't play: {}!", err)).await;
+    }
+
+    let started = blackjack::start(casino, chatter.id, wager, &mut rand::thread_rng());
+    let reply = match started {
+        Some((saved, hand)) => describe(casino, &saved, &hand),
+        None => "Couldn't deal you in, try again!".to_string(),
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+// !hit, !stand and !double
+pub async fn handle_blackjack_move_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    bot_state: &mut State,
+    next: Move,
+) -> anyhow::Result<(), anyhow::Error> {
+    let casino = &bot_state.casino;
+    let chatter = match db::get_chatter(msg.sender().id()) {
+        Some(chatter) => chatter,
+        None => return Ok(()),
+    };
+    let saved = match db::get_blackjack_hand(chatter.id) {
+        Some(saved) => saved,
+        None => {
+            return messaging::reply_to(
+                client,
+                msg,
+                "You don't have a hand going, start one with !blackjack <wager>",
+            )
+            .await
+        }
+    };
+    if next == Move::Double {
+        if let Err(err) = casino.check_wager(&chatter, saved.wager) {
+            return messaging::reply_to(client, msg, &format!("Can't double: {}!", err)).await;
+        }
+    }
+
+    let mut hand = Hand::from_saved(&saved);
+    let status = match hand.play(next) {
+        Ok(status) => status,
+        Err(err) => return messaging::reply_to(client, msg, &format!("Can't: {}!", err)).await,
+    };
+    let reply = match blackjack::save(casino, &saved, &hand, status) {
+        Some(saved) => describe(casino, &saved, &hand),
+        None => "That hand is already over!".to_string(),
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
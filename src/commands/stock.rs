@@ -0,0 +1,521 @@
+use crate::chatter;
+use crate::db;
+use crate::market::{self, Shares};
+use crate::messaging;
+use crate::messaging::{list_with_title, ItemSeparator};
+use crate::models::{MarketEventKind, NewOrder, NewStock, OrderStatus, Stock};
+use crate::order_book::{self, PlaceError, Side};
+
+fn describe(stock: &Stock) -> String {
+    let change = market::to_f64(&stock.roi_percentage);
+    let arrow = if change > 0.0 {
+        "▲"
+    } else if change < 0.0 {
+        "▼"
+    } else {
+        "="
+    };
+    format!(
+        "{} {} {}{:.2}%",
+        stock.symbol,
+        stock.ticket_price,
+        arrow,
+        change.abs()
+    )
+}
+
+pub async fn handle_stocks_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    let stocks = db::get_stocks()?;
+    if stocks.is_empty() {
+        return messaging::reply_to(client, msg, "The market is closed, there are no stocks!")
+            .await;
+    }
+    let entries = stocks.iter().map(describe).collect::<Vec<String>>();
+    let reply = list_with_title(
+        "📈 Stocks (buy with !buy <symbol> <shares>):",
+        &entries,
+        ItemSeparator::Dash,
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+// !buy <symbol> <shares>, handle_buy_command sends stock purchases here.
+pub async fn handle_buy_stock_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    stock: Stock,
+    shares: i32,
+) -> anyhow::Result<(), anyhow::Error> {
+    let buyer = match db::get_chatter(msg.sender().id()) {
+        Some(buyer) => buyer,
+        None => return Ok(()),
+    };
+    let cost = market::cost(&stock.ticket_price, shares);
+    if buyer.points < cost {
+        let reply = format!(
+            "{} share(s) of {} cost {} point(s) and you have {}!",
+            shares, stock.symbol, cost, buyer.points
+        );
+        return messaging::reply_to(client, msg, &reply).await;
+    }
+
+    let order = NewOrder {
+        stock_id: stock.id,
+        owner_id: buyer.id,
+        num_shares: shares,
+        strike_price: &stock.ticket_price,
+        status: OrderStatus::Filled.as_str(),
+        remaining_shares: 0,
+        escrow: 0,
+    };
+    if !db::record_trade(&order, cost) {
+        return messaging::reply_to(client, msg, "The order didn't go through, try again!").await;
+    }
+    chatter::subtract_points(&buyer.twitch_id, cost);
+
+    let reply = format!(
+        "You bought {} share(s) of {} at {} for {} point(s)!",
+        shares, stock.symbol, stock.ticket_price, cost
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_sell_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !sell <symbol> <shares|all>
+    let args = msg.text().split_once(' ').map_or("", |(_, args)| args);
+    let (symbol, shares) = match market::parse_trade(args) {
+        Some(trade) => trade,
+        None => {
+            return messaging::reply_to(client, msg, "Use !sell <symbol> <shares|all>").await;
+        }
+    };
+    let stock = match db::get_stock_by_symbol(&symbol) {
+        Some(stock) => stock,
+        None => {
+            return messaging::reply_to(client, msg, "No such stock! Use !stocks to see them.")
+                .await;
+        }
+    };
+    let seller = match db::get_chatter(msg.sender().id()) {
+        Some(seller) => seller,
+        None => return Ok(()),
+    };
+    let holding = match db::get_holding(seller.id, stock.id) {
+        Some(holding) if holding.num_shares > 0 => holding,
+        _ => {
+            let reply = format!("You don't have any shares of {}!", stock.symbol);
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+    // shares up for sale with !ask stay there
+    let available = holding.num_shares - db::get_shares_for_sale(seller.id, stock.id);
+    let shares = match shares {
+        Shares::All if available > 0 => available,
+        Shares::Count(count) if count <= available => count,
+        _ => {
+            let reply = format!(
+                "You only have {} share(s) of {} that aren't up for sale!",
+                available, stock.symbol
+            );
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+
+    // the cost basis goes down by the average price paid for the shares sold
+    let basis_sold = holding.cost_basis * shares as i64 / holding.num_shares as i64;
+    let order = NewOrder {
+        stock_id: stock.id,
+        owner_id: seller.id,
+        num_shares: -shares,
+        strike_price: &stock.ticket_price,
+        status: OrderStatus::Filled.as_str(),
+        remaining_shares: 0,
+        escrow: 0,
+    };
+    if !db::record_trade(&order, -basis_sold) {
+        return messaging::reply_to(client, msg, "The order didn't go through, try again!").await;
+    }
+    let proceeds = market::proceeds(&stock.ticket_price, shares);
+    chatter::add_points(&seller.twitch_id, proceeds);
+
+    let reply = format!(
+        "You sold {} share(s) of {} at {} for {} point(s) ({:+})!",
+        shares,
+        stock.symbol,
+        stock.ticket_price,
+        proceeds,
+        proceeds - basis_sold
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_portfolio_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    let owner = match db::get_chatter(msg.sender().id()) {
+        Some(owner) => owner,
+        None => return Ok(()),
+    };
+    let holdings = db::get_holdings(owner.id);
+    if holdings.is_empty() {
+        return messaging::reply_to(
+            client,
+            msg,
+            "You don't own any stocks yet! Use !stocks to see the market.",
+        )
+        .await;
+    }
+
+    let mut total = 0;
+    let mut total_basis = 0;
+    let entries = holdings
+        .iter()
+        .map(|(holding, stock)| {
+            let value = market::proceeds(&stock.ticket_price, holding.num_shares);
+            total += value;
+            total_basis += holding.cost_basis;
+            format!(
+                "{} x{} worth {} ({:+})",
+                stock.symbol,
+                holding.num_shares,
+                value,
+                value - holding.cost_basis
+            )
+        })
+        .collect::<Vec<String>>();
+    let title = format!(
+        "💼 Portfolio worth {} point(s) ({:+}):",
+        total,
+        total - total_basis
+    );
+    let reply = list_with_title(&title, &entries, ItemSeparator::Dash);
+    messaging::reply_to(client, msg, &reply).await
+}
+
+// !bid <symbol> <shares> <price> and !ask <symbol> <shares> <price>
+pub async fn handle_limit_order_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    side: Side,
+) -> anyhow::Result<(), anyhow::Error> {
+    let command = match side {
+        Side::Buy => "!bid",
+        Side::Sell => "!ask",
+    };
+    let args = msg.text().split_once(' ').map_or("", |(_, args)| args);
+    let (symbol, shares, price) = match order_book::parse_limit_order(args) {
+        Some(order) => order,
+        None => {
+            let reply = format!("Use {} <symbol> <shares> <price per share>", command);
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+    let stock = match db::get_stock_by_symbol(&symbol) {
+        Some(stock) => stock,
+        None => {
+            return messaging::reply_to(client, msg, "No such stock! Use !stocks to see them.")
+                .await;
+        }
+    };
+    let owner = match db::get_chatter(msg.sender().id()) {
+        Some(owner) => owner,
+        None => return Ok(()),
+    };
+
+    let (order, execution) = match db::place_limit_order(stock.id, owner.id, side, price, shares) {
+        Ok(placed) => placed,
+        Err(err) => {
+            let reply = match err.downcast_ref::<PlaceError>() {
+                Some(err) => format!("Can't place that order: {}!", err),
+                None => {
+                    println!("An error occurred while placing order: {}", err);
+                    "The order didn't go through, try again!".to_string()
+                }
+            };
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+
+    let (traded, value) = execution
+        .fills
+        .iter()
+        .filter(|f| f.buy_order_id == order.id || f.sell_order_id == order.id)
+        .fold((0, 0), |(shares, value), f| {
+            (shares + f.shares, value + f.value)
+        });
+    let action = match side {
+        Side::Buy => "bought",
+        Side::Sell => "sold",
+    };
+    let reply = if traded == shares {
+        format!(
+            "Order #{} {} {} share(s) of {} for {} point(s)!",
+            order.id, action, traded, stock.symbol, value
+        )
+    } else if traded > 0 {
+        format!(
+            "Order #{} {} {} share(s) of {} for {} point(s), the other {} wait on the book at {}. !cancel {} to take them off.",
+            order.id,
+            action,
+            traded,
+            stock.symbol,
+            value,
+            shares - traded,
+            order.strike_price,
+            order.id
+        )
+    } else {
+        format!(
+            "Order #{} for {} share(s) of {} at {} is on the book. !cancel {} to take it off.",
+            order.id, shares, stock.symbol, order.strike_price, order.id
+        )
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_cancel_order_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !cancel <order id>
+    let order_id = match msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .and_then(|id| id.trim_start_matches('#').parse::<i32>().ok())
+    {
+        Some(id) => id,
+        None => return messaging::reply_to(client, msg, "Use !cancel <order id>").await,
+    };
+    let owner = match db::get_chatter(msg.sender().id()) {
+        Some(owner) => owner,
+        None => return Ok(()),
+    };
+
+    let reply = match db::cancel_order(order_id, owner.id) {
+        Some(order) if order.escrow > 0 => format!(
+            "Cancelled order #{}, {} point(s) are back in your balance.",
+            order.id, order.escrow
+        ),
+        Some(order) => format!("Cancelled order #{}.", order.id),
+        None => format!("You don't have an open order #{}!", order_id),
+    };
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_orders_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    let owner = match db::get_chatter(msg.sender().id()) {
+        Some(owner) => owner,
+        None => return Ok(()),
+    };
+    let orders = db::get_open_orders(owner.id);
+    if orders.is_empty() {
+        return messaging::reply_to(
+            client,
+            msg,
+            "You don't have any open orders! Place one with !bid or !ask.",
+        )
+        .await;
+    }
+    let entries = orders
+        .iter()
+        .map(|(order, stock)| {
+            format!(
+                "#{} {} {} {}/{} at {}",
+                order.id,
+                if order.is_buy() { "bid" } else { "ask" },
+                stock.symbol,
+                order.remaining_shares,
+                order.num_shares.abs(),
+                order.strike_price
+            )
+        })
+        .collect::<Vec<String>>();
+    let reply = list_with_title("📋 Your open orders:", &entries, ItemSeparator::Dash);
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_book_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !book <symbol>
+    let stock = match msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .and_then(db::get_stock_by_symbol)
+    {
+        Some(stock) => stock,
+        None => return messaging::reply_to(client, msg, "Use !book <symbol>").await,
+    };
+    let book = db::get_order_book(stock.id);
+    let levels = |side| {
+        let depth = book
+            .depth(side, 3)
+            .iter()
+            .map(|(price, shares)| format!("{}@{}", shares, order_book::from_cents(*price)))
+            .collect::<Vec<String>>();
+        if depth.is_empty() {
+            "none".to_string()
+        } else {
+            depth.join(", ")
+        }
+    };
+    let reply = format!(
+        "📖 {} bids: {} | asks: {}",
+        stock.symbol,
+        levels(Side::Buy),
+        levels(Side::Sell)
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_trades_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !trades <symbol>
+    let stock = match msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .and_then(db::get_stock_by_symbol)
+    {
+        Some(stock) => stock,
+        None => return messaging::reply_to(client, msg, "Use !trades <symbol>").await,
+    };
+    let trades = db::get_trades(stock.id, 5);
+    if trades.is_empty() {
+        let reply = format!("Nobody has traded {} on the book yet!", stock.symbol);
+        return messaging::reply_to(client, msg, &reply).await;
+    }
+    let entries = trades
+        .iter()
+        .map(|t| format!("{}@{}", t.num_shares, t.price))
+        .collect::<Vec<String>>();
+    let title = format!("🧾 Last {} trades:", stock.symbol);
+    let reply = list_with_title(&title, &entries, ItemSeparator::Dash);
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_chart_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !chart <symbol>
+    let stock = match msg
+        .text()
+        .split_whitespace()
+        .nth(1)
+        .and_then(db::get_stock_by_symbol)
+    {
+        Some(stock) => stock,
+        None => return messaging::reply_to(client, msg, "Use !chart <symbol>").await,
+    };
+    let prices = db::get_stock_prices(stock.id, 24)
+        .iter()
+        .map(|p| market::to_f64(&p.price))
+        .collect::<Vec<f64>>();
+    let (first, last) = match (prices.first(), prices.last()) {
+        (Some(first), Some(last)) if prices.len() > 1 => (first, last),
+        _ => {
+            let reply = format!("{} doesn't have a price history yet!", stock.symbol);
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+    let reply = format!(
+        "{} {} {:.2} ({:+.2}% over {} ticks)",
+        stock.symbol,
+        market::sparkline(&prices),
+        last,
+        (last - first) / first * 100.0,
+        prices.len() - 1
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+// !pump <symbol> [percent] and !dump <symbol> [percent]
+pub async fn handle_nudge_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+    kind: MarketEventKind,
+) -> anyhow::Result<(), anyhow::Error> {
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can move the market!").await;
+    }
+    let args = msg.text().split_once(' ').map_or("", |(_, args)| args);
+    let (symbol, percent) = match market::parse_nudge(args) {
+        Some(nudge) => nudge,
+        None => {
+            let reply = format!("Use !{} <symbol> [1-50 percent]", kind.as_str());
+            return messaging::reply_to(client, msg, &reply).await;
+        }
+    };
+    if market::is_index(&symbol) {
+        let reply = format!("{} only moves with what happens on stream!", symbol);
+        return messaging::reply_to(client, msg, &reply).await;
+    }
+    if db::get_stock_by_symbol(&symbol).is_none() {
+        return messaging::reply_to(client, msg, "No such stock! Use !stocks to see them.").await;
+    }
+
+    market::record_event(&symbol, kind, percent);
+    let direction = match kind {
+        MarketEventKind::Dump => "drop",
+        _ => "jump",
+    };
+    let reply = format!(
+        "{} will {} {}% on the next tick!",
+        symbol, direction, percent
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
+
+pub async fn handle_addstock_command(
+    client: &mut tmi::Client,
+    msg: &tmi::Privmsg<'_>,
+) -> anyhow::Result<(), anyhow::Error> {
+    // !addstock <symbol> <price> <name>
+    if !chatter::is_moderator(msg) {
+        return messaging::reply_to(client, msg, "Only mods can list stocks!").await;
+    }
+    let args = msg.text().split_once(' ').map_or("", |(_, args)| args);
+    let (symbol, price, name) = match market::parse_new_stock(args) {
+        Some(stock) => stock,
+        None => {
+            return messaging::reply_to(
+                client,
+                msg,
+                "Use !addstock <symbol of up to 6 letters> <price> <name>",
+            )
+            .await;
+        }
+    };
+    if db::get_stock_by_symbol(&symbol).is_some() {
+        let reply = format!("{} is already listed!", symbol);
+        return messaging::reply_to(client, msg, &reply).await;
+    }
+
+    let stock = db::create_stock(&NewStock {
+        name: &name,
+        symbol: &symbol,
+        ticket_price: market::to_price(price),
+        future_value: market::to_price(price),
+    });
+    let reply = format!(
+        "{} ({}) is now trading at {}!",
+        stock.name, stock.symbol, stock.ticket_price
+    );
+    messaging::reply_to(client, msg, &reply).await
+}
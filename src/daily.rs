@@ -0,0 +1,204 @@
+// Daily login rewards. Claiming again within a day of the cooldown running out keeps
+// the streak going, and every day of streak adds to the reward up to a cap.
+use chrono::{Duration, NaiveDateTime};
+
+use crate::helpers::env_number;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyConfig {
+    pub reward: i64,
+    // percent added to the reward for every day of streak after the first
+    pub streak_bonus: i64,
+    // streak days past this don't add any more bonus
+    pub max_bonus_days: i32,
+    pub cooldown: Duration,
+    // extra time after the day to claim in, before the streak is lost
+    pub grace: Duration,
+}
+
+impl Default for DailyConfig {
+    fn default() -> Self {
+        DailyConfig {
+            reward: 100,
+            streak_bonus: 10,
+            max_bonus_days: 10,
+            cooldown: Duration::hours(24),
+            grace: Duration::hours(6),
+        }
+    }
+}
+
+impl DailyConfig {
+    // DAILY_REWARD, DAILY_STREAK_BONUS (percent per day), DAILY_MAX_BONUS_DAYS and
+    // DAILY_GRACE_HOURS, anything unset keeps its default.
+    pub fn from_env() -> DailyConfig {
+        let defaults = DailyConfig::default();
+        DailyConfig {
+            reward: env_number("DAILY_REWARD", defaults.reward),
+            streak_bonus: env_number("DAILY_STREAK_BONUS", defaults.streak_bonus),
+            max_bonus_days: env_number("DAILY_MAX_BONUS_DAYS", defaults.max_bonus_days),
+            cooldown: defaults.cooldown,
+            grace: Duration::hours(env_number("DAILY_GRACE_HOURS", defaults.grace.num_hours())),
+        }
+    }
+
+    // The streak is kept when the next claim comes within a day of the cooldown
+    // running out, plus the grace period.
+    fn streak_window(&self) -> Duration {
+        self.cooldown + Duration::hours(24) + self.grace
+    }
+
+    // Percent of the reward paid out on the given day of a streak.
+    pub fn multiplier(&self, streak: i32) -> i64 {
+        let bonus_days = (streak.min(self.max_bonus_days) - 1).max(0) as i64;
+        100 + self.streak_bonus * bonus_days
+    }
+
+    pub fn payout(&self, streak: i32) -> i64 {
+        self.reward * self.multiplier(streak) / 100
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Claim {
+    pub streak: i32,
+    pub payout: i64,
+    // the streak that was lost, if the chatter came back too late to keep it
+    pub lost_streak: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimError {
+    // time left until the next claim
+    Cooldown(Duration),
+}
+
+pub fn claim(
+    config: &DailyConfig,
+    last_claimed: Option<NaiveDateTime>,
+    streak: i32,
+    now: NaiveDateTime,
+) -> Result<Claim, ClaimError> {
+    let elapsed = last_claimed.map(|last| now - last);
+    if let Some(elapsed) = elapsed {
+        if elapsed < config.cooldown {
+            return Err(ClaimError::Cooldown(config.cooldown - elapsed));
+        }
+    }
+
+    let kept = elapsed.is_some_and(|elapsed| elapsed < config.streak_window());
+    let new_streak = if kept { streak + 1 } else { 1 };
+    Ok(Claim {
+        streak: new_streak,
+        payout: config.payout(new_streak),
+        lost_streak: if !kept && streak > 1 {
+            Some(streak)
+        } else {
+            None
+        },
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreakStatus {
+    // 0 once the streak has run out
+    pub streak: i32,
+    // time left until !daily can be claimed again, None if it can be claimed now
+    pub next_claim_in: Option<Duration>,
+    // time left to claim before the streak is lost
+    pub expires_in: Option<Duration>,
+}
+
+pub fn status(
+    config: &DailyConfig,
+    last_claimed: Option<NaiveDateTime>,
+    streak: i32,
+    now: NaiveDateTime,
+) -> StreakStatus {
+    let Some(last) = last_claimed else {
+        return StreakStatus {
+            streak: 0,
+            next_claim_in: None,
+            expires_in: None,
+        };
+    };
+    let elapsed = now - last;
+    let alive = elapsed < config.streak_window();
+    StreakStatus {
+        streak: if alive { streak } else { 0 },
+        next_claim_in: (elapsed < config.cooldown).then(|| config.cooldown - elapsed),
+        expires_in: alive.then(|| config.streak_window() - elapsed),
+    }
+}
+
+// "5h 12m", or "12m" when it's under an hour.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = (duration.num_seconds() + 59) / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours: i64) -> NaiveDateTime {
+        chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc() + Duration::hours(hours)
+    }
+
+    #[test]
+    fn test_first_claim_starts_streak() {
+        let config = DailyConfig::default();
+        let claim = claim(&config, None, 0, at(0)).unwrap();
+        assert_eq!(1, claim.streak);
+        assert_eq!(100, claim.payout);
+        assert_eq!(None, claim.lost_streak);
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let config = DailyConfig::default();
+        assert_eq!(
+            Err(ClaimError::Cooldown(Duration::hours(4))),
+            claim(&config, Some(at(0)), 1, at(20))
+        );
+        assert!(claim(&config, Some(at(0)), 1, at(24)).is_ok());
+    }
+
+    #[test]
+    fn test_streak_grace_and_loss() {
+        let config = DailyConfig::default();
+        // the next day, late in the grace period, and too late
+        assert_eq!(4, claim(&config, Some(at(0)), 3, at(30)).unwrap().streak);
+        assert_eq!(4, claim(&config, Some(at(0)), 3, at(53)).unwrap().streak);
+        let late = claim(&config, Some(at(0)), 3, at(54)).unwrap();
+        assert_eq!(1, late.streak);
+        assert_eq!(Some(3), late.lost_streak);
+    }
+
+    #[test]
+    fn test_multiplier_is_capped() {
+        let config = DailyConfig::default();
+        assert_eq!(100, config.payout(1));
+        assert_eq!(150, config.payout(6));
+        assert_eq!(190, config.payout(10));
+        assert_eq!(190, config.payout(30));
+    }
+
+    #[test]
+    fn test_status() {
+        let config = DailyConfig::default();
+        let fresh = status(&config, Some(at(0)), 2, at(1));
+        assert_eq!(2, fresh.streak);
+        assert_eq!(Some(Duration::hours(23)), fresh.next_claim_in);
+        assert_eq!(Some(Duration::hours(53)), fresh.expires_in);
+        let expired = status(&config, Some(at(0)), 2, at(60));
+        assert_eq!(0, expired.streak);
+        assert_eq!(None, expired.next_claim_in);
+        assert_eq!(None, expired.expires_in);
+        assert_eq!("5h 12m", format_duration(Duration::minutes(312)));
+        assert_eq!("1m", format_duration(Duration::seconds(30)));
+    }
+}
@@ -6,11 +6,24 @@ use dotenv::dotenv;
 use log::info;
 
 use crate::models::{
-    AcceptedDuel, Category, Chatter, Duel, LosersPool, Lurker, NewAcceptedDuel, NewCategory,
-    NewChatter, NewDuel, NewLurker, NewPool, NewQuestion, Question,
+    AcceptedDuel, Bet, BlackjackHand, BlackjackStatus, CasinoSeed, Category, Chatter, Duel,
+    GamblingLimit, Holding, LosersPool, Lurker, MarketEvent, NewAcceptedDuel, NewBet,
+    NewBlackjackHand, NewCasinoSeed, NewCategory, NewChatter, NewDuel, NewLurker, NewMarketEvent,
+    NewOrder, NewPool, NewPoolTicket, NewPoolWinner, NewQuestion, NewQuestionAsk,
+    NewQuestionReport, NewQuestionVote, NewRedemption, NewShopItem, NewStock, NewStockPrice,
+    NewTrade, Order, OrderStatus, PoolTicket, PoolWinner, Question, QuestionAsk, QuestionReport,
+    QuestionStatus, Redemption, RedemptionStatus, ShopItem, Stock, StockPrice, Trade,
 };
 
+use crate::casino::blackjack::Hand;
+use crate::casino::limits::LimitKind;
+use crate::casino::stats::Totals;
 use crate::chatter::on_new_chatter;
+use crate::difficulty::Difficulty;
+use crate::generator::{GeneratedQuestion, Topic};
+use crate::order_book::{self, BookOrder, Execution, OrderBook, PlaceError, Side};
+use crate::pool::Payouts;
+use crate::question_type::QuestionType;
 
 pub fn establish_connection() -> PgConnection {
     dotenv().ok();
@@ -20,6 +33,16 @@ pub fn establish_connection() -> PgConnection {
         .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
 }
 
+// Like establish_connection, for the background tasks that log a missing database
+// instead of panicking.
+pub fn try_establish_connection() -> anyhow::Result<PgConnection> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL")?;
+
+    PgConnection::establish(&database_url)
+        .map_err(|err| anyhow::anyhow!("Error connecting to {}: {}", database_url, err))
+}
+
 pub fn create_chatter(conn: &mut PgConnection, twitch_id: &str, username: &str) -> Chatter {
     use crate::schema::chatters;
 
@@ -73,6 +96,25 @@ pub fn get_chatter_by_username(username: &str) -> Option<Chatter> {
     db_get_chatter_by_username(&mut establish_connection(), username)
 }
 
+fn db_get_chatter_by_id(conn: &mut PgConnection, id: i32) -> Option<Chatter> {
+    use crate::schema::chatters::dsl::chatters;
+
+    let chatter = chatters
+        .find(id)
+        .select(Chatter::as_select())
+        .first(conn)
+        .optional();
+
+    chatter.unwrap_or_else(|_| {
+        println!("An error occurred while fetching chatter #{}", id);
+        None
+    })
+}
+
+pub fn get_chatter_by_id(id: i32) -> Option<Chatter> {
+    db_get_chatter_by_id(&mut establish_connection(), id)
+}
+
 fn update_last_seen(conn: &mut PgConnection, chatter_id: i32) {
     use crate::schema::chatters::dsl::{chatters, id, last_seen};
     use diesel::dsl;
@@ -138,6 +180,27 @@ pub fn update_points(id: &str, new_points: i64) {
     db_update_points(&mut establish_connection(), id, new_points)
 }
 
+// One statement, so points given or taken at the same time all count. Nobody goes
+// below -1000. False if there's no chatter with the id.
+fn db_add_points(conn: &mut PgConnection, id: &str, delta: i64) -> bool {
+    use crate::schema::chatters::dsl::{chatters, points, twitch_id};
+    use diesel::sql_types::BigInt;
+    diesel::define_sql_function!(fn greatest(a: BigInt, b: BigInt) -> BigInt);
+
+    diesel::update(chatters.filter(twitch_id.eq(id)))
+        .set(points.eq(greatest(points + delta, -1000)))
+        .execute(conn)
+        .unwrap_or_else(|err| {
+            println!("Error adding {} points to {}: {}", delta, id, err);
+            0
+        })
+        > 0
+}
+
+pub fn add_points(id: &str, delta: i64) -> bool {
+    db_add_points(&mut establish_connection(), id, delta)
+}
+
 fn db_update_wins(conn: &mut PgConnection, id: &str, new_wins: i32) {
     use crate::schema::chatters::dsl::{chatters, twitch_id, wins};
     diesel::update(chatters.filter(twitch_id.eq(id)))
@@ -162,6 +225,56 @@ pub fn update_losses(id: &str, losses: i32) {
     db_update_losses(&mut establish_connection(), id, losses);
 }
 
+fn db_update_daily(
+    conn: &mut PgConnection,
+    id: &str,
+    claimed_at: chrono::NaiveDateTime,
+    streak: i32,
+) {
+    use crate::schema::chatters::dsl::{chatters, daily_streak, last_daily_at, twitch_id};
+
+    diesel::update(chatters.filter(twitch_id.eq(id)))
+        .set((last_daily_at.eq(claimed_at), daily_streak.eq(streak)))
+        .execute(conn)
+        .expect("Daily streak should be i32");
+}
+
+pub fn update_daily(id: &str, claimed_at: chrono::NaiveDateTime, streak: i32) {
+    db_update_daily(&mut establish_connection(), id, claimed_at, streak)
+}
+
+fn db_get_chatters_by_twitch_ids(
+    conn: &mut PgConnection,
+    ids: &[String],
+) -> QueryResult<Vec<Chatter>> {
+    use crate::schema::chatters::dsl::{chatters, twitch_id};
+
+    chatters
+        .filter(twitch_id.eq_any(ids))
+        .select(Chatter::as_select())
+        .load::<Chatter>(conn)
+}
+
+pub fn get_chatters_by_twitch_ids(ids: &[String]) -> anyhow::Result<Vec<Chatter>> {
+    Ok(db_get_chatters_by_twitch_ids(
+        &mut try_establish_connection()?,
+        ids,
+    )?)
+}
+
+fn db_add_lurk_time(conn: &mut PgConnection, ids: &[String], seconds: i32) -> QueryResult<usize> {
+    use crate::schema::chatters::dsl::{chatters, lurk_time, twitch_id};
+
+    diesel::update(chatters.filter(twitch_id.eq_any(ids)))
+        .set(lurk_time.eq(lurk_time + seconds))
+        .execute(conn)
+}
+
+pub fn add_lurk_time(ids: &[String], seconds: i32) -> anyhow::Result<()> {
+    db_add_lurk_time(&mut try_establish_connection()?, ids, seconds)?;
+    Ok(())
+}
+
 fn db_update_lurk_time(conn: &mut PgConnection, id: &str, new_lurk_time: i32) {
     use crate::schema::chatters::dsl::{chatters, lurk_time, twitch_id};
     diesel::update(chatters.filter(twitch_id.eq(id)))
@@ -174,45 +287,18 @@ pub fn update_lurk_time(id: &str, new_lurk_time: i32) {
     db_update_lurk_time(&mut establish_connection(), id, new_lurk_time);
 }
 
-fn db_create_duel(
-    conn: &mut PgConnection,
-    challenger: &str,
-    challenged: &str,
-    challenger_id: &str,
-    challenged_id: &str,
-    points: i64,
-) -> Duel {
+fn db_create_duel(conn: &mut PgConnection, new_duel: &NewDuel) -> Duel {
     use crate::schema::duels;
-    let new_duel = NewDuel {
-        challenger,
-        challenged,
-        challenger_id: challenger_id,
-        challenged_id: challenged_id,
-        points,
-    };
 
     diesel::insert_into(duels::table)
-        .values(&new_duel)
+        .values(new_duel)
         .returning(Duel::as_returning())
         .get_result(conn)
         .expect("Error saving new duel")
 }
 
-pub fn create_duel(
-    challenger: &str,
-    challenged: &str,
-    challenger_id: &str,
-    challenged_id: &str,
-    points: i64,
-) -> Duel {
-    db_create_duel(
-        &mut establish_connection(),
-        challenger,
-        challenged,
-        challenger_id,
-        challenged_id,
-        points,
-    )
+pub fn create_duel(new_duel: &NewDuel) -> Duel {
+    db_create_duel(&mut establish_connection(), new_duel)
 }
 
 fn db_get_duel(conn: &mut PgConnection, id: i32) -> Option<Duel> {
@@ -270,17 +356,98 @@ pub fn get_accepted_duel(responder: &str) -> Option<AcceptedDuel> {
     db_get_accepted_duel(&mut establish_connection(), responder)
 }
 
-fn db_set_question_duel(conn: &mut PgConnection, id: i32, question: &str, answer: &str) {
-    use crate::schema::duels::dsl::{answer as duel_answer, duels, question as duel_question};
+fn db_set_question_duel(
+    conn: &mut PgConnection,
+    id: i32,
+    question: &Question,
+    difficulty: &str,
+    options: &[String],
+    guesses: i32,
+) {
+    use crate::schema::duels::dsl::{
+        alternate_answers as duel_alternate_answers, answer as duel_answer, challenged_guesses,
+        challenger_guesses, difficulty as duel_difficulty, duels, options as duel_options,
+        question as duel_question, question_id as duel_question_id, question_kind,
+    };
 
     diesel::update(duels.find(id))
-        .set((duel_question.eq(question), duel_answer.eq(answer)))
+        .set((
+            duel_question.eq(&question.question),
+            duel_answer.eq(&question.answer),
+            duel_question_id.eq(question.id),
+            duel_difficulty.eq(difficulty),
+            question_kind.eq(question.question_type().as_str()),
+            duel_options.eq(options),
+            duel_alternate_answers.eq(&question.alternate_answers),
+            challenger_guesses.eq(guesses),
+            challenged_guesses.eq(guesses),
+        ))
         .execute(conn)
         .expect("Winner should be a valid twitch id");
 }
 
-pub fn set_question_duel(id: i32, question: &str, answer: &str) {
-    db_set_question_duel(&mut establish_connection(), id, question, answer);
+pub fn set_question_duel(
+    id: i32,
+    question: &Question,
+    difficulty: &str,
+    options: &[String],
+    guesses: i32,
+) {
+    db_set_question_duel(
+        &mut establish_connection(),
+        id,
+        question,
+        difficulty,
+        options,
+        guesses,
+    );
+}
+
+fn db_set_generated_question_duel(
+    conn: &mut PgConnection,
+    id: i32,
+    question: &GeneratedQuestion,
+    topic: Topic,
+    options: &[String],
+    guesses: i32,
+) {
+    use crate::schema::duels::dsl::{
+        alternate_answers as duel_alternate_answers, answer as duel_answer, challenged_guesses,
+        challenger_guesses, difficulty as duel_difficulty, duels, options as duel_options,
+        question as duel_question, question_kind, topic as duel_topic,
+    };
+
+    diesel::update(duels.find(id))
+        .set((
+            duel_question.eq(&question.question),
+            duel_answer.eq(&question.answer),
+            duel_difficulty.eq(question.difficulty.as_str()),
+            duel_topic.eq(topic.as_str()),
+            question_kind.eq(question.kind.as_str()),
+            duel_options.eq(options),
+            duel_alternate_answers.eq(&question.alternate_answers),
+            challenger_guesses.eq(guesses),
+            challenged_guesses.eq(guesses),
+        ))
+        .execute(conn)
+        .expect("Error saving generated question");
+}
+
+pub fn set_generated_question_duel(
+    id: i32,
+    question: &GeneratedQuestion,
+    topic: Topic,
+    options: &[String],
+    guesses: i32,
+) {
+    db_set_generated_question_duel(
+        &mut establish_connection(),
+        id,
+        question,
+        topic,
+        options,
+        guesses,
+    );
 }
 
 fn db_complete_duel(conn: &mut PgConnection, id: i32, winner: &str, status: &str) {
@@ -360,6 +527,58 @@ pub fn decrement_guesses(id: i32, is_challenger: bool) {
     db_decrement_guesses(&mut establish_connection(), id, is_challenger);
 }
 
+fn db_set_numeric_guess(conn: &mut PgConnection, id: i32, is_challenger: bool, guess: f64) {
+    use crate::schema::duels::dsl::{challenged_numeric_guess, challenger_numeric_guess, duels};
+    if is_challenger {
+        diesel::update(duels.find(id))
+            .set(challenger_numeric_guess.eq(guess))
+            .execute(conn)
+            .expect("Error saving numeric guess");
+    } else {
+        diesel::update(duels.find(id))
+            .set(challenged_numeric_guess.eq(guess))
+            .execute(conn)
+            .expect("Error saving numeric guess");
+    }
+}
+
+pub fn set_numeric_guess(id: i32, is_challenger: bool, guess: f64) {
+    db_set_numeric_guess(&mut establish_connection(), id, is_challenger, guess);
+}
+
+fn db_get_last_asked_duel(
+    conn: &mut PgConnection,
+    participant: Option<&str>,
+    since: chrono::NaiveDateTime,
+) -> Option<Duel> {
+    use crate::schema::duels::dsl::{challenged_id, challenger_id, duels, question_id, updated_at};
+    let mut query = duels
+        .filter(question_id.is_not_null())
+        .filter(updated_at.gt(since))
+        .into_boxed();
+    if let Some(id) = participant {
+        query = query.filter(challenger_id.eq(id).or(challenged_id.eq(id)));
+    }
+    let duel = query
+        .order(updated_at.desc())
+        .select(Duel::as_select())
+        .first::<Duel>(conn)
+        .optional();
+
+    duel.unwrap_or_else(|_| {
+        println!("An error occurred while fetching the last asked duel");
+        None
+    })
+}
+
+// The most recent duel with a question that `twitch_id` played in, or failing
+// that the most recent one anyone in chat could have seen.
+pub fn get_last_asked_duel(twitch_id: &str, since: chrono::NaiveDateTime) -> Option<Duel> {
+    let conn = &mut establish_connection();
+    db_get_last_asked_duel(conn, Some(twitch_id), since)
+        .or_else(|| db_get_last_asked_duel(conn, None, since))
+}
+
 fn db_get_top_duelists(conn: &mut PgConnection) -> Vec<Chatter> {
     use crate::schema::chatters::dsl::{chatters, wins};
     chatters
@@ -466,22 +685,39 @@ fn db_create_question(
     answer: &str,
     submitter_id: i32,
     category_id: i32,
+    status: QuestionStatus,
+    points_paid: i64,
 ) -> Question {
     let new_question = NewQuestion {
         question,
         answer,
         submitter_id,
         category_id,
+        status: status.as_str(),
+        points_paid,
+        alternate_answers: &[],
+        authored_difficulty: None,
+        source: None,
+        incorrect_answers: &[],
+        kind: QuestionType::FreeText.as_str(),
     };
 
+    db_insert_question(conn, &new_question)
+}
+
+fn db_insert_question(conn: &mut PgConnection, new_question: &NewQuestion) -> Question {
     use crate::schema::questions;
     diesel::insert_into(questions::table)
-        .values(&new_question)
+        .values(new_question)
         .returning(Question::as_returning())
         .get_result(conn)
         .expect("Error saving new question")
 }
 
+pub fn insert_question(new_question: &NewQuestion) -> Question {
+    db_insert_question(&mut establish_connection(), new_question)
+}
+
 pub fn create_question(
     question: &str,
     answer: &str,
@@ -494,10 +730,102 @@ pub fn create_question(
         answer,
         submitter_id,
         category_id,
+        QuestionStatus::Approved,
+        0,
+    )
+}
+
+pub fn submit_question(
+    question: &str,
+    answer: &str,
+    submitter_id: i32,
+    category_id: i32,
+    status: QuestionStatus,
+    points_paid: i64,
+) -> Question {
+    db_create_question(
+        &mut establish_connection(),
+        question,
+        answer,
+        submitter_id,
+        category_id,
+        status,
+        points_paid,
     )
 }
 
-fn _db_get_question(conn: &mut PgConnection, id: i32) -> Option<Question> {
+fn db_get_pending_questions(conn: &mut PgConnection) -> Vec<Question> {
+    use crate::schema::questions::dsl::{created_at, questions, status};
+    questions
+        .filter(status.eq(QuestionStatus::Pending.as_str()))
+        .order(created_at)
+        .select(Question::as_select())
+        .load::<Question>(conn)
+        .expect("Error loading pending questions")
+}
+
+pub fn get_pending_questions() -> Vec<Question> {
+    db_get_pending_questions(&mut establish_connection())
+}
+
+fn db_update_question_status(
+    conn: &mut PgConnection,
+    id: i32,
+    new_status: QuestionStatus,
+    note: Option<&str>,
+) {
+    use crate::schema::questions::dsl::{questions, review_note, status};
+    diesel::update(questions.find(id))
+        .set((status.eq(new_status.as_str()), review_note.eq(note)))
+        .execute(conn)
+        .expect("Question ID should be i32");
+}
+
+pub fn update_question_status(id: i32, status: QuestionStatus, note: Option<&str>) {
+    db_update_question_status(&mut establish_connection(), id, status, note);
+}
+
+fn db_review_question(
+    conn: &mut PgConnection,
+    id: i32,
+    new_status: QuestionStatus,
+    note: Option<&str>,
+) -> bool {
+    use crate::schema::chatters::dsl::{chatters, points, twitch_id};
+    use crate::schema::questions::dsl::{questions, review_note, status};
+
+    conn.transaction(|conn| {
+        // only a pending question can be reviewed, so it's refunded once at most
+        let reviewed = diesel::update(questions.find(id))
+            .filter(status.eq(QuestionStatus::Pending.as_str()))
+            .set((status.eq(new_status.as_str()), review_note.eq(note)))
+            .returning(Question::as_returning())
+            .get_result(conn)
+            .optional()?;
+        let question = match reviewed {
+            Some(question) => question,
+            None => return Ok(false),
+        };
+        if new_status == QuestionStatus::Rejected && question.points_paid > 0 {
+            diesel::update(chatters.filter(twitch_id.eq(question.submitter_id.to_string())))
+                .set(points.eq(points + question.points_paid))
+                .execute(conn)?;
+        }
+        Ok(true)
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while reviewing question {}: {}", id, err);
+        false
+    })
+}
+
+// Approves or rejects a pending question, refunding what the submitter paid when it's
+// rejected. False if it wasn't pending anymore.
+pub fn review_question(id: i32, status: QuestionStatus, note: Option<&str>) -> bool {
+    db_review_question(&mut establish_connection(), id, status, note)
+}
+
+fn db_get_question(conn: &mut PgConnection, id: i32) -> Option<Question> {
     use crate::schema::questions::dsl::questions;
     let question = questions
         .find(id)
@@ -510,8 +838,8 @@ fn _db_get_question(conn: &mut PgConnection, id: i32) -> Option<Question> {
     })
 }
 
-pub fn _get_question(id: i32) -> Option<Question> {
-    _db_get_question(&mut establish_connection(), id)
+pub fn get_question(id: i32) -> Option<Question> {
+    db_get_question(&mut establish_connection(), id)
 }
 
 fn db_get_questions(conn: &mut PgConnection) -> Vec<Question> {
@@ -525,16 +853,16 @@ pub fn get_questions() -> Vec<Question> {
     db_get_questions(&mut establish_connection())
 }
 
-fn db_update_times_asked(conn: &mut PgConnection, id: i32, new_times_asked: i32) {
+fn db_increment_times_asked(conn: &mut PgConnection, id: i32) {
     use crate::schema::questions::dsl::{questions, times_asked};
     diesel::update(questions.find(id))
-        .set(times_asked.eq(new_times_asked))
+        .set(times_asked.eq(times_asked + 1))
         .execute(conn)
         .expect("Times asked should be i32");
 }
 
-pub fn update_times_asked(id: i32, new_times_asked: i32) {
-    db_update_times_asked(&mut establish_connection(), id, new_times_asked);
+pub fn increment_times_asked(id: i32) {
+    db_increment_times_asked(&mut establish_connection(), id);
 }
 
 fn db_update_times_not_answered(conn: &mut PgConnection, id: i32) {
@@ -549,6 +877,158 @@ pub fn update_times_not_answered(id: i32) {
     db_update_times_not_answered(&mut establish_connection(), id);
 }
 
+fn db_update_times_answered_correctly(conn: &mut PgConnection, id: i32) {
+    use crate::schema::questions::dsl::{questions, times_answered_correctly};
+    diesel::update(questions.find(id))
+        .set(times_answered_correctly.eq(times_answered_correctly + 1))
+        .execute(conn)
+        .expect("Times answered correctly should be i32");
+}
+
+pub fn update_times_answered_correctly(id: i32) {
+    db_update_times_answered_correctly(&mut establish_connection(), id);
+}
+
+fn db_update_times_guessed_wrong(conn: &mut PgConnection, id: i32) {
+    use crate::schema::questions::dsl::{questions, times_guessed_wrong};
+    diesel::update(questions.find(id))
+        .set(times_guessed_wrong.eq(times_guessed_wrong + 1))
+        .execute(conn)
+        .expect("Times guessed wrong should be i32");
+}
+
+pub fn update_times_guessed_wrong(id: i32) {
+    db_update_times_guessed_wrong(&mut establish_connection(), id);
+}
+
+fn db_create_question_report(
+    conn: &mut PgConnection,
+    question_id: i32,
+    duel_id: Option<i32>,
+    reporter_id: &str,
+    reason: Option<&str>,
+) {
+    use crate::schema::question_reports;
+    let new_report = NewQuestionReport {
+        question_id,
+        duel_id,
+        reporter_id,
+        reason,
+    };
+
+    // one report per chatter per question, repeat reports are ignored
+    diesel::insert_into(question_reports::table)
+        .values(&new_report)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .expect("Error saving question report");
+}
+
+pub fn create_question_report(
+    question_id: i32,
+    duel_id: Option<i32>,
+    reporter_id: &str,
+    reason: Option<&str>,
+) {
+    db_create_question_report(
+        &mut establish_connection(),
+        question_id,
+        duel_id,
+        reporter_id,
+        reason,
+    );
+}
+
+fn db_get_open_reports(conn: &mut PgConnection, id: i32) -> Vec<QuestionReport> {
+    use crate::schema::question_reports::dsl::{
+        created_at, question_id, question_reports, resolved,
+    };
+    question_reports
+        .filter(question_id.eq(id))
+        .filter(resolved.eq(false))
+        .order(created_at)
+        .select(QuestionReport::as_select())
+        .load::<QuestionReport>(conn)
+        .expect("Error loading question reports")
+}
+
+pub fn get_open_reports(question_id: i32) -> Vec<QuestionReport> {
+    db_get_open_reports(&mut establish_connection(), question_id)
+}
+
+pub fn count_open_reports(question_id: i32) -> i64 {
+    db_get_open_reports(&mut establish_connection(), question_id).len() as i64
+}
+
+// Question ids with unresolved reports, most reported first.
+fn db_get_reported_questions(conn: &mut PgConnection) -> Vec<(i32, i64)> {
+    use crate::schema::question_reports::dsl::{question_id, question_reports, resolved};
+    use diesel::dsl::count_star;
+    question_reports
+        .filter(resolved.eq(false))
+        .group_by(question_id)
+        .select((question_id, count_star()))
+        .order(count_star().desc())
+        .load::<(i32, i64)>(conn)
+        .expect("Error loading reported questions")
+}
+
+pub fn get_reported_questions() -> Vec<(i32, i64)> {
+    db_get_reported_questions(&mut establish_connection())
+}
+
+fn db_resolve_reports(conn: &mut PgConnection, id: i32) {
+    use crate::schema::question_reports::dsl::{question_id, question_reports, resolved};
+    diesel::update(question_reports.filter(question_id.eq(id)))
+        .set(resolved.eq(true))
+        .execute(conn)
+        .expect("Question ID should be i32");
+}
+
+pub fn resolve_reports(question_id: i32) {
+    db_resolve_reports(&mut establish_connection(), question_id);
+}
+
+fn db_upsert_question_vote(conn: &mut PgConnection, question_id: i32, voter_id: &str, vote: i16) {
+    use crate::schema::question_votes::dsl::{
+        question_id as vote_question_id, question_votes, value, voter_id as vote_voter_id,
+    };
+    let new_vote = NewQuestionVote {
+        question_id,
+        voter_id,
+        value: vote,
+    };
+
+    // chatters can change their mind, but only get one vote per question
+    diesel::insert_into(question_votes)
+        .values(&new_vote)
+        .on_conflict((vote_question_id, vote_voter_id))
+        .do_update()
+        .set(value.eq(vote))
+        .execute(conn)
+        .expect("Error saving question vote");
+}
+
+pub fn upsert_question_vote(question_id: i32, voter_id: &str, vote: i16) {
+    db_upsert_question_vote(&mut establish_connection(), question_id, voter_id, vote);
+}
+
+fn db_get_question_votes(conn: &mut PgConnection, id: i32) -> (i64, i64) {
+    use crate::schema::question_votes::dsl::{question_id, question_votes, value};
+    let votes = question_votes
+        .filter(question_id.eq(id))
+        .select(value)
+        .load::<i16>(conn)
+        .expect("Error loading question votes");
+    let upvotes = votes.iter().filter(|v| **v > 0).count() as i64;
+    (upvotes, votes.len() as i64 - upvotes)
+}
+
+// (upvotes, downvotes)
+pub fn get_question_votes(question_id: i32) -> (i64, i64) {
+    db_get_question_votes(&mut establish_connection(), question_id)
+}
+
 fn _db_create_category(conn: &mut PgConnection, name: &str, submitter_id: i32) -> Category {
     use crate::schema::categories;
     let new_category = NewCategory { name, submitter_id };
@@ -623,10 +1103,12 @@ pub fn get_categories() -> Vec<Category> {
     db_get_categories(&mut establish_connection())
 }
 
-fn db_get_random_question(conn: &mut PgConnection) -> Option<Question> {
+fn db_get_random_question(conn: &mut PgConnection, exclude: &[i32]) -> Option<Question> {
     sql_function!(fn random() -> Integer);
-    use crate::schema::questions::dsl::questions;
+    use crate::schema::questions::dsl::{id, questions, status};
     let question = questions
+        .filter(status.eq(QuestionStatus::Approved.as_str()))
+        .filter(id.ne_all(exclude))
         .order(random())
         .limit(1)
         .select(Question::as_select())
@@ -638,8 +1120,57 @@ fn db_get_random_question(conn: &mut PgConnection) -> Option<Question> {
     })
 }
 
-pub fn get_random_question() -> Option<Question> {
-    db_get_random_question(&mut establish_connection())
+pub fn get_random_question(exclude: &[i32]) -> Option<Question> {
+    db_get_random_question(&mut establish_connection(), exclude)
+}
+
+fn db_get_random_question_with_difficulty(
+    conn: &mut PgConnection,
+    tier: Difficulty,
+    exclude: &[i32],
+) -> Option<Question> {
+    use rand::seq::SliceRandom;
+
+    // difficulty is derived from the outcome counters, so filter in rust rather than sql
+    let candidates = db_get_questions(conn)
+        .into_iter()
+        .filter(|q| q.status == QuestionStatus::Approved.as_str())
+        .filter(|q| q.difficulty() == tier)
+        .filter(|q| !exclude.contains(&q.id))
+        .collect::<Vec<Question>>();
+    candidates.choose(&mut rand::thread_rng()).cloned()
+}
+
+pub fn get_random_question_with_difficulty(tier: Difficulty, exclude: &[i32]) -> Option<Question> {
+    db_get_random_question_with_difficulty(&mut establish_connection(), tier, exclude)
+}
+
+fn db_log_question_ask(conn: &mut PgConnection, ask: &NewQuestionAsk) {
+    use crate::schema::question_asks;
+    diesel::insert_into(question_asks::table)
+        .values(ask)
+        .execute(conn)
+        .expect("Error logging question ask");
+}
+
+pub fn log_question_ask(ask: &NewQuestionAsk) {
+    db_log_question_ask(&mut establish_connection(), ask);
+}
+
+fn db_get_question_asks_since(
+    conn: &mut PgConnection,
+    since: chrono::NaiveDateTime,
+) -> Vec<QuestionAsk> {
+    use crate::schema::question_asks::dsl::{asked_at, question_asks};
+    question_asks
+        .filter(asked_at.gt(since))
+        .select(QuestionAsk::as_select())
+        .load::<QuestionAsk>(conn)
+        .expect("Error loading question asks")
+}
+
+pub fn get_question_asks_since(since: chrono::NaiveDateTime) -> Vec<QuestionAsk> {
+    db_get_question_asks_since(&mut establish_connection(), since)
 }
 
 fn db_get_random_chatter(curr_chatter: &Chatter) -> Chatter {
@@ -675,24 +1206,29 @@ pub fn create_new_pool() -> i32 {
     new_pool.id
 }
 
-pub fn add_pool_points(points: i64) {
-    let conn = &mut establish_connection();
-    use crate::schema::losers_pool::dsl::{amount, id as db_id, losers_pool};
+// One statement, so points added at the same time all make it into the pool.
+fn db_add_pool_points(conn: &mut PgConnection, points: i64) -> bool {
+    use crate::schema::losers_pool::dsl::{
+        amount, cancelled_at, created_at, id as db_id, losers_pool, winner,
+    };
 
-    let pool = get_current_pool();
+    let current = diesel::alias!(crate::schema::losers_pool as current);
+    let current_id = current
+        .filter(current.field(winner).is_null())
+        .filter(current.field(cancelled_at).is_null())
+        .order(current.field(created_at).desc())
+        .select(current.field(db_id))
+        .limit(1);
+    diesel::update(losers_pool.filter(db_id.eq_any(current_id)))
+        .set(amount.eq(amount + points))
+        .execute(conn)
+        .expect("error updating pool")
+        > 0
+}
 
-    match pool {
-        Some(p) => {
-            let new_points = p.amount + points;
-            diesel::update(losers_pool)
-                .filter(db_id.eq(p.id))
-                .set(amount.eq(new_points))
-                .execute(conn)
-                .expect("error updating pool");
-        }
-        None => {
-            println!("failed to update points, pool doesn't seem to exist");
-        }
+pub fn add_pool_points(points: i64) {
+    if !db_add_pool_points(&mut establish_connection(), points) {
+        println!("failed to update points, pool doesn't seem to exist");
     }
 }
 
@@ -736,12 +1272,19 @@ pub fn update_pool_winner(id: i32, winner_id: i32) {
 }
 
 pub fn get_current_pool() -> Option<LosersPool> {
-    let conn = &mut establish_connection();
-    use crate::schema::losers_pool::dsl::{created_at, losers_pool, winner};
+    let conn = &mut match try_establish_connection() {
+        Ok(conn) => conn,
+        Err(err) => {
+            println!("failed to find pool: {}", err);
+            return None;
+        }
+    };
+    use crate::schema::losers_pool::dsl::{cancelled_at, created_at, losers_pool, winner};
 
     let pool = losers_pool
         .order(created_at.desc())
         .filter(winner.is_null())
+        .filter(cancelled_at.is_null())
         .first::<LosersPool>(conn)
         .optional();
 
@@ -750,3 +1293,1792 @@ pub fn get_current_pool() -> Option<LosersPool> {
         None
     })
 }
+
+fn db_buy_pool_tickets(
+    conn: &mut PgConnection,
+    chatter: i32,
+    count: i64,
+    price: i64,
+) -> Option<LosersPool> {
+    use crate::schema::chatters::dsl::{chatters, points};
+    use crate::schema::losers_pool::dsl::{amount, cancelled_at, created_at, losers_pool, winner};
+    use crate::schema::pool_tickets::dsl::pool_tickets;
+
+    let cost = count.checked_mul(price)?;
+    conn.transaction(|conn| {
+        let pool = match losers_pool
+            .order(created_at.desc())
+            .filter(winner.is_null())
+            .filter(cancelled_at.is_null())
+            .for_update()
+            .first::<LosersPool>(conn)
+            .optional()?
+        {
+            Some(pool) => pool,
+            None => return Ok(None),
+        };
+        let paid = diesel::update(chatters.find(chatter))
+            .filter(points.ge(cost))
+            .set(points.eq(points - cost))
+            .execute(conn)?;
+        if paid == 0 {
+            return Ok(None);
+        }
+        diesel::insert_into(pool_tickets)
+            .values(&NewPoolTicket {
+                pool_id: pool.id,
+                chatter_id: chatter,
+                tickets: count,
+                points: cost,
+            })
+            .execute(conn)?;
+        diesel::update(losers_pool.find(pool.id))
+            .set(amount.eq(amount + cost))
+            .returning(LosersPool::as_returning())
+            .get_result(conn)
+            .map(Some)
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while buying pool tickets: {}", err);
+        None
+    })
+}
+
+// Takes the tickets' price from the chatter and adds it to the current pool. None
+// when they can't afford it or there's no pool.
+pub fn buy_pool_tickets(chatter_id: i32, count: i64, price: i64) -> Option<LosersPool> {
+    db_buy_pool_tickets(&mut establish_connection(), chatter_id, count, price)
+}
+
+fn db_get_pool_tickets(conn: &mut PgConnection, pool: i32) -> QueryResult<Vec<(i32, i64)>> {
+    use crate::schema::pool_tickets::dsl::{chatter_id, pool_id, pool_tickets, tickets};
+    use bigdecimal::ToPrimitive;
+    use diesel::dsl;
+
+    pool_tickets
+        .filter(pool_id.eq(pool))
+        .group_by(chatter_id)
+        .select((chatter_id, dsl::sum(tickets)))
+        .order(chatter_id)
+        .load::<(i32, Option<bigdecimal::BigDecimal>)>(conn)
+        .map(|entries| {
+            entries
+                .into_iter()
+                .map(|(chatter, count)| (chatter, count.and_then(|c| c.to_i64()).unwrap_or(0)))
+                .collect()
+        })
+}
+
+// (chatter id, tickets) of everyone holding tickets in the pool.
+pub fn get_pool_tickets(pool_id: i32) -> anyhow::Result<Vec<(i32, i64)>> {
+    Ok(db_get_pool_tickets(
+        &mut try_establish_connection()?,
+        pool_id,
+    )?)
+}
+
+fn db_draw_pool(
+    conn: &mut PgConnection,
+    pool: i32,
+    picked: &[(i32, i64)],
+    payouts: &Payouts,
+) -> Option<(LosersPool, Vec<PoolWinner>)> {
+    use crate::schema::chatters::dsl::{chatters, points, twitch_id};
+    use crate::schema::losers_pool::dsl::{
+        broadcaster_cut, cancelled_at, losers_pool, rollover, winner,
+    };
+    use crate::schema::pool_winners::dsl::pool_winners;
+
+    let first = picked.first()?.0;
+    let broadcaster_id = env::var("BROADCASTER_ID").expect("BROADCASTER_ID must be set");
+    conn.transaction(|conn| {
+        // claiming the pool locks it, so no ticket gets bought while it's split
+        let claimed = match diesel::update(losers_pool.find(pool))
+            .filter(winner.is_null())
+            .filter(cancelled_at.is_null())
+            .set(winner.eq(first))
+            .returning(LosersPool::as_returning())
+            .get_result(conn)
+            .optional()?
+        {
+            Some(claimed) => claimed,
+            None => return Ok(None),
+        };
+        let split = payouts.split(claimed.amount, picked.len());
+        let drawn = diesel::update(losers_pool.find(pool))
+            .set((
+                rollover.eq(split.rollover),
+                broadcaster_cut.eq(split.broadcaster),
+            ))
+            .returning(LosersPool::as_returning())
+            .get_result(conn)?;
+
+        let winners = picked
+            .iter()
+            .zip(split.prizes.iter())
+            .enumerate()
+            .map(|(place, ((chatter, tickets), prize))| NewPoolWinner {
+                pool_id: pool,
+                chatter_id: *chatter,
+                place: place as i32 + 1,
+                amount: *prize,
+                tickets: *tickets,
+            })
+            .collect::<Vec<NewPoolWinner>>();
+        for new_winner in &winners {
+            diesel::update(chatters.find(new_winner.chatter_id))
+                .set(points.eq(points + new_winner.amount))
+                .execute(conn)?;
+        }
+        let winners = diesel::insert_into(pool_winners)
+            .values(&winners)
+            .returning(PoolWinner::as_returning())
+            .get_results(conn)?;
+        diesel::update(chatters.filter(twitch_id.eq(&broadcaster_id)))
+            .set(points.eq(points + split.broadcaster))
+            .execute(conn)?;
+        diesel::insert_into(losers_pool)
+            .values(NewPool {
+                amount: 100 + split.rollover,
+            })
+            .execute(conn)?;
+        Ok(Some((drawn, winners)))
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while drawing the pool: {}", err);
+        None
+    })
+}
+
+// Splits the pool between the picked (chatter id, tickets), first place first, and
+// starts a new one with the rollover. None if the pool was already drawn or
+// cancelled.
+pub fn draw_pool(
+    pool_id: i32,
+    picked: &[(i32, i64)],
+    payouts: &Payouts,
+) -> Option<(LosersPool, Vec<PoolWinner>)> {
+    db_draw_pool(&mut establish_connection(), pool_id, picked, payouts)
+}
+
+fn db_cancel_pool(conn: &mut PgConnection, pool: i32) -> Option<(LosersPool, Vec<PoolTicket>)> {
+    use crate::schema::chatters::dsl::{chatters, points};
+    use crate::schema::losers_pool::dsl::{cancelled_at, losers_pool, winner};
+    use crate::schema::pool_tickets::dsl::{pool_id, pool_tickets, refunded};
+
+    conn.transaction(|conn| {
+        let cancelled = match diesel::update(losers_pool.find(pool))
+            .filter(winner.is_null())
+            .filter(cancelled_at.is_null())
+            .set(cancelled_at.eq(diesel::dsl::now))
+            .returning(LosersPool::as_returning())
+            .get_result(conn)
+            .optional()?
+        {
+            Some(cancelled) => cancelled,
+            None => return Ok(None),
+        };
+        let refunds = diesel::update(pool_tickets)
+            .filter(pool_id.eq(pool))
+            .filter(refunded.eq(false))
+            .set(refunded.eq(true))
+            .returning(PoolTicket::as_returning())
+            .get_results(conn)?;
+        for ticket in &refunds {
+            diesel::update(chatters.find(ticket.chatter_id))
+                .set(points.eq(points + ticket.points))
+                .execute(conn)?;
+        }
+        // what the casino put in carries over to the next pool
+        let refunded_points = refunds.iter().map(|ticket| ticket.points).sum::<i64>();
+        diesel::insert_into(losers_pool)
+            .values(NewPool {
+                amount: cancelled.amount - refunded_points,
+            })
+            .execute(conn)?;
+        Ok(Some((cancelled, refunds)))
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while cancelling the pool: {}", err);
+        None
+    })
+}
+
+// Refunds every ticket of the pool and starts a new one with what's left. Returns
+// the cancelled pool and the refunded tickets.
+pub fn cancel_pool(pool_id: i32) -> Option<(LosersPool, Vec<PoolTicket>)> {
+    db_cancel_pool(&mut establish_connection(), pool_id)
+}
+
+fn db_set_pool_draw_at(conn: &mut PgConnection, pool: i32, at: Option<chrono::NaiveDateTime>) {
+    use crate::schema::losers_pool::dsl::{draw_at, losers_pool};
+
+    diesel::update(losers_pool.find(pool))
+        .set(draw_at.eq(at))
+        .execute(conn)
+        .unwrap_or_else(|err| {
+            println!("Error scheduling the draw of pool {}: {}", pool, err);
+            0
+        });
+}
+
+pub fn set_pool_draw_at(pool_id: i32, at: Option<chrono::NaiveDateTime>) {
+    db_set_pool_draw_at(&mut establish_connection(), pool_id, at)
+}
+
+fn db_get_pool_history(conn: &mut PgConnection, limit: i64) -> Vec<(LosersPool, Vec<String>)> {
+    use crate::schema::chatters::dsl::{chatters, username};
+    use crate::schema::losers_pool::dsl::{cancelled_at, losers_pool, updated_at, winner};
+    use crate::schema::pool_winners::dsl::{place, pool_id, pool_winners};
+
+    let pools = losers_pool
+        .filter(winner.is_not_null().or(cancelled_at.is_not_null()))
+        .order(updated_at.desc())
+        .limit(limit)
+        .select(LosersPool::as_select())
+        .load::<LosersPool>(conn)
+        .expect("Error loading pool history");
+    let winners = pool_winners
+        .inner_join(chatters)
+        .filter(pool_id.eq_any(pools.iter().map(|pool| pool.id)))
+        .order((pool_id, place))
+        .select((pool_id, username))
+        .load::<(i32, String)>(conn)
+        .expect("Error loading pool winners");
+    pools
+        .into_iter()
+        .map(|pool| {
+            let names = winners
+                .iter()
+                .filter(|(id, _)| *id == pool.id)
+                .map(|(_, name)| name.clone())
+                .collect();
+            (pool, names)
+        })
+        .collect()
+}
+
+// The latest pools that were drawn or cancelled, with their winners' names, first
+// place first.
+pub fn get_pool_history(limit: i64) -> Vec<(LosersPool, Vec<String>)> {
+    db_get_pool_history(&mut establish_connection(), limit)
+}
+
+fn db_upsert_shop_item(conn: &mut PgConnection, item: &NewShopItem) -> ShopItem {
+    use crate::schema::shop_items::dsl::{name, shop_items};
+
+    diesel::insert_into(shop_items)
+        .values(item)
+        .on_conflict(name)
+        .do_update()
+        .set(item)
+        .returning(ShopItem::as_returning())
+        .get_result(conn)
+        .expect("Error saving shop item")
+}
+
+pub fn upsert_shop_item(item: &NewShopItem) -> ShopItem {
+    db_upsert_shop_item(&mut establish_connection(), item)
+}
+
+fn db_get_shop_items(conn: &mut PgConnection) -> Vec<ShopItem> {
+    use crate::schema::shop_items::dsl::{cost, enabled, shop_items};
+
+    shop_items
+        .filter(enabled.eq(true))
+        .order(cost.asc())
+        .select(ShopItem::as_select())
+        .load::<ShopItem>(conn)
+        .expect("Error loading shop items")
+}
+
+// Only the items for sale.
+pub fn get_shop_items() -> Vec<ShopItem> {
+    db_get_shop_items(&mut establish_connection())
+}
+
+fn db_count_shop_items(conn: &mut PgConnection) -> i64 {
+    use crate::schema::shop_items::dsl::shop_items;
+
+    shop_items
+        .count()
+        .get_result(conn)
+        .expect("Error counting shop items")
+}
+
+// Including the ones taken off sale.
+pub fn count_shop_items() -> i64 {
+    db_count_shop_items(&mut establish_connection())
+}
+
+fn db_get_shop_item(conn: &mut PgConnection, item_id: i32) -> Option<ShopItem> {
+    use crate::schema::shop_items::dsl::shop_items;
+
+    shop_items
+        .find(item_id)
+        .select(ShopItem::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching shop item {}", item_id);
+            None
+        })
+}
+
+pub fn get_shop_item(item_id: i32) -> Option<ShopItem> {
+    db_get_shop_item(&mut establish_connection(), item_id)
+}
+
+fn db_get_shop_item_by_name(conn: &mut PgConnection, item_name: &str) -> Option<ShopItem> {
+    use crate::schema::shop_items::dsl::{name, shop_items};
+
+    shop_items
+        .filter(name.eq(item_name.to_lowercase()))
+        .select(ShopItem::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching shop item {}", item_name);
+            None
+        })
+}
+
+pub fn get_shop_item_by_name(item_name: &str) -> Option<ShopItem> {
+    db_get_shop_item_by_name(&mut establish_connection(), item_name)
+}
+
+fn db_disable_shop_item(conn: &mut PgConnection, item_name: &str) -> bool {
+    use crate::schema::shop_items::dsl::{enabled, name, shop_items};
+
+    diesel::update(shop_items.filter(name.eq(item_name.to_lowercase())))
+        .set(enabled.eq(false))
+        .execute(conn)
+        .expect("Error disabling shop item")
+        > 0
+}
+
+pub fn disable_shop_item(item_name: &str) -> bool {
+    db_disable_shop_item(&mut establish_connection(), item_name)
+}
+
+fn db_count_redemptions(conn: &mut PgConnection, item: i32, buyer_id: &str) -> i64 {
+    use crate::schema::redemptions::dsl::{item_id, redemptions, status, twitch_id};
+
+    redemptions
+        .filter(item_id.eq(item))
+        .filter(twitch_id.eq(buyer_id))
+        .filter(status.ne(RedemptionStatus::Refunded.as_str()))
+        .count()
+        .get_result(conn)
+        .expect("Error counting redemptions")
+}
+
+// Refunded redemptions don't count towards the per user limit.
+pub fn count_redemptions(item_id: i32, twitch_id: &str) -> i64 {
+    db_count_redemptions(&mut establish_connection(), item_id, twitch_id)
+}
+
+fn db_create_redemption(conn: &mut PgConnection, redemption: &NewRedemption) -> Option<Redemption> {
+    use crate::schema::redemptions;
+    use crate::schema::shop_items::dsl::{shop_items, stock};
+
+    conn.transaction(|conn| {
+        // take one off the stock, unless there's none left
+        let taken = diesel::update(shop_items.find(redemption.item_id))
+            .filter(stock.is_null().or(stock.gt(0)))
+            .set(stock.eq(stock - 1))
+            .execute(conn)?;
+        if taken == 0 {
+            return Ok(None);
+        }
+        diesel::insert_into(redemptions::table)
+            .values(redemption)
+            .returning(Redemption::as_returning())
+            .get_result(conn)
+            .map(Some)
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while saving redemption: {}", err);
+        None
+    })
+}
+
+// None if the item sold out in the meantime.
+pub fn create_redemption(redemption: &NewRedemption) -> Option<Redemption> {
+    db_create_redemption(&mut establish_connection(), redemption)
+}
+
+fn db_get_redemption(conn: &mut PgConnection, id: i32) -> Option<Redemption> {
+    use crate::schema::redemptions::dsl::redemptions;
+
+    redemptions
+        .find(id)
+        .select(Redemption::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching redemption {}", id);
+            None
+        })
+}
+
+pub fn get_redemption(id: i32) -> Option<Redemption> {
+    db_get_redemption(&mut establish_connection(), id)
+}
+
+fn db_get_pending_redemptions(conn: &mut PgConnection) -> Vec<Redemption> {
+    use crate::schema::redemptions::dsl::{created_at, redemptions, status};
+
+    redemptions
+        .filter(status.eq(RedemptionStatus::Pending.as_str()))
+        .order(created_at.asc())
+        .select(Redemption::as_select())
+        .load::<Redemption>(conn)
+        .expect("Error loading redemptions")
+}
+
+// Oldest first, that's the order mods work through them.
+pub fn get_pending_redemptions() -> Vec<Redemption> {
+    db_get_pending_redemptions(&mut establish_connection())
+}
+
+fn db_set_redemption_status(conn: &mut PgConnection, id: i32, new_status: RedemptionStatus) {
+    use crate::schema::redemptions::dsl::{redemptions, status};
+
+    diesel::update(redemptions.find(id))
+        .set(status.eq(new_status.as_str()))
+        .execute(conn)
+        .expect("Error updating redemption");
+}
+
+pub fn set_redemption_status(id: i32, status: RedemptionStatus) {
+    db_set_redemption_status(&mut establish_connection(), id, status);
+}
+
+fn db_restock_shop_item(conn: &mut PgConnection, item_id: i32) {
+    use crate::schema::shop_items::dsl::{shop_items, stock};
+
+    // unlimited items stay unlimited
+    diesel::update(shop_items.find(item_id))
+        .set(stock.eq(stock + 1))
+        .execute(conn)
+        .expect("Error restocking shop item");
+}
+
+pub fn restock_shop_item(item_id: i32) {
+    db_restock_shop_item(&mut establish_connection(), item_id);
+}
+
+fn db_create_stock(conn: &mut PgConnection, stock: &NewStock) -> Stock {
+    use crate::schema::stocks;
+
+    diesel::insert_into(stocks::table)
+        .values(stock)
+        .returning(Stock::as_returning())
+        .get_result(conn)
+        .expect("Error saving new stock")
+}
+
+pub fn create_stock(stock: &NewStock) -> Stock {
+    db_create_stock(&mut establish_connection(), stock)
+}
+
+fn db_get_stocks(conn: &mut PgConnection) -> QueryResult<Vec<Stock>> {
+    use crate::schema::stocks::dsl::{stocks, symbol};
+
+    stocks
+        .order(symbol.asc())
+        .select(Stock::as_select())
+        .load::<Stock>(conn)
+}
+
+pub fn get_stocks() -> anyhow::Result<Vec<Stock>> {
+    Ok(db_get_stocks(&mut try_establish_connection()?)?)
+}
+
+fn db_get_stock_by_symbol(conn: &mut PgConnection, stock_symbol: &str) -> Option<Stock> {
+    use crate::schema::stocks::dsl::{stocks, symbol};
+
+    stocks
+        .filter(symbol.eq(stock_symbol.to_uppercase()))
+        .select(Stock::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching stock {}", stock_symbol);
+            None
+        })
+}
+
+pub fn get_stock_by_symbol(symbol: &str) -> Option<Stock> {
+    db_get_stock_by_symbol(&mut establish_connection(), symbol)
+}
+
+fn db_update_stock_price(
+    conn: &mut PgConnection,
+    stock_id: i32,
+    price: &bigdecimal::BigDecimal,
+    target: &bigdecimal::BigDecimal,
+    change: &bigdecimal::BigDecimal,
+) {
+    use crate::schema::stocks::dsl::{future_value, roi_percentage, stocks, ticket_price};
+
+    diesel::update(stocks.find(stock_id))
+        .set((
+            ticket_price.eq(price),
+            future_value.eq(target),
+            roi_percentage.eq(change),
+        ))
+        .execute(conn)
+        .unwrap_or_else(|err| {
+            println!("Error updating the price of stock {}: {}", stock_id, err);
+            0
+        });
+}
+
+pub fn update_stock_price(
+    stock_id: i32,
+    price: &bigdecimal::BigDecimal,
+    target: &bigdecimal::BigDecimal,
+    change: &bigdecimal::BigDecimal,
+) {
+    db_update_stock_price(&mut establish_connection(), stock_id, price, target, change);
+}
+
+fn db_get_holding(conn: &mut PgConnection, owner: i32, stock: i32) -> Option<Holding> {
+    use crate::schema::holdings::dsl::{holdings, owner_id, stock_id};
+
+    holdings
+        .filter(owner_id.eq(owner))
+        .filter(stock_id.eq(stock))
+        .select(Holding::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching holding");
+            None
+        })
+}
+
+pub fn get_holding(owner_id: i32, stock_id: i32) -> Option<Holding> {
+    db_get_holding(&mut establish_connection(), owner_id, stock_id)
+}
+
+fn db_get_holdings(conn: &mut PgConnection, owner: i32) -> Vec<(Holding, Stock)> {
+    use crate::schema::holdings::dsl::{holdings, num_shares, owner_id};
+    use crate::schema::stocks::dsl::{stocks, symbol};
+
+    holdings
+        .inner_join(stocks)
+        .filter(owner_id.eq(owner))
+        .filter(num_shares.gt(0))
+        .order(symbol.asc())
+        .select((Holding::as_select(), Stock::as_select()))
+        .load::<(Holding, Stock)>(conn)
+        .expect("Error loading holdings")
+}
+
+// Only the stocks the chatter still has shares in.
+pub fn get_holdings(owner_id: i32) -> Vec<(Holding, Stock)> {
+    db_get_holdings(&mut establish_connection(), owner_id)
+}
+
+// Adds shares to a holding, or takes them off it when shares is negative, as long
+// as at least `keep` shares are left. false if there aren't enough shares.
+fn move_shares(
+    conn: &mut PgConnection,
+    owner: i32,
+    stock: i32,
+    shares: i32,
+    cost_basis_change: i64,
+    keep: i32,
+) -> QueryResult<bool> {
+    use crate::schema::holdings::dsl::{cost_basis, holdings, num_shares, owner_id, stock_id};
+
+    let updated = if shares > 0 {
+        diesel::insert_into(holdings)
+            .values((
+                owner_id.eq(owner),
+                stock_id.eq(stock),
+                num_shares.eq(shares),
+                cost_basis.eq(cost_basis_change),
+            ))
+            .on_conflict((owner_id, stock_id))
+            .do_update()
+            .set((
+                num_shares.eq(num_shares + shares),
+                cost_basis.eq(cost_basis + cost_basis_change),
+            ))
+            .execute(conn)?
+    } else {
+        diesel::update(holdings)
+            .filter(owner_id.eq(owner))
+            .filter(stock_id.eq(stock))
+            .filter(num_shares.ge(keep - shares))
+            .set((
+                num_shares.eq(num_shares + shares),
+                cost_basis.eq(cost_basis + cost_basis_change),
+            ))
+            .execute(conn)?
+    };
+    Ok(updated > 0)
+}
+
+// Locks the stock so orders for it are placed, matched and cancelled one at a time,
+// and hands back its open orders.
+fn lock_order_book(conn: &mut PgConnection, stock: i32) -> QueryResult<Vec<Order>> {
+    use crate::schema::orders::dsl::{orders, status, stock_id};
+    use crate::schema::stocks::dsl::{id, stocks};
+
+    stocks
+        .find(stock)
+        .select(id)
+        .for_update()
+        .first::<i32>(conn)?;
+    orders
+        .filter(stock_id.eq(stock))
+        .filter(status.eq(OrderStatus::Open.as_str()))
+        .select(Order::as_select())
+        .load::<Order>(conn)
+}
+
+fn shares_for_sale(open_orders: &[Order], owner: i32) -> i32 {
+    open_orders
+        .iter()
+        .filter(|o| o.owner_id == owner && !o.is_buy())
+        .map(|o| o.remaining_shares)
+        .sum()
+}
+
+fn db_record_trade(conn: &mut PgConnection, order: &NewOrder, cost_basis_change: i64) -> bool {
+    use crate::schema::orders;
+
+    conn.transaction(|conn| {
+        // shares up for sale on the order book can't be sold to the market too
+        let for_sale = shares_for_sale(&lock_order_book(conn, order.stock_id)?, order.owner_id);
+        if !move_shares(
+            conn,
+            order.owner_id,
+            order.stock_id,
+            order.num_shares,
+            cost_basis_change,
+            for_sale,
+        )? {
+            return Ok(false);
+        }
+        diesel::insert_into(orders::table)
+            .values(order)
+            .execute(conn)?;
+        Ok(true)
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while saving order: {}", err);
+        false
+    })
+}
+
+// Saves a market order and moves the shares, sells have negative shares and cost
+// basis changes. false if the chatter doesn't have the shares they're selling.
+pub fn record_trade(order: &NewOrder, cost_basis_change: i64) -> bool {
+    db_record_trade(&mut establish_connection(), order, cost_basis_change)
+}
+
+fn db_get_shares_for_sale(conn: &mut PgConnection, owner: i32, stock: i32) -> i32 {
+    use crate::schema::orders::dsl::{orders, owner_id, remaining_shares, status, stock_id};
+
+    orders
+        .filter(owner_id.eq(owner))
+        .filter(stock_id.eq(stock))
+        .filter(status.eq(OrderStatus::Open.as_str()))
+        .filter(diesel::dsl::sql::<diesel::sql_types::Bool>(
+            "num_shares < 0",
+        ))
+        .select(remaining_shares)
+        .load::<i32>(conn)
+        .expect("Error loading orders")
+        .iter()
+        .sum()
+}
+
+// Shares the chatter has on the order book in sell orders.
+pub fn get_shares_for_sale(owner_id: i32, stock_id: i32) -> i32 {
+    db_get_shares_for_sale(&mut establish_connection(), owner_id, stock_id)
+}
+
+fn db_place_limit_order(
+    conn: &mut PgConnection,
+    stock: i32,
+    owner: i32,
+    side: Side,
+    price: i64,
+    shares: i32,
+) -> anyhow::Result<(Order, Execution)> {
+    use crate::schema::chatters::dsl::{chatters, points};
+    use crate::schema::holdings::dsl::{cost_basis, holdings, num_shares, owner_id, stock_id};
+    use crate::schema::orders::dsl::{escrow, orders, remaining_shares, status};
+    use crate::schema::trades;
+
+    conn.transaction(|conn| {
+        let open_orders = lock_order_book(conn, stock)?;
+
+        let held_back = match side {
+            Side::Buy => {
+                let needed = order_book::escrow_for(price, shares);
+                let taken = diesel::update(chatters.find(owner))
+                    .filter(points.ge(needed))
+                    .set(points.eq(points - needed))
+                    .execute(conn)?;
+                if taken == 0 {
+                    let balance = chatters.find(owner).select(points).first::<i64>(conn)?;
+                    return Err(PlaceError::NotEnoughPoints {
+                        escrow: needed,
+                        balance,
+                    }
+                    .into());
+                }
+                needed
+            }
+            Side::Sell => {
+                let owned = holdings
+                    .filter(owner_id.eq(owner))
+                    .filter(stock_id.eq(stock))
+                    .select(num_shares)
+                    .first::<i32>(conn)
+                    .optional()?
+                    .unwrap_or(0);
+                let available = owned - shares_for_sale(&open_orders, owner);
+                if available < shares {
+                    return Err(PlaceError::NotEnoughShares { available }.into());
+                }
+                0
+            }
+        };
+
+        let order = diesel::insert_into(orders)
+            .values(&NewOrder {
+                stock_id: stock,
+                owner_id: owner,
+                num_shares: match side {
+                    Side::Buy => shares,
+                    Side::Sell => -shares,
+                },
+                strike_price: &order_book::from_cents(price),
+                status: OrderStatus::Open.as_str(),
+                remaining_shares: shares,
+                escrow: held_back,
+            })
+            .returning(Order::as_returning())
+            .get_result(conn)?;
+
+        let mut book = OrderBook::new(open_orders.iter().map(BookOrder::from_order));
+        let execution = book.submit(BookOrder::from_order(&order));
+
+        for fill in execution.fills.iter() {
+            diesel::insert_into(trades::table)
+                .values(&NewTrade {
+                    stock_id: stock,
+                    buy_order_id: fill.buy_order_id,
+                    sell_order_id: fill.sell_order_id,
+                    buyer_id: fill.buyer_id,
+                    seller_id: fill.seller_id,
+                    num_shares: fill.shares,
+                    price: &order_book::from_cents(fill.price),
+                    value: fill.value,
+                })
+                .execute(conn)?;
+
+            let (seller_shares, seller_basis) = holdings
+                .filter(owner_id.eq(fill.seller_id))
+                .filter(stock_id.eq(stock))
+                .select((num_shares, cost_basis))
+                .first::<(i32, i64)>(conn)?;
+            let basis_sold = seller_basis * fill.shares as i64 / seller_shares.max(1) as i64;
+            if !move_shares(conn, fill.seller_id, stock, -fill.shares, -basis_sold, 0)? {
+                return Err(anyhow::anyhow!(
+                    "seller {} doesn't have the shares of order {}",
+                    fill.seller_id,
+                    fill.sell_order_id
+                ));
+            }
+            diesel::update(chatters.find(fill.seller_id))
+                .set(points.eq(points + fill.value))
+                .execute(conn)?;
+            move_shares(conn, fill.buyer_id, stock, fill.shares, fill.value, 0)?;
+        }
+
+        // write back every order the match touched
+        for partial in execution
+            .fills
+            .iter()
+            .flat_map(|f| [f.buy_order_id, f.sell_order_id])
+            .filter_map(|order_id| book.get(order_id))
+        {
+            diesel::update(orders.find(partial.id))
+                .set((
+                    remaining_shares.eq(partial.remaining),
+                    escrow.eq(partial.escrow),
+                ))
+                .execute(conn)?;
+        }
+        for filled in execution.filled.iter() {
+            diesel::update(orders.find(filled.id))
+                .set((
+                    status.eq(OrderStatus::Filled.as_str()),
+                    remaining_shares.eq(0),
+                    escrow.eq(0),
+                ))
+                .execute(conn)?;
+            // what's left of the escrow goes back to the buyer
+            diesel::update(chatters.find(filled.owner_id))
+                .set(points.eq(points + filled.escrow))
+                .execute(conn)?;
+        }
+        Ok((order, execution))
+    })
+}
+
+// Places a limit order and matches it against the book, the error is a PlaceError
+// when the chatter can't afford the order.
+pub fn place_limit_order(
+    stock_id: i32,
+    owner_id: i32,
+    side: Side,
+    price: i64,
+    shares: i32,
+) -> anyhow::Result<(Order, Execution)> {
+    db_place_limit_order(
+        &mut establish_connection(),
+        stock_id,
+        owner_id,
+        side,
+        price,
+        shares,
+    )
+}
+
+fn db_cancel_order(conn: &mut PgConnection, order_id: i32, owner: i32) -> Option<Order> {
+    use crate::schema::chatters::dsl::{chatters, points};
+    use crate::schema::orders::dsl::{escrow, orders, owner_id, status};
+
+    let stock = orders
+        .find(order_id)
+        .filter(owner_id.eq(owner))
+        .select(Order::as_select())
+        .first(conn)
+        .optional()
+        .ok()??
+        .stock_id;
+    conn.transaction(|conn| {
+        let open_order = match lock_order_book(conn, stock)?
+            .into_iter()
+            .find(|o| o.id == order_id)
+        {
+            Some(order) => order,
+            None => return Ok(None),
+        };
+        diesel::update(orders.find(order_id))
+            .set((status.eq(OrderStatus::Cancelled.as_str()), escrow.eq(0)))
+            .execute(conn)?;
+        diesel::update(chatters.find(owner))
+            .set(points.eq(points + open_order.escrow))
+            .execute(conn)?;
+        Ok(Some(open_order))
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!(
+            "An error occurred while cancelling order {}: {}",
+            order_id, err
+        );
+        None
+    })
+}
+
+// Takes an open order off the book and gives back its escrow. None if the chatter
+// has no such open order.
+pub fn cancel_order(order_id: i32, owner_id: i32) -> Option<Order> {
+    db_cancel_order(&mut establish_connection(), order_id, owner_id)
+}
+
+fn db_get_open_orders(conn: &mut PgConnection, owner: i32) -> Vec<(Order, Stock)> {
+    use crate::schema::orders::dsl::{id, orders, owner_id, status};
+    use crate::schema::stocks::dsl::stocks;
+
+    orders
+        .inner_join(stocks)
+        .filter(owner_id.eq(owner))
+        .filter(status.eq(OrderStatus::Open.as_str()))
+        .order(id.asc())
+        .select((Order::as_select(), Stock::as_select()))
+        .load::<(Order, Stock)>(conn)
+        .expect("Error loading orders")
+}
+
+pub fn get_open_orders(owner_id: i32) -> Vec<(Order, Stock)> {
+    db_get_open_orders(&mut establish_connection(), owner_id)
+}
+
+fn db_get_order_book(conn: &mut PgConnection, stock: i32) -> OrderBook {
+    use crate::schema::orders::dsl::{orders, status, stock_id};
+
+    let open_orders = orders
+        .filter(stock_id.eq(stock))
+        .filter(status.eq(OrderStatus::Open.as_str()))
+        .select(Order::as_select())
+        .load::<Order>(conn)
+        .expect("Error loading orders");
+    OrderBook::new(open_orders.iter().map(BookOrder::from_order))
+}
+
+pub fn get_order_book(stock_id: i32) -> OrderBook {
+    db_get_order_book(&mut establish_connection(), stock_id)
+}
+
+fn db_get_trades(conn: &mut PgConnection, stock: i32, limit: i64) -> Vec<Trade> {
+    use crate::schema::trades::dsl::{id, stock_id, trades};
+
+    trades
+        .filter(stock_id.eq(stock))
+        .order(id.desc())
+        .limit(limit)
+        .select(Trade::as_select())
+        .load::<Trade>(conn)
+        .expect("Error loading trades")
+}
+
+// Newest first.
+pub fn get_trades(stock_id: i32, limit: i64) -> Vec<Trade> {
+    db_get_trades(&mut establish_connection(), stock_id, limit)
+}
+
+fn db_add_market_event(conn: &mut PgConnection, event: &NewMarketEvent) {
+    use crate::schema::market_events;
+
+    diesel::insert_into(market_events::table)
+        .values(event)
+        .execute(conn)
+        .expect("Error saving market event");
+}
+
+pub fn add_market_event(event: &NewMarketEvent) {
+    db_add_market_event(&mut establish_connection(), event)
+}
+
+fn db_take_market_events(conn: &mut PgConnection) -> QueryResult<Vec<MarketEvent>> {
+    use crate::schema::market_events::dsl::{applied_at, market_events};
+    use diesel::dsl;
+
+    diesel::update(market_events.filter(applied_at.is_null()))
+        .set(applied_at.eq(dsl::now))
+        .returning(MarketEvent::as_returning())
+        .get_results(conn)
+}
+
+// The events no tick has moved prices for yet, marking them as applied.
+pub fn take_market_events() -> anyhow::Result<Vec<MarketEvent>> {
+    Ok(db_take_market_events(&mut try_establish_connection()?)?)
+}
+
+fn db_add_stock_price(conn: &mut PgConnection, price: &NewStockPrice) {
+    use crate::schema::stock_prices;
+
+    diesel::insert_into(stock_prices::table)
+        .values(price)
+        .execute(conn)
+        .unwrap_or_else(|err| {
+            println!(
+                "Error saving the price history of stock {}: {}",
+                price.stock_id, err
+            );
+            0
+        });
+}
+
+pub fn add_stock_price(price: &NewStockPrice) {
+    db_add_stock_price(&mut establish_connection(), price)
+}
+
+fn db_get_stock_prices(conn: &mut PgConnection, stock: i32, limit: i64) -> Vec<StockPrice> {
+    use crate::schema::stock_prices::dsl::{id, stock_id, stock_prices};
+
+    let mut prices = stock_prices
+        .filter(stock_id.eq(stock))
+        .order(id.desc())
+        .limit(limit)
+        .select(StockPrice::as_select())
+        .load::<StockPrice>(conn)
+        .expect("Error loading stock prices");
+    prices.reverse();
+    prices
+}
+
+// The last prices of the stock, oldest first.
+pub fn get_stock_prices(stock_id: i32, limit: i64) -> Vec<StockPrice> {
+    db_get_stock_prices(&mut establish_connection(), stock_id, limit)
+}
+
+fn db_start_blackjack_hand(
+    conn: &mut PgConnection,
+    chatter: i32,
+    hand: &Hand,
+    hand_status: BlackjackStatus,
+    hand_payout: i64,
+) -> Option<BlackjackHand> {
+    use crate::schema::blackjack_hands::dsl::{blackjack_hands, chatter_id, status};
+    use crate::schema::chatters::dsl::{chatters, points};
+
+    let (player_cards, dealer_cards, deck) = hand.encode();
+    let new_hand = NewBlackjackHand {
+        chatter_id: chatter,
+        wager: hand.wager,
+        player_cards: &player_cards,
+        dealer_cards: &dealer_cards,
+        deck: &deck,
+        status: hand_status.as_str(),
+        payout: hand_payout,
+    };
+    conn.transaction(|conn| {
+        let playing = blackjack_hands
+            .filter(chatter_id.eq(chatter))
+            .filter(status.eq(BlackjackStatus::Playing.as_str()))
+            .count()
+            .get_result::<i64>(conn)?;
+        if playing > 0 {
+            return Ok(None);
+        }
+        // hold the wager back, and pay straight away when someone was dealt a natural
+        let taken = diesel::update(chatters.find(chatter))
+            .filter(points.ge(hand.wager))
+            .set(points.eq(points - hand.wager + hand_payout))
+            .execute(conn)?;
+        if taken == 0 {
+            return Ok(None);
+        }
+        diesel::insert_into(blackjack_hands)
+            .values(&new_hand)
+            .returning(BlackjackHand::as_returning())
+            .get_result(conn)
+            .map(Some)
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while saving blackjack hand: {}", err);
+        None
+    })
+}
+
+// Takes the wager and saves a new hand. None if the chatter can't cover the wager or
+// already has a hand going.
+pub fn start_blackjack_hand(
+    chatter_id: i32,
+    hand: &Hand,
+    status: BlackjackStatus,
+    payout: i64,
+) -> Option<BlackjackHand> {
+    db_start_blackjack_hand(
+        &mut establish_connection(),
+        chatter_id,
+        hand,
+        status,
+        payout,
+    )
+}
+
+fn db_update_blackjack_hand(
+    conn: &mut PgConnection,
+    old: &BlackjackHand,
+    hand: &Hand,
+    hand_status: BlackjackStatus,
+    hand_payout: i64,
+    extra_wager: i64,
+) -> Option<BlackjackHand> {
+    use crate::schema::blackjack_hands::dsl::{
+        blackjack_hands, dealer_cards, deck, doubled, payout, player_cards, status, wager,
+    };
+    use crate::schema::chatters::dsl::{chatters, points};
+
+    let (new_player_cards, new_dealer_cards, new_deck) = hand.encode();
+    conn.transaction(|conn| {
+        // every move draws a card or ends the hand, so a hand that still has the old
+        // cards and is still playing hasn't been moved since it was loaded
+        let saved = diesel::update(blackjack_hands.find(old.id))
+            .filter(status.eq(BlackjackStatus::Playing.as_str()))
+            .filter(player_cards.eq(&old.player_cards))
+            .set((
+                wager.eq(hand.wager),
+                player_cards.eq(&new_player_cards),
+                dealer_cards.eq(&new_dealer_cards),
+                deck.eq(&new_deck),
+                doubled.eq(hand.doubled),
+                status.eq(hand_status.as_str()),
+                payout.eq(hand_payout),
+            ))
+            .returning(BlackjackHand::as_returning())
+            .get_result(conn)
+            .optional()?;
+        let saved = match saved {
+            Some(saved) => saved,
+            None => return Ok(None),
+        };
+        // only a double needs the points to be there
+        let needed = if extra_wager > 0 {
+            extra_wager
+        } else {
+            i64::MIN
+        };
+        let paid = diesel::update(chatters.find(saved.chatter_id))
+            .filter(points.ge(needed))
+            .set(points.eq(points - extra_wager + hand_payout))
+            .execute(conn)?;
+        if paid == 0 {
+            // couldn't cover the double, leave the hand as it was
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+        Ok(Some(saved))
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        if err != diesel::result::Error::RollbackTransaction {
+            println!(
+                "An error occurred while saving blackjack hand {}: {}",
+                old.id, err
+            );
+        }
+        None
+    })
+}
+
+// Saves a move on the hand as it was loaded, taking extra_wager for a double and paying
+// out a finished hand. None if the hand was already moved or over, or the double
+// couldn't be covered.
+pub fn update_blackjack_hand(
+    old: &BlackjackHand,
+    hand: &Hand,
+    status: BlackjackStatus,
+    payout: i64,
+    extra_wager: i64,
+) -> Option<BlackjackHand> {
+    db_update_blackjack_hand(
+        &mut establish_connection(),
+        old,
+        hand,
+        status,
+        payout,
+        extra_wager,
+    )
+}
+
+fn db_get_blackjack_hand(conn: &mut PgConnection, chatter: i32) -> Option<BlackjackHand> {
+    use crate::schema::blackjack_hands::dsl::{blackjack_hands, chatter_id, status};
+
+    blackjack_hands
+        .filter(chatter_id.eq(chatter))
+        .filter(status.eq(BlackjackStatus::Playing.as_str()))
+        .select(BlackjackHand::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!(
+                "An error occurred while fetching blackjack hand for {}",
+                chatter
+            );
+            None
+        })
+}
+
+// The hand the chatter is still playing, if any.
+pub fn get_blackjack_hand(chatter_id: i32) -> Option<BlackjackHand> {
+    db_get_blackjack_hand(&mut establish_connection(), chatter_id)
+}
+
+fn db_get_stale_blackjack_hands(
+    conn: &mut PgConnection,
+    timeout: std::time::Duration,
+) -> QueryResult<Vec<BlackjackHand>> {
+    use crate::schema::blackjack_hands::dsl::{blackjack_hands, status, updated_at};
+    use diesel::dsl::{now, IntervalDsl};
+
+    blackjack_hands
+        .filter(status.eq(BlackjackStatus::Playing.as_str()))
+        .filter(updated_at.lt(now - (timeout.as_secs() as i64).seconds()))
+        .select(BlackjackHand::as_select())
+        .load::<BlackjackHand>(conn)
+}
+
+// Hands nobody has moved on for longer than the timeout.
+pub fn get_stale_blackjack_hands(
+    timeout: std::time::Duration,
+) -> anyhow::Result<Vec<BlackjackHand>> {
+    Ok(db_get_stale_blackjack_hands(
+        &mut try_establish_connection()?,
+        timeout,
+    )?)
+}
+
+fn db_get_active_casino_seed(conn: &mut PgConnection) -> Option<CasinoSeed> {
+    use crate::schema::casino_seeds::dsl::{casino_seeds, revealed_at};
+
+    casino_seeds
+        .filter(revealed_at.is_null())
+        .select(CasinoSeed::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching the casino seed");
+            None
+        })
+}
+
+// The server seed bets are made with, still secret.
+pub fn get_active_casino_seed() -> Option<CasinoSeed> {
+    db_get_active_casino_seed(&mut establish_connection())
+}
+
+fn db_get_casino_seed(conn: &mut PgConnection, id: i32) -> Option<CasinoSeed> {
+    use crate::schema::casino_seeds::dsl::casino_seeds;
+
+    casino_seeds
+        .find(id)
+        .select(CasinoSeed::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching casino seed {}", id);
+            None
+        })
+}
+
+pub fn get_casino_seed(id: i32) -> Option<CasinoSeed> {
+    db_get_casino_seed(&mut establish_connection(), id)
+}
+
+fn db_create_casino_seed(conn: &mut PgConnection, new_seed: &str, new_hash: &str) -> CasinoSeed {
+    use crate::schema::casino_seeds;
+
+    diesel::insert_into(casino_seeds::table)
+        .values(&NewCasinoSeed {
+            seed: new_seed,
+            seed_hash: new_hash,
+        })
+        .returning(CasinoSeed::as_returning())
+        .get_result(conn)
+        .expect("Error saving new casino seed")
+}
+
+pub fn create_casino_seed(seed: &str, seed_hash: &str) -> CasinoSeed {
+    db_create_casino_seed(&mut establish_connection(), seed, seed_hash)
+}
+
+fn db_rotate_casino_seed(
+    conn: &mut PgConnection,
+    new_seed: &str,
+    new_hash: &str,
+) -> Option<CasinoSeed> {
+    use crate::schema::casino_seeds::dsl::{casino_seeds, revealed_at};
+    use diesel::dsl::now;
+
+    conn.transaction(|conn| {
+        let revealed = diesel::update(casino_seeds.filter(revealed_at.is_null()))
+            .set(revealed_at.eq(now))
+            .returning(CasinoSeed::as_returning())
+            .get_result(conn)
+            .optional()?;
+        db_create_casino_seed(conn, new_seed, new_hash);
+        Ok(revealed)
+    })
+    .unwrap_or_else(|err: diesel::result::Error| {
+        println!("An error occurred while rotating the casino seed: {}", err);
+        None
+    })
+}
+
+// Reveals the seed in use and starts the new one. Returns the revealed seed, None if
+// there wasn't one.
+pub fn rotate_casino_seed(seed: &str, seed_hash: &str) -> Option<CasinoSeed> {
+    db_rotate_casino_seed(&mut establish_connection(), seed, seed_hash)
+}
+
+fn db_take_casino_nonce(conn: &mut PgConnection, seed_id: i32) -> Option<i32> {
+    use crate::schema::casino_seeds::dsl::{casino_seeds, nonce, revealed_at};
+
+    diesel::update(casino_seeds.find(seed_id))
+        .filter(revealed_at.is_null())
+        .set(nonce.eq(nonce + 1))
+        .returning(nonce)
+        .get_result(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while taking a nonce of seed {}", seed_id);
+            None
+        })
+}
+
+// The next bet's nonce, None if the seed has been revealed in the meantime.
+pub fn take_casino_nonce(seed_id: i32) -> Option<i32> {
+    db_take_casino_nonce(&mut establish_connection(), seed_id)
+}
+
+fn db_create_bet(conn: &mut PgConnection, bet: &NewBet) -> Bet {
+    use crate::schema::bets;
+
+    diesel::insert_into(bets::table)
+        .values(bet)
+        .returning(Bet::as_returning())
+        .get_result(conn)
+        .expect("Error saving new bet")
+}
+
+pub fn create_bet(bet: &NewBet) -> Bet {
+    db_create_bet(&mut establish_connection(), bet)
+}
+
+fn db_get_bet(conn: &mut PgConnection, id: i32) -> Option<Bet> {
+    use crate::schema::bets::dsl::bets;
+
+    bets.find(id)
+        .select(Bet::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!("An error occurred while fetching bet {}", id);
+            None
+        })
+}
+
+pub fn get_bet(id: i32) -> Option<Bet> {
+    db_get_bet(&mut establish_connection(), id)
+}
+
+fn db_set_client_seed(conn: &mut PgConnection, id: &str, seed: &str) {
+    use crate::schema::chatters::dsl::{chatters, client_seed, twitch_id};
+
+    diesel::update(chatters.filter(twitch_id.eq(id)))
+        .set(client_seed.eq(seed))
+        .execute(conn)
+        .expect("Error updating client seed");
+}
+
+pub fn set_client_seed(twitch_id: &str, seed: &str) {
+    db_set_client_seed(&mut establish_connection(), twitch_id, seed)
+}
+
+type BetSums = (
+    i64,
+    Option<bigdecimal::BigDecimal>,
+    Option<bigdecimal::BigDecimal>,
+    Option<bigdecimal::BigDecimal>,
+    Option<bigdecimal::BigDecimal>,
+);
+
+fn to_totals((bets, wagered, paid, pool, broadcaster): BetSums) -> Totals {
+    use bigdecimal::ToPrimitive;
+
+    let points = |sum: Option<bigdecimal::BigDecimal>| sum.and_then(|s| s.to_i64()).unwrap_or(0);
+    Totals {
+        bets,
+        wagered: points(wagered),
+        paid: points(paid),
+        pool: points(pool),
+        broadcaster: points(broadcaster),
+    }
+}
+
+fn db_get_bet_totals_by_game(
+    conn: &mut PgConnection,
+    since: chrono::NaiveDateTime,
+) -> Vec<(String, Totals)> {
+    use crate::schema::bets::dsl::{
+        bets, broadcaster_cut, created_at, game, payout, pool_cut, wager,
+    };
+    use diesel::dsl::{self, count_star};
+
+    bets.filter(created_at.ge(since))
+        .group_by(game)
+        .select((
+            game,
+            count_star(),
+            dsl::sum(wager),
+            dsl::sum(payout),
+            dsl::sum(pool_cut),
+            dsl::sum(broadcaster_cut),
+        ))
+        .order(game.asc())
+        .load::<(String, i64, _, _, _, _)>(conn)
+        .expect("Error loading bet totals")
+        .into_iter()
+        .map(|(name, b, w, p, pc, bc)| (name, to_totals((b, w, p, pc, bc))))
+        .collect()
+}
+
+// Totals per game of the bets made since then.
+pub fn get_bet_totals_by_game(since: chrono::NaiveDateTime) -> Vec<(String, Totals)> {
+    db_get_bet_totals_by_game(&mut establish_connection(), since)
+}
+
+fn db_get_bet_totals_by_player(
+    conn: &mut PgConnection,
+    since: chrono::NaiveDateTime,
+    limit: i64,
+) -> Vec<(String, Totals)> {
+    use crate::schema::bets::dsl::{bets, broadcaster_cut, created_at, payout, pool_cut, wager};
+    use crate::schema::chatters::dsl::{id, username};
+    use diesel::dsl::{self, count_star};
+
+    bets.inner_join(crate::schema::chatters::table)
+        .filter(created_at.ge(since))
+        .group_by((id, username))
+        .select((
+            username,
+            count_star(),
+            dsl::sum(wager),
+            dsl::sum(payout),
+            dsl::sum(pool_cut),
+            dsl::sum(broadcaster_cut),
+        ))
+        .order(dsl::sum(payout - wager).desc())
+        .limit(limit)
+        .load::<(String, i64, _, _, _, _)>(conn)
+        .expect("Error loading bet totals")
+        .into_iter()
+        .map(|(name, b, w, p, pc, bc)| (name, to_totals((b, w, p, pc, bc))))
+        .collect()
+}
+
+// Totals per chatter of the bets made since then, biggest winners first.
+pub fn get_bet_totals_by_player(since: chrono::NaiveDateTime, limit: i64) -> Vec<(String, Totals)> {
+    db_get_bet_totals_by_player(&mut establish_connection(), since, limit)
+}
+
+fn db_get_bet_totals_for(conn: &mut PgConnection, chatter: i32) -> Totals {
+    use crate::schema::bets::dsl::{bets, broadcaster_cut, chatter_id, payout, pool_cut, wager};
+    use diesel::dsl::{self, count_star};
+
+    bets.filter(chatter_id.eq(chatter))
+        .select((
+            count_star(),
+            dsl::sum(wager),
+            dsl::sum(payout),
+            dsl::sum(pool_cut),
+            dsl::sum(broadcaster_cut),
+        ))
+        .first::<BetSums>(conn)
+        .map(to_totals)
+        .expect("Error loading bet totals")
+}
+
+pub fn get_bet_totals_for(chatter_id: i32) -> Totals {
+    db_get_bet_totals_for(&mut establish_connection(), chatter_id)
+}
+
+fn db_get_bets_since(conn: &mut PgConnection, since: chrono::NaiveDateTime) -> Vec<Bet> {
+    use crate::schema::bets::dsl::{bets, created_at};
+
+    bets.filter(created_at.ge(since))
+        .order(created_at.asc())
+        .select(Bet::as_select())
+        .load::<Bet>(conn)
+        .expect("Error loading bets")
+}
+
+// Oldest first.
+pub fn get_bets_since(since: chrono::NaiveDateTime) -> Vec<Bet> {
+    db_get_bets_since(&mut establish_connection(), since)
+}
+
+fn db_get_gambling_limit(conn: &mut PgConnection, chatter: i32) -> Option<GamblingLimit> {
+    use crate::schema::gambling_limits::dsl::gambling_limits;
+
+    gambling_limits
+        .find(chatter)
+        .select(GamblingLimit::as_select())
+        .first(conn)
+        .optional()
+        .unwrap_or_else(|_| {
+            println!(
+                "An error occurred while fetching gambling limits for {}",
+                chatter
+            );
+            None
+        })
+}
+
+// The chatter's self-exclusion and the limits mods set for them, if any.
+pub fn get_gambling_limit(chatter_id: i32) -> Option<GamblingLimit> {
+    db_get_gambling_limit(&mut establish_connection(), chatter_id)
+}
+
+fn db_set_excluded_until(
+    conn: &mut PgConnection,
+    chatter: i32,
+    until: Option<chrono::NaiveDateTime>,
+) {
+    use crate::schema::gambling_limits::dsl::{chatter_id, excluded_until, gambling_limits};
+
+    diesel::insert_into(gambling_limits)
+        .values((chatter_id.eq(chatter), excluded_until.eq(until)))
+        .on_conflict(chatter_id)
+        .do_update()
+        .set(excluded_until.eq(until))
+        .execute(conn)
+        .expect("Error saving self-exclusion");
+}
+
+// None lifts the exclusion.
+pub fn set_excluded_until(chatter_id: i32, until: Option<chrono::NaiveDateTime>) {
+    db_set_excluded_until(&mut establish_connection(), chatter_id, until)
+}
+
+fn db_set_gambling_limit(
+    conn: &mut PgConnection,
+    chatter: i32,
+    kind: LimitKind,
+    limit: Option<i64>,
+) {
+    use crate::schema::gambling_limits::dsl::{
+        chatter_id, daily_loss_limit, gambling_limits, max_wager, session_loss_limit,
+    };
+
+    let insert = diesel::insert_into(gambling_limits);
+    match kind {
+        LimitKind::Wager => insert
+            .values((chatter_id.eq(chatter), max_wager.eq(limit)))
+            .on_conflict(chatter_id)
+            .do_update()
+            .set(max_wager.eq(limit))
+            .execute(conn),
+        LimitKind::Session => insert
+            .values((chatter_id.eq(chatter), session_loss_limit.eq(limit)))
+            .on_conflict(chatter_id)
+            .do_update()
+            .set(session_loss_limit.eq(limit))
+            .execute(conn),
+        LimitKind::Daily => insert
+            .values((chatter_id.eq(chatter), daily_loss_limit.eq(limit)))
+            .on_conflict(chatter_id)
+            .do_update()
+            .set(daily_loss_limit.eq(limit))
+            .execute(conn),
+    }
+    .expect("Error saving gambling limit");
+}
+
+// None puts the chatter back on the casino's default.
+pub fn set_gambling_limit(chatter_id: i32, kind: LimitKind, limit: Option<i64>) {
+    db_set_gambling_limit(&mut establish_connection(), chatter_id, kind, limit)
+}
+
+fn db_get_bet_results_since(
+    conn: &mut PgConnection,
+    chatter: i32,
+    since: chrono::NaiveDateTime,
+) -> Vec<(chrono::NaiveDateTime, i64)> {
+    use crate::schema::bets::dsl::{bets, chatter_id, created_at, payout, wager};
+    use crate::schema::pool_tickets::dsl::{
+        chatter_id as ticket_chatter_id, created_at as bought_at, points, pool_tickets, refunded,
+    };
+
+    let mut results = bets
+        .filter(chatter_id.eq(chatter))
+        .filter(created_at.ge(since))
+        .select((created_at, payout - wager))
+        .load::<(chrono::NaiveDateTime, i64)>(conn)
+        .expect("Error loading bets");
+    let tickets = pool_tickets
+        .filter(ticket_chatter_id.eq(chatter))
+        .filter(refunded.eq(false))
+        .filter(bought_at.ge(since))
+        .select((bought_at, points))
+        .load::<(chrono::NaiveDateTime, i64)>(conn)
+        .expect("Error loading pool tickets");
+    results.extend(tickets.into_iter().map(|(at, spent)| (at, -spent)));
+    results.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+    results
+}
+
+// (when, what the chatter won) of their bets since then, newest first. Pool tickets
+// they haven't had refunded count as lost.
+pub fn get_bet_results_since(
+    chatter_id: i32,
+    since: chrono::NaiveDateTime,
+) -> Vec<(chrono::NaiveDateTime, i64)> {
+    db_get_bet_results_since(&mut establish_connection(), chatter_id, since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A connection whose changes are rolled back. These tests are ignored unless asked
+    // for, since they need the database in DATABASE_URL.
+    fn test_connection() -> PgConnection {
+        dotenv().ok();
+        let url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let mut conn = PgConnection::establish(&url).expect("Error connecting to the database");
+        conn.begin_test_transaction()
+            .expect("Error starting a test transaction");
+        conn
+    }
+
+    fn pending_question(conn: &mut PgConnection, twitch_id: &str) -> Question {
+        let category = _db_create_category(conn, &format!("test {}", twitch_id), 1);
+        db_create_question(
+            conn,
+            "What is 2 + 2?",
+            "4",
+            twitch_id.parse().unwrap(),
+            category.id,
+            QuestionStatus::Pending,
+            5000,
+        )
+    }
+
+    #[test]
+    #[ignore = "needs a database, run with --ignored"]
+    fn test_add_points_stops_at_the_floor() {
+        let conn = &mut test_connection();
+        let chatter = create_chatter(conn, "900003", "floor");
+        assert!(db_add_points(conn, "900003", 250));
+        assert!(db_add_points(conn, "900003", -100));
+        let points = db_get_chatter(conn, "900003").unwrap().points;
+        assert_eq!(chatter.points + 150, points);
+        assert!(db_add_points(conn, "900003", -(points + 5000)));
+        assert_eq!(-1000, db_get_chatter(conn, "900003").unwrap().points);
+        assert!(!db_add_points(conn, "no such chatter", 10));
+    }
+
+    #[test]
+    #[ignore = "needs a database, run with --ignored"]
+    fn test_pool_tickets_count_as_lost_until_refunded() {
+        use crate::schema::losers_pool::dsl::losers_pool;
+        let conn = &mut test_connection();
+        let chatter = create_chatter(conn, "900004", "ticket buyer");
+        db_add_points(conn, "900004", 100);
+        let day_ago = chrono::Utc::now().naive_utc() - chrono::Duration::days(1);
+        assert!(db_get_bet_results_since(conn, chatter.id, day_ago).is_empty());
+        let pool = diesel::insert_into(losers_pool)
+            .values(NewPool { amount: 100 })
+            .returning(LosersPool::as_returning())
+            .get_result(conn)
+            .unwrap();
+
+        assert!(db_buy_pool_tickets(conn, chatter.id, 3, 10).is_some());
+        let results = db_get_bet_results_since(conn, chatter.id, day_ago);
+        assert_eq!(
+            vec![-30],
+            results.iter().map(|(_, net)| *net).collect::<Vec<i64>>()
+        );
+
+        assert!(db_cancel_pool(conn, pool.id).is_some());
+        assert!(db_get_bet_results_since(conn, chatter.id, day_ago).is_empty());
+    }
+
+    #[test]
+    #[ignore = "needs a database, run with --ignored"]
+    fn test_add_pool_points_goes_to_the_current_pool() {
+        use crate::schema::losers_pool::dsl::losers_pool;
+        let conn = &mut test_connection();
+        let pool = diesel::insert_into(losers_pool)
+            .values(NewPool { amount: 100 })
+            .returning(LosersPool::as_returning())
+            .get_result(conn)
+            .unwrap();
+        assert!(db_add_pool_points(conn, 25));
+        assert!(db_add_pool_points(conn, 5));
+        let pool = losers_pool
+            .find(pool.id)
+            .select(LosersPool::as_select())
+            .first(conn)
+            .unwrap();
+        assert_eq!(130, pool.amount);
+    }
+
+    #[test]
+    #[ignore = "needs a database, run with --ignored"]
+    fn test_a_stale_blackjack_hand_cannot_overwrite_a_newer_move() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let conn = &mut test_connection();
+        let chatter = create_chatter(conn, "900006", "blackjack");
+        assert!(db_add_points(conn, "900006", 1000));
+        // a deal that's still playing after a hit, so only the cards tell the moves apart
+        let (hand, status, hit) = (0..)
+            .find_map(|seed| {
+                let (hand, status) = Hand::deal(100, &mut StdRng::seed_from_u64(seed));
+                let mut hit = hand.clone();
+                let playing = BlackjackStatus::Playing;
+                (status == playing && hit.hit() == playing).then_some((hand, status, hit))
+            })
+            .unwrap();
+        let loaded = db_start_blackjack_hand(conn, chatter.id, &hand, status, 0).unwrap();
+        let moved =
+            db_update_blackjack_hand(conn, &loaded, &hit, BlackjackStatus::Playing, 0, 0).unwrap();
+
+        // the timeout standing on the copy it loaded before the hit
+        let mut stood = Hand::from_saved(&loaded);
+        let stood_status = stood.stand();
+        assert!(db_update_blackjack_hand(conn, &loaded, &stood, stood_status, 0, 0).is_none());
+        let saved = db_get_blackjack_hand(conn, chatter.id).unwrap();
+        assert_eq!(moved.player_cards, saved.player_cards);
+        assert!(saved.is_playing());
+    }
+
+    #[test]
+    #[ignore = "needs a database, run with --ignored"]
+    fn test_rejecting_refunds_once() {
+        let conn = &mut test_connection();
+        let submitter = create_chatter(conn, "900001", "submitter");
+        let question = pending_question(conn, "900001");
+
+        assert!(db_review_question(
+            conn,
+            question.id,
+            QuestionStatus::Rejected,
+            Some("duplicate")
+        ));
+        let refunded = db_get_chatter(conn, "900001").unwrap().points;
+        assert_eq!(submitter.points + 5000, refunded);
+
+        assert!(!db_review_question(
+            conn,
+            question.id,
+            QuestionStatus::Rejected,
+            Some("again")
+        ));
+        assert!(!db_review_question(
+            conn,
+            question.id,
+            QuestionStatus::Approved,
+            None
+        ));
+        assert_eq!(refunded, db_get_chatter(conn, "900001").unwrap().points);
+        let question = db_get_question(conn, question.id).unwrap();
+        assert_eq!(QuestionStatus::Rejected.as_str(), question.status);
+        assert_eq!(Some("duplicate".to_string()), question.review_note);
+    }
+
+    #[test]
+    #[ignore = "needs a database, run with --ignored"]
+    fn test_rejecting_an_approved_question_does_not_refund() {
+        let conn = &mut test_connection();
+        let submitter = create_chatter(conn, "900002", "submitter");
+        let question = pending_question(conn, "900002");
+
+        assert!(db_review_question(
+            conn,
+            question.id,
+            QuestionStatus::Approved,
+            None
+        ));
+        assert!(!db_review_question(
+            conn,
+            question.id,
+            QuestionStatus::Rejected,
+            Some("changed my mind")
+        ));
+        assert_eq!(
+            submitter.points,
+            db_get_chatter(conn, "900002").unwrap().points
+        );
+        assert_eq!(
+            QuestionStatus::Approved.as_str(),
+            db_get_question(conn, question.id).unwrap().status
+        );
+    }
+}
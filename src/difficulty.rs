@@ -0,0 +1,196 @@
+// Derives how hard a question is from how it has played out in past duels.
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::models::Question;
+
+// Both duelists get 5 guesses each, so one ask can rack up at most 10 wrong guesses.
+const MAX_WRONG_GUESSES_PER_ASK: f64 = 10.0;
+
+// A fresh question is treated as if it had already been asked 4 times, so a couple
+// of lucky or unlucky duels don't swing it straight to Easy or Hard. How many of
+// those were solved comes from the authored difficulty, if the question has one.
+const PRIOR_ASKS: f64 = 4.0;
+const PRIOR_WRONG_GUESSES: f64 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_score(score: f64) -> Difficulty {
+        if score < 0.3 {
+            Difficulty::Easy
+        } else if score < 0.55 {
+            Difficulty::Medium
+        } else {
+            Difficulty::Hard
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    // Winners of harder duels take home more than their wager.
+    pub fn payout_multiplier(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 1.0,
+            Difficulty::Medium => 1.25,
+            Difficulty::Hard => 1.5,
+        }
+    }
+
+    pub fn scale_payout(&self, points: i64) -> i64 {
+        (points as f64 * self.payout_multiplier()).round() as i64
+    }
+
+    fn prior_solve_rate(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.85,
+            Difficulty::Medium => 0.5,
+            Difficulty::Hard => 0.2,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(anyhow!("difficulty must be one of easy, medium or hard")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuestionStats {
+    pub times_asked: i32,
+    pub times_answered_correctly: i32,
+    pub times_guessed_wrong: i32,
+    pub authored: Option<Difficulty>,
+}
+
+impl From<&Question> for QuestionStats {
+    fn from(question: &Question) -> Self {
+        QuestionStats {
+            times_asked: question.times_asked,
+            times_answered_correctly: question.times_answered_correctly,
+            times_guessed_wrong: question.times_guessed_wrong,
+            authored: question
+                .authored_difficulty
+                .as_ref()
+                .and_then(|d| d.parse().ok()),
+        }
+    }
+}
+
+// 0.0 is trivially easy, 1.0 is never solved and every guess is wrong.
+// Mostly driven by how often the question goes unsolved, with wrong guesses
+// separating the questions people get eventually from the ones they nail first try.
+pub fn score(stats: &QuestionStats) -> f64 {
+    let asked = stats.times_asked.max(0) as f64;
+    let solved = (stats.times_answered_correctly.max(0) as f64).min(asked);
+    let wrong = stats.times_guessed_wrong.max(0) as f64;
+
+    let prior_solves = stats
+        .authored
+        .unwrap_or(Difficulty::Medium)
+        .prior_solve_rate()
+        * PRIOR_ASKS;
+    let solve_rate = (solved + prior_solves) / (asked + PRIOR_ASKS);
+    let wrong_per_ask = (wrong + PRIOR_WRONG_GUESSES) / (asked + PRIOR_ASKS);
+    let wrong_rate = (wrong_per_ask / MAX_WRONG_GUESSES_PER_ASK).min(1.0);
+
+    0.7 * (1.0 - solve_rate) + 0.3 * wrong_rate
+}
+
+pub fn difficulty(stats: &QuestionStats) -> Difficulty {
+    Difficulty::from_score(score(stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(asked: i32, correct: i32, wrong: i32) -> QuestionStats {
+        QuestionStats {
+            times_asked: asked,
+            times_answered_correctly: correct,
+            times_guessed_wrong: wrong,
+            authored: None,
+        }
+    }
+
+    #[test]
+    fn test_new_question_is_medium() {
+        assert_eq!(Difficulty::Medium, difficulty(&stats(0, 0, 0)));
+    }
+
+    #[test]
+    fn test_difficulty_follows_outcomes() {
+        assert_eq!(Difficulty::Easy, difficulty(&stats(20, 20, 2)));
+        assert_eq!(Difficulty::Medium, difficulty(&stats(20, 12, 20)));
+        assert_eq!(Difficulty::Hard, difficulty(&stats(20, 2, 150)));
+    }
+
+    #[test]
+    fn test_authored_difficulty_is_a_prior() {
+        let authored = |asked, correct, wrong, d| QuestionStats {
+            authored: Some(d),
+            ..stats(asked, correct, wrong)
+        };
+        assert_eq!(
+            Difficulty::Easy,
+            difficulty(&authored(0, 0, 0, Difficulty::Easy))
+        );
+        assert_eq!(
+            Difficulty::Hard,
+            difficulty(&authored(0, 0, 0, Difficulty::Hard))
+        );
+        // enough duels outweigh whatever the author thought
+        assert_eq!(
+            Difficulty::Easy,
+            difficulty(&authored(40, 40, 4, Difficulty::Hard))
+        );
+    }
+
+    #[test]
+    fn test_score_is_bounded() {
+        assert!(score(&stats(1000, 0, 100_000)) <= 1.0);
+        assert!(score(&stats(1000, 1000, 0)) >= 0.0);
+        // bad counters from the database shouldn't produce nonsense
+        assert!(score(&stats(-5, 10, -3)) >= 0.0);
+    }
+
+    #[test]
+    fn test_parse_and_payout() {
+        assert_eq!(Difficulty::Hard, " HARD ".parse::<Difficulty>().unwrap());
+        assert!("impossible".parse::<Difficulty>().is_err());
+        assert_eq!(100, Difficulty::Easy.scale_payout(100));
+        assert_eq!(150, Difficulty::Hard.scale_payout(100));
+    }
+}
@@ -0,0 +1,210 @@
+// How many points a chat message earns. Short, repeated and command messages earn
+// nothing, and each message within a minute earns less than the one before, up to
+// a cap per minute, so spamming chat isn't worth it.
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::helpers::env_number;
+use crate::question_pack::normalize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EarningConfig {
+    // what the first message in a minute earns
+    pub points: i64,
+    // shorter messages, not counting punctuation and spacing, earn nothing
+    pub min_length: usize,
+    // repeating a message within this long earns nothing
+    pub duplicate_window: Duration,
+    pub minute_cap: i64,
+    // every message already earned on in the last minute scales the next one by this
+    pub decay: f64,
+    pub bot_username: Option<String>,
+}
+
+impl Default for EarningConfig {
+    fn default() -> Self {
+        EarningConfig {
+            points: 5,
+            min_length: 4,
+            duplicate_window: Duration::minutes(5),
+            minute_cap: 15,
+            decay: 0.5,
+            bot_username: None,
+        }
+    }
+}
+
+impl EarningConfig {
+    // EARN_POINTS, EARN_MIN_LENGTH, EARN_DUPLICATE_MINUTES, EARN_MINUTE_CAP and
+    // EARN_DECAY, anything unset keeps its default.
+    pub fn from_env() -> EarningConfig {
+        let defaults = EarningConfig::default();
+        EarningConfig {
+            points: env_number("EARN_POINTS", defaults.points),
+            min_length: env_number("EARN_MIN_LENGTH", defaults.min_length),
+            duplicate_window: Duration::minutes(env_number(
+                "EARN_DUPLICATE_MINUTES",
+                defaults.duplicate_window.num_minutes(),
+            )),
+            minute_cap: env_number("EARN_MINUTE_CAP", defaults.minute_cap),
+            decay: env_number("EARN_DECAY", defaults.decay),
+            bot_username: std::env::var("BOT_USERNAME").ok(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Activity {
+    // (sent at, points earned) for the last minute
+    earned: VecDeque<(NaiveDateTime, i64)>,
+    // (sent at, normalized text) within the duplicate window
+    messages: VecDeque<(NaiveDateTime, String)>,
+}
+
+#[derive(Debug, Default)]
+pub struct EarningTracker {
+    pub config: EarningConfig,
+    activity: HashMap<String, Activity>,
+}
+
+impl EarningTracker {
+    pub fn new(config: EarningConfig) -> EarningTracker {
+        EarningTracker {
+            config,
+            activity: HashMap::new(),
+        }
+    }
+
+    // Points the message earns, remembering it for the duplicate and per-minute rules.
+    pub fn points_for(
+        &mut self,
+        twitch_id: &str,
+        username: &str,
+        text: &str,
+        now: NaiveDateTime,
+    ) -> i64 {
+        let config = &self.config;
+        if config
+            .bot_username
+            .as_deref()
+            .is_some_and(|bot| bot.eq_ignore_ascii_case(username))
+            || text.trim_start().starts_with('!')
+        {
+            return 0;
+        }
+        let normalized = normalize(text);
+        if normalized.chars().filter(|c| !c.is_whitespace()).count() < config.min_length {
+            return 0;
+        }
+
+        let activity = self.activity.entry(twitch_id.to_string()).or_default();
+        while activity
+            .earned
+            .front()
+            .is_some_and(|(at, _)| now - *at >= Duration::minutes(1))
+        {
+            activity.earned.pop_front();
+        }
+        while activity
+            .messages
+            .front()
+            .is_some_and(|(at, _)| now - *at >= config.duplicate_window)
+        {
+            activity.messages.pop_front();
+        }
+
+        if activity.messages.iter().any(|(_, m)| *m == normalized) {
+            return 0;
+        }
+        activity.messages.push_back((now, normalized));
+
+        let earned = activity.earned.iter().map(|(_, p)| p).sum::<i64>();
+        let scaled = config.points as f64 * config.decay.powi(activity.earned.len() as i32);
+        let points = (scaled.round() as i64)
+            .min(config.minute_cap - earned)
+            .max(0);
+        if points > 0 {
+            activity.earned.push_back((now, points));
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> NaiveDateTime {
+        chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc() + Duration::seconds(seconds)
+    }
+
+    fn tracker() -> EarningTracker {
+        EarningTracker::new(EarningConfig {
+            bot_username: Some("ttb_bot".to_string()),
+            ..EarningConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_short_command_and_bot_messages_earn_nothing() {
+        let mut tracker = tracker();
+        assert_eq!(0, tracker.points_for("1", "chatter", "lol", at(0)));
+        assert_eq!(0, tracker.points_for("1", "chatter", "  !!!  ", at(0)));
+        assert_eq!(
+            0,
+            tracker.points_for("1", "chatter", "!points please", at(0))
+        );
+        assert_eq!(0, tracker.points_for("2", "TTB_Bot", "hello there", at(0)));
+        assert_eq!(5, tracker.points_for("1", "chatter", "hello there", at(0)));
+    }
+
+    #[test]
+    fn test_duplicates_earn_nothing() {
+        let mut tracker = tracker();
+        assert_eq!(5, tracker.points_for("1", "chatter", "good game", at(0)));
+        assert_eq!(
+            0,
+            tracker.points_for("1", "chatter", "Good game!!", at(120))
+        );
+        // someone else saying it is fine, and so is saying it again later
+        assert_eq!(5, tracker.points_for("2", "other", "good game", at(120)));
+        assert_eq!(5, tracker.points_for("1", "chatter", "good game", at(420)));
+    }
+
+    #[test]
+    fn test_diminishing_returns_and_cap() {
+        let mut tracker = EarningTracker::new(EarningConfig {
+            points: 8,
+            minute_cap: 14,
+            ..EarningConfig::default()
+        });
+        let earned = (0..6)
+            .map(|i| tracker.points_for("1", "chatter", &format!("message {}", i), at(i)))
+            .collect::<Vec<i64>>();
+        assert_eq!(vec![8, 4, 2, 0, 0, 0], earned);
+
+        let mut tracker = EarningTracker::new(EarningConfig {
+            points: 10,
+            decay: 1.0,
+            ..EarningConfig::default()
+        });
+        assert_eq!(
+            10,
+            tracker.points_for("1", "chatter", "first message", at(0))
+        );
+        assert_eq!(
+            5,
+            tracker.points_for("1", "chatter", "second message", at(10))
+        );
+        assert_eq!(
+            0,
+            tracker.points_for("1", "chatter", "third message", at(20))
+        );
+        // a minute after the first one it starts paying again
+        assert_eq!(
+            10,
+            tracker.points_for("1", "chatter", "fourth message", at(60))
+        );
+    }
+}
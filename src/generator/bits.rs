@@ -0,0 +1,108 @@
+// Bit twiddling on numbers written in binary, hex and decimal. The answer can be
+// given in any of the three.
+use rand::Rng;
+
+use crate::difficulty::Difficulty;
+use crate::generator::GeneratedQuestion;
+
+pub const CATEGORY: &str = "Bit Twiddling";
+
+// Writes `n` the way Rust source would, in a base picked by the rng.
+fn literal<R: Rng>(n: u32, rng: &mut R) -> String {
+    match rng.gen_range(0..3) {
+        0 => format!("{:#b}", n),
+        1 => format!("{:#x}", n),
+        _ => n.to_string(),
+    }
+}
+
+fn question(expression: String, answer: u32, difficulty: Difficulty) -> GeneratedQuestion {
+    let mut question = GeneratedQuestion::free_text(
+        format!("{} = ? (u8)", expression),
+        answer.to_string(),
+        CATEGORY,
+        difficulty,
+    );
+    question.alternate_answers = vec![format!("{:#x}", answer), format!("{:#b}", answer)];
+    question
+}
+
+pub fn generate<R: Rng>(difficulty: Difficulty, rng: &mut R) -> GeneratedQuestion {
+    match difficulty {
+        Difficulty::Easy => {
+            let a = rng.gen_range(1..16);
+            let b = rng.gen_range(1..16);
+            let (a_lit, b_lit) = (literal(a, rng), literal(b, rng));
+            if rng.gen_bool(0.5) {
+                question(format!("{} & {}", a_lit, b_lit), a & b, difficulty)
+            } else {
+                question(format!("{} | {}", a_lit, b_lit), a | b, difficulty)
+            }
+        }
+        Difficulty::Medium => {
+            let a = rng.gen_range(1..64);
+            let b = rng.gen_range(1..64);
+            let shift = rng.gen_range(1..3);
+            let (a_lit, b_lit) = (literal(a, rng), literal(b, rng));
+            if rng.gen_bool(0.5) {
+                question(format!("{} ^ {}", a_lit, b_lit), a ^ b, difficulty)
+            } else {
+                question(format!("{} << {}", a_lit, shift), a << shift, difficulty)
+            }
+        }
+        Difficulty::Hard => {
+            let a = rng.gen_range(1..256);
+            let b = rng.gen_range(1..256);
+            let shift = rng.gen_range(1..5);
+            let (a_lit, b_lit) = (literal(a, rng), literal(b, rng));
+            match rng.gen_range(0..3) {
+                0 => question(format!("!{} & {}", a_lit, b_lit), !a & b & 0xff, difficulty),
+                1 => question(
+                    format!("({} >> {}) ^ {}", a_lit, shift, b_lit),
+                    (a >> shift) ^ b,
+                    difficulty,
+                ),
+                // a method call needs a typed literal, `200.count_ones()` doesn't compile
+                _ => question(
+                    format!("{}u8.count_ones()", a_lit),
+                    a.count_ones(),
+                    difficulty,
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_answers_fit_in_a_byte_and_accept_any_base() {
+        for seed in 0..200 {
+            for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                let q = generate(difficulty, &mut StdRng::seed_from_u64(seed));
+                let answer: u32 = q.answer.parse().unwrap();
+                assert!(answer <= 0xff, "{}", q.question);
+                assert!(q.accepts(&format!("{:#x}", answer), seed));
+                assert!(q.accepts(&format!("{:#b}", answer), seed));
+                if q.question.contains("count_ones") {
+                    assert!(q.question.contains("u8.count_ones()"), "{}", q.question);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_literal_bases() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let literals = (0..30)
+            .map(|_| literal(11, &mut rng))
+            .collect::<Vec<String>>();
+        assert!(literals.contains(&"0b1011".to_string()));
+        assert!(literals.contains(&"0xb".to_string()));
+        assert!(literals.contains(&"11".to_string()));
+    }
+}
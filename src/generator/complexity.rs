@@ -0,0 +1,117 @@
+// "Guess the complexity" of a snippet, answered by picking from big O options.
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::difficulty::Difficulty;
+use crate::generator::GeneratedQuestion;
+use crate::question_type::QuestionType;
+
+pub const CATEGORY: &str = "Complexity";
+
+const OPTIONS: &[&str] = &[
+    "O(1)",
+    "O(log n)",
+    "O(n)",
+    "O(n log n)",
+    "O(n^2)",
+    "O(n * m)",
+    "O(2^n)",
+];
+
+// (snippet, time complexity in terms of n)
+const EASY: &[(&str, &str)] = &[
+    ("v.push(x) on a Vec (amortized)", "O(1)"),
+    ("map.get(&key) on a HashMap (average)", "O(1)"),
+    ("v.contains(&x) on an unsorted Vec of n items", "O(n)"),
+    ("for x in &v { total += x; } with v.len() == n", "O(n)"),
+    (
+        "for i in 0..n { for j in 0..n { grid[i][j] = 0; } }",
+        "O(n^2)",
+    ),
+];
+
+const MEDIUM: &[(&str, &str)] = &[
+    ("v.binary_search(&x) on a sorted Vec of n items", "O(log n)"),
+    ("v.sort() on a Vec of n items", "O(n log n)"),
+    ("let mut i = n; while i > 1 { i /= 2; }", "O(log n)"),
+    (
+        "for i in 0..n { for j in 0..m { sum += a[i][j]; } }",
+        "O(n * m)",
+    ),
+    ("v.insert(0, x) on a Vec of n items", "O(n)"),
+    ("heap.pop() on a BinaryHeap of n items", "O(log n)"),
+];
+
+const HARD: &[(&str, &str)] = &[
+    (
+        "fn fib(n: u64) -> u64 { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }",
+        "O(2^n)",
+    ),
+    ("for i in 0..n { for j in 0..i { count += 1; } }", "O(n^2)"),
+    (
+        "for i in 0..n { let mut j = 1; while j < n { j *= 2; } }",
+        "O(n log n)",
+    ),
+    ("let mut i = 1; while i < n { i *= 3; }", "O(log n)"),
+    (
+        "for x in &v { set.insert(x); } with a HashSet and v.len() == n",
+        "O(n)",
+    ),
+    (
+        "while !v.is_empty() { v.remove(0); } with v.len() == n",
+        "O(n^2)",
+    ),
+];
+
+pub fn generate<R: Rng>(difficulty: Difficulty, rng: &mut R) -> GeneratedQuestion {
+    let templates = match difficulty {
+        Difficulty::Easy => EASY,
+        Difficulty::Medium => MEDIUM,
+        Difficulty::Hard => HARD,
+    };
+    let (snippet, answer) = templates.choose(rng).unwrap();
+    let incorrect_answers = OPTIONS
+        .iter()
+        .filter(|o| *o != answer)
+        .copied()
+        .collect::<Vec<&str>>()
+        .choose_multiple(rng, 3)
+        .map(|o| o.to_string())
+        .collect();
+
+    GeneratedQuestion {
+        kind: QuestionType::MultipleChoice,
+        question: format!("What's the time complexity? {}", snippet),
+        answer: answer.to_string(),
+        alternate_answers: vec![],
+        incorrect_answers,
+        category: CATEGORY,
+        difficulty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_templates_use_known_options() {
+        for (_, answer) in EASY.iter().chain(MEDIUM).chain(HARD) {
+            assert!(OPTIONS.contains(answer), "{}", answer);
+        }
+    }
+
+    #[test]
+    fn test_options_are_distinct() {
+        for seed in 0..50 {
+            let q = generate(Difficulty::Medium, &mut StdRng::seed_from_u64(seed));
+            let mut options = q.options(seed);
+            assert_eq!(4, options.len());
+            options.sort();
+            options.dedup();
+            assert_eq!(4, options.len());
+        }
+    }
+}
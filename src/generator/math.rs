@@ -0,0 +1,98 @@
+// Mental arithmetic, answered with a number and won by the closest guess.
+use rand::Rng;
+
+use crate::difficulty::Difficulty;
+use crate::generator::GeneratedQuestion;
+use crate::question_type::QuestionType;
+
+pub const CATEGORY: &str = "Math";
+
+fn numeric(expression: String, answer: i64, difficulty: Difficulty) -> GeneratedQuestion {
+    GeneratedQuestion {
+        kind: QuestionType::Numeric,
+        question: format!("{} = ?", expression),
+        answer: answer.to_string(),
+        alternate_answers: vec![],
+        incorrect_answers: vec![],
+        category: CATEGORY,
+        difficulty,
+    }
+}
+
+pub fn generate<R: Rng>(difficulty: Difficulty, rng: &mut R) -> GeneratedQuestion {
+    match difficulty {
+        Difficulty::Easy => {
+            let a = rng.gen_range(10..100);
+            let b = rng.gen_range(10..100);
+            if rng.gen_bool(0.5) {
+                numeric(format!("{} + {}", a, b), a + b, difficulty)
+            } else {
+                let (a, b) = (a.max(b), a.min(b));
+                numeric(format!("{} - {}", a, b), a - b, difficulty)
+            }
+        }
+        Difficulty::Medium => {
+            let a = rng.gen_range(12..100);
+            let b = rng.gen_range(3..13);
+            // below a * b so a * b - c stays positive
+            let c = rng.gen_range(2..50.min(a * b));
+            match rng.gen_range(0..3) {
+                0 => numeric(format!("{} * {}", a, b), a * b, difficulty),
+                1 => numeric(format!("({} + {}) * {}", a, c, b), (a + c) * b, difficulty),
+                _ => numeric(format!("{} * {} - {}", a, b, c), a * b - c, difficulty),
+            }
+        }
+        Difficulty::Hard => {
+            let a = rng.gen_range(100..1000);
+            let b = rng.gen_range(12..100);
+            let c = rng.gen_range(3..20);
+            match rng.gen_range(0..3) {
+                0 => numeric(format!("{} * {}", a, b), a * b, difficulty),
+                1 => numeric(
+                    format!("{} % {}", a * c + b, c + b),
+                    (a * c + b) % (c + b),
+                    difficulty,
+                ),
+                _ => numeric(
+                    format!("{} / {} (rounded down)", a * b, c),
+                    a * b / c,
+                    difficulty,
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Checks the answer against the expression by evaluating the simple
+    // "a op b" forms, the ones with parentheses are checked by shape only.
+    #[test]
+    fn test_answers_match_expressions() {
+        for seed in 0..200 {
+            for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                let q = generate(difficulty, &mut StdRng::seed_from_u64(seed));
+                assert!(q.question.ends_with(" = ?"));
+                let answer: i64 = q.answer.parse().unwrap();
+                let parts = q.question.split_whitespace().collect::<Vec<&str>>();
+                if parts.len() == 5 {
+                    let a: i64 = parts[0].parse().unwrap();
+                    let b: i64 = parts[2].parse().unwrap();
+                    let expected = match parts[1] {
+                        "+" => a + b,
+                        "-" => a - b,
+                        "*" => a * b,
+                        "%" => a % b,
+                        op => panic!("unexpected operator {}", op),
+                    };
+                    assert_eq!(expected, answer, "{}", q.question);
+                }
+                assert!(answer >= 0);
+            }
+        }
+    }
+}
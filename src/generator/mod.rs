@@ -0,0 +1,207 @@
+// Questions that are made up when a duel asks for them instead of being stored in
+// the questions table. A duel picks a generator with a topic, e.g. `!duel @user 100 scramble`.
+// Generation is deterministic: the same topic, difficulty and seed always give the
+// same question, which is how a duel's question is judged and replayed later.
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::db;
+use crate::difficulty::Difficulty;
+use crate::models::QuestionStatus;
+use crate::question_type::{self, QuestionType};
+
+pub mod bits;
+pub mod complexity;
+pub mod math;
+pub mod rust_output;
+pub mod scramble;
+
+#[derive(Debug, Clone)]
+pub struct GeneratedQuestion {
+    pub kind: QuestionType,
+    pub question: String,
+    pub answer: String,
+    pub alternate_answers: Vec<String>,
+    pub incorrect_answers: Vec<String>,
+    pub category: &'static str,
+    pub difficulty: Difficulty,
+}
+
+impl GeneratedQuestion {
+    pub fn free_text(
+        question: String,
+        answer: String,
+        category: &'static str,
+        difficulty: Difficulty,
+    ) -> GeneratedQuestion {
+        GeneratedQuestion {
+            kind: QuestionType::FreeText,
+            question,
+            answer,
+            alternate_answers: vec![],
+            incorrect_answers: vec![],
+            category,
+            difficulty,
+        }
+    }
+
+    pub fn options(&self, seed: u64) -> Vec<String> {
+        match self.kind {
+            QuestionType::MultipleChoice => {
+                question_type::shuffled_options(&self.answer, &self.incorrect_answers, seed)
+            }
+            _ => vec![],
+        }
+    }
+
+    pub fn prompt(&self, seed: u64) -> String {
+        question_type::prompt(&self.question, &self.options(seed))
+    }
+
+    pub fn accepts(&self, guess: &str, seed: u64) -> bool {
+        question_type::accepts(
+            self.kind,
+            &self.answer,
+            &self.alternate_answers,
+            &self.options(seed),
+            guess,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    Scramble,
+    Math,
+    Bits,
+    RustOutput,
+    Complexity,
+}
+
+impl Topic {
+    pub const ALL: &'static [Topic] = &[
+        Topic::Scramble,
+        Topic::Math,
+        Topic::Bits,
+        Topic::RustOutput,
+        Topic::Complexity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::Scramble => "scramble",
+            Topic::Math => "math",
+            Topic::Bits => "bits",
+            Topic::RustOutput => "rust",
+            Topic::Complexity => "complexity",
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Topic {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Topic::ALL
+            .iter()
+            .find(|topic| topic.as_str() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| anyhow!("unknown topic {}", s))
+    }
+}
+
+pub fn topic_names() -> Vec<&'static str> {
+    Topic::ALL.iter().map(Topic::as_str).collect()
+}
+
+// Set SCRAMBLE_FROM_ANSWERS=true to also scramble one word answers of approved questions.
+fn scramble_words() -> Vec<String> {
+    let mut words = scramble::bundled_words();
+    let from_answers = std::env::var("SCRAMBLE_FROM_ANSWERS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    if from_answers {
+        words.extend(
+            db::get_questions()
+                .into_iter()
+                .filter(|q| q.status == QuestionStatus::Approved.as_str())
+                .map(|q| q.answer.trim().to_lowercase())
+                .filter(|a| scramble::is_scrambleable(a)),
+        );
+        words.sort();
+        words.dedup();
+    }
+    words
+}
+
+// Without a requested difficulty one is picked from the seed first, so generating
+// again with the difficulty that was picked gives back the same question.
+pub fn generate(topic: Topic, difficulty: Option<Difficulty>, seed: u64) -> GeneratedQuestion {
+    let difficulty = difficulty.unwrap_or_else(|| {
+        *[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+            .choose(&mut StdRng::seed_from_u64(seed))
+            .unwrap()
+    });
+    let mut rng = StdRng::seed_from_u64(seed);
+    match topic {
+        // when the requested tier has no words the next one that does is used, with
+        // a fresh rng so asking again for the tier that was used gives the same word
+        Topic::Scramble => {
+            let words = scramble_words();
+            std::iter::once(difficulty)
+                .chain([Difficulty::Easy, Difficulty::Medium, Difficulty::Hard])
+                .find_map(|d| scramble::generate(&words, Some(d), &mut StdRng::seed_from_u64(seed)))
+                .expect("bundled scramble word list is empty")
+        }
+        Topic::Math => math::generate(difficulty, &mut rng),
+        Topic::Bits => bits::generate(difficulty, &mut rng),
+        Topic::RustOutput => rust_output::generate(difficulty, &mut rng),
+        Topic::Complexity => complexity::generate(difficulty, &mut rng),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_replayable() {
+        for topic in [
+            Topic::Scramble,
+            Topic::Math,
+            Topic::Bits,
+            Topic::RustOutput,
+            Topic::Complexity,
+        ] {
+            for seed in 0..20 {
+                let asked = generate(topic, None, seed);
+                let replayed = generate(topic, Some(asked.difficulty), seed);
+                assert_eq!(asked.prompt(seed), replayed.prompt(seed));
+                assert_eq!(asked.answer, replayed.answer);
+                let guess = match asked.options(seed).iter().position(|o| *o == asked.answer) {
+                    Some(i) => question_type::option_letter(i).to_string(),
+                    None => asked.answer.clone(),
+                };
+                assert!(asked.accepts(&guess, seed));
+            }
+        }
+    }
+
+    #[test]
+    fn test_topic_names() {
+        for topic in Topic::ALL {
+            assert_eq!(*topic, topic.as_str().parse::<Topic>().unwrap());
+        }
+        assert!("history".parse::<Topic>().is_err());
+    }
+}
@@ -0,0 +1,144 @@
+// "What does this Rust snippet print?" Each template fills in random values and
+// works out the output itself, so the snippets never need compiling.
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::difficulty::Difficulty;
+use crate::generator::GeneratedQuestion;
+
+pub const CATEGORY: &str = "Rust Output";
+
+const WORDS: &[&str] = &[
+    "ferris", "cargo", "borrow", "crate", "trait", "tokio", "unsafe", "lifetime",
+];
+
+fn easy<R: Rng>(rng: &mut R) -> (String, String) {
+    match rng.gen_range(0..3) {
+        0 => {
+            let (a, b) = (rng.gen_range(2..10), rng.gen_range(2..10));
+            (
+                format!("let x = {}; let x = x * {}; println!(\"{{x}}\");", a, b),
+                (a * b).to_string(),
+            )
+        }
+        1 => {
+            let v = (0..3).map(|_| rng.gen_range(1..20)).collect::<Vec<i32>>();
+            (
+                format!(
+                    "let v = vec![{}, {}, {}]; println!(\"{{}}\", v.iter().sum::<i32>());",
+                    v[0], v[1], v[2]
+                ),
+                v.iter().sum::<i32>().to_string(),
+            )
+        }
+        _ => {
+            let word = WORDS.choose(rng).unwrap();
+            (
+                format!("println!(\"{{}}\", \"{}\".len());", word),
+                word.len().to_string(),
+            )
+        }
+    }
+}
+
+fn medium<R: Rng>(rng: &mut R) -> (String, String) {
+    match rng.gen_range(0..3) {
+        0 => {
+            let (a, b) = (rng.gen_range(10..100), rng.gen_range(3..10));
+            (
+                format!("println!(\"{{}} {{}}\", {} / {}, {} % {});", a, b, a, b),
+                format!("{} {}", a / b, a % b),
+            )
+        }
+        1 => {
+            let word = WORDS.choose(rng).unwrap();
+            (
+                format!(
+                    "println!(\"{{}}\", \"{}\".chars().rev().collect::<String>());",
+                    word
+                ),
+                word.chars().rev().collect(),
+            )
+        }
+        _ => {
+            let n = rng.gen_range(5..30);
+            (
+                format!(
+                    "println!(\"{{}}\", (1..={}).filter(|x| x % 2 == 0).count());",
+                    n
+                ),
+                (n / 2).to_string(),
+            )
+        }
+    }
+}
+
+fn hard<R: Rng>(rng: &mut R) -> (String, String) {
+    match rng.gen_range(0..3) {
+        0 => {
+            let n = rng.gen_range(1..20u8);
+            (
+                format!("println!(\"{{}}\", u8::MAX.wrapping_add({}));", n),
+                u8::MAX.wrapping_add(n).to_string(),
+            )
+        }
+        1 => {
+            let (n, step) = (rng.gen_range(10..40), rng.gen_range(3..8));
+            let mut count = 0;
+            for i in 0..n {
+                if i % step == 0 {
+                    count += 1;
+                }
+            }
+            (
+                format!(
+                    "let mut n = 0; for i in 0..{} {{ if i % {} == 0 {{ n += 1; }} }} println!(\"{{n}}\");",
+                    n, step
+                ),
+                count.to_string(),
+            )
+        }
+        _ => {
+            let n = rng.gen_range(-100..-1i32);
+            (
+                format!("println!(\"{{}}\", {}i32 as u8);", n),
+                (n as u8).to_string(),
+            )
+        }
+    }
+}
+
+pub fn generate<R: Rng>(difficulty: Difficulty, rng: &mut R) -> GeneratedQuestion {
+    let (snippet, output) = match difficulty {
+        Difficulty::Easy => easy(rng),
+        Difficulty::Medium => medium(rng),
+        Difficulty::Hard => hard(rng),
+    };
+    GeneratedQuestion::free_text(
+        format!("What does this print? {}", snippet),
+        output,
+        CATEGORY,
+        difficulty,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_outputs_are_worked_out() {
+        for seed in 0..100 {
+            for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                let q = generate(difficulty, &mut StdRng::seed_from_u64(seed));
+                assert!(q.question.contains("println!"));
+                assert!(!q.answer.is_empty());
+            }
+        }
+        // spot check against what rustc actually prints
+        assert_eq!("4", (-252i32 as u8).to_string());
+        assert_eq!("9", u8::MAX.wrapping_add(10).to_string());
+    }
+}
@@ -0,0 +1,123 @@
+// Word Scramble questions made up on the spot from a list of words.
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::difficulty::Difficulty;
+use crate::generator::GeneratedQuestion;
+
+pub const CATEGORY: &str = "Word Scramble";
+
+const WORD_LIST: &str = include_str!("scramble_words.txt");
+
+// Anything shorter is too easy to guess, anything longer is a wall of letters.
+const MIN_WORD_LEN: usize = 4;
+const MAX_WORD_LEN: usize = 12;
+
+pub fn bundled_words() -> Vec<String> {
+    WORD_LIST
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Words that can't be scrambled into something different, like "aaaa", are left out.
+pub fn is_scrambleable(word: &str) -> bool {
+    let len = word.chars().count();
+    (MIN_WORD_LEN..=MAX_WORD_LEN).contains(&len)
+        && word.chars().all(|c| c.is_ascii_alphabetic())
+        && word.chars().any(|c| !word.starts_with(c))
+}
+
+pub fn word_difficulty(word: &str) -> Difficulty {
+    match word.chars().count() {
+        0..=5 => Difficulty::Easy,
+        6..=7 => Difficulty::Medium,
+        _ => Difficulty::Hard,
+    }
+}
+
+// Never hands back the word itself, as long as it has two different letters.
+pub fn scramble<R: Rng>(word: &str, rng: &mut R) -> String {
+    let mut letters = word.chars().collect::<Vec<char>>();
+    letters.shuffle(rng);
+    let mut scrambled = letters.iter().collect::<String>();
+    if scrambled == word {
+        // shifting every letter along by one only leaves a word unchanged
+        // when all of its letters are the same
+        letters.rotate_left(1);
+        scrambled = letters.iter().collect();
+    }
+    scrambled
+}
+
+pub fn generate<R: Rng>(
+    words: &[String],
+    difficulty: Option<Difficulty>,
+    rng: &mut R,
+) -> Option<GeneratedQuestion> {
+    let candidates = words
+        .iter()
+        .filter(|w| is_scrambleable(w))
+        .filter(|w| difficulty.is_none_or(|d| word_difficulty(w) == d))
+        .collect::<Vec<&String>>();
+    let word = candidates.choose(rng)?;
+
+    Some(GeneratedQuestion::free_text(
+        scramble(word, rng),
+        word.to_string(),
+        CATEGORY,
+        word_difficulty(word),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_scramble_is_never_the_word() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for word in ["pool", "tolu", "abab", "ab", "lasagna"] {
+            for _ in 0..50 {
+                let scrambled = scramble(word, &mut rng);
+                assert_ne!(word, scrambled);
+                let mut a = word.chars().collect::<Vec<char>>();
+                let mut b = scrambled.chars().collect::<Vec<char>>();
+                a.sort();
+                b.sort();
+                assert_eq!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bundled_words() {
+        let words = bundled_words();
+        assert!(words.len() > 100);
+        assert!(words.iter().all(|w| is_scrambleable(w)));
+        assert!(!is_scrambleable("aaaa"));
+        assert!(!is_scrambleable("c++"));
+    }
+
+    #[test]
+    fn test_generate_respects_difficulty_and_seed() {
+        let words = bundled_words();
+        let hard = generate(
+            &words,
+            Some(Difficulty::Hard),
+            &mut StdRng::seed_from_u64(3),
+        )
+        .unwrap();
+        assert_eq!(Difficulty::Hard, hard.difficulty);
+        assert!(hard.answer.len() >= 8);
+
+        let a = generate(&words, None, &mut StdRng::seed_from_u64(9)).unwrap();
+        let b = generate(&words, None, &mut StdRng::seed_from_u64(9)).unwrap();
+        assert_eq!(a.question, b.question);
+        assert!(generate(&[], None, &mut StdRng::seed_from_u64(9)).is_none());
+    }
+}
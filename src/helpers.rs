@@ -19,6 +19,17 @@ pub fn relative_time_string(datetime: NaiveDateTime) -> String {
     }
 }
 
+// The number in the environment variable, or the default when it isn't set.
+pub fn env_number<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("{} should be a number", name)),
+        Err(_) => default,
+    }
+}
+
 pub fn overflow_add(a: i64, b: i64) -> i64 {
     // calls built-in function checked_add, if returns none, function returns a
     match a.checked_add(b) {
@@ -1,9 +1,23 @@
+pub mod casino;
 pub mod chatter;
 pub mod commands;
+pub mod daily;
 pub mod db;
+pub mod difficulty;
+pub mod earning;
+pub mod generator;
 pub mod helpers;
+pub mod market;
 pub mod messaging;
 pub mod models;
+pub mod opentdb;
+pub mod order_book;
+pub mod pool;
+pub mod question_pack;
+pub mod question_source;
+pub mod question_type;
 pub mod schema;
 pub mod seed;
+pub mod shop;
 pub mod state;
+pub mod watch_time;
@@ -4,43 +4,53 @@ use tmi::client::ConnectError;
 use tmi::Client;
 use tokio::select;
 use tokio::signal::ctrl_c;
-use twitch_api2::{helix::channels::GetChannelInformationRequest, TwitchClient};
+use tokio::sync::mpsc;
 
-use state::State;
-
-mod chatter;
-mod commands;
-mod db;
-mod helpers;
-mod messaging;
-mod models;
-mod schema;
-mod seed;
-mod state;
+use duel_bot::casino::{blackjack, Casino};
+use duel_bot::state::State;
+use duel_bot::watch_time::{self, HelixClient, WatchConfig};
+use duel_bot::{market, messaging, pool, seed};
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
     dotenv().ok();
     let broadcaster_id = std::env::var("BROADCASTER_ID").expect("BROADCASTER_ID must be set");
-    let _bot_id: String = std::env::var("BOT_TWITCH_ID").expect("BOT_TWITCH_ID must be set.");
-    let client_secret = std::env::var("TWITCH_CLIENT_SECRET")
-        .expect("TWITCH_CLIENT_SECRET must be set.")
-        .into();
-    let client_id = std::env::var("TWITCH_CLIENT_ID")
-        .expect("TWITCH_CLIENT_ID must be set.")
-        .into();
-
+    let bot_id: String = std::env::var("BOT_TWITCH_ID").expect("BOT_TWITCH_ID must be set.");
     let token = std::env::var("BOT_OAUTH_TOKEN").expect("BOT_OAUTH_TOKEN must be set.");
     let oauth = std::fmt::format(format_args!("oauth:{}", token));
     let user: String = std::env::var("BOT_USERNAME").expect("BOT_USERNAME must be set.");
 
     seed::seed_initial_data();
 
-    let mut client =
-        match get_client(broadcaster_id, client_secret, client_id, token, oauth, user).await {
-            Ok(c) => c,
-            Err(_err) => panic!("Connection was not successful!"),
-        };
+    tokio::spawn(market::run(market::tick_interval()));
+    tokio::spawn(blackjack::run(Casino::from_env()));
+
+    // what the pool schedule wants said in chat
+    let (announce, announcements) = mpsc::channel(16);
+    let schedule = pool::Schedule::from_env();
+    if schedule.is_enabled() {
+        tokio::spawn(pool::run(
+            schedule,
+            pool::Payouts::from_env(),
+            HelixClient::from_env(),
+            broadcaster_id.clone(),
+            announce,
+        ));
+    }
+
+    if let Some(config) = WatchConfig::from_env() {
+        tokio::spawn(watch_time::run(
+            HelixClient::from_env(),
+            config,
+            broadcaster_id.clone(),
+            bot_id,
+        ));
+    }
+
+    let mut client = match get_client(oauth, user).await {
+        Ok(c) => c,
+        Err(_err) => panic!("Connection was not successful!"),
+    };
 
     let channels = vec!["#ToluAfo".to_string()]
         .into_iter()
@@ -54,26 +64,13 @@ async fn main() -> Result<()> {
       _ = ctrl_c() => {
         Ok(())
       }
-      res = tokio::spawn(run(client, channels)) => {
+      res = tokio::spawn(run(client, channels, announcements)) => {
         res?
       }
     }
 }
 
-async fn get_client(
-    broadcaster_id: String,
-    _client_secret: String,
-    _client_id: String,
-    _token: String,
-    oauth: String,
-    user: String,
-) -> Result<Client, ConnectError> {
-    let _twitch_client: TwitchClient<reqwest::Client> = TwitchClient::default();
-
-    let _req = GetChannelInformationRequest::builder()
-        .broadcaster_id(broadcaster_id)
-        .build();
-
+async fn get_client(oauth: String, user: String) -> Result<Client, ConnectError> {
     let credentials = tmi::client::Credentials::new(user, oauth);
 
     println!("Connecting as {}", credentials.nick);
@@ -84,21 +81,34 @@ async fn get_client(
     Ok(client)
 }
 
-async fn run(mut client: tmi::Client, channels: Vec<tmi::Channel>) -> Result<()> {
+async fn run(
+    mut client: tmi::Client,
+    channels: Vec<tmi::Channel>,
+    mut announcements: mpsc::Receiver<String>,
+) -> Result<()> {
     let mut bot_state = State::new();
 
     loop {
-        let msg = client.recv().await?;
-        match msg.as_typed()? {
-            tmi::Message::Privmsg(msg) => {
-                messaging::on_msg(&mut client, &msg, &mut bot_state).await?
-            }
-            tmi::Message::Reconnect => {
-                client.reconnect().await?;
-                client.join_all(&channels).await?;
+        select! {
+          msg = client.recv() => {
+            let msg = msg?;
+            match msg.as_typed()? {
+                tmi::Message::Privmsg(msg) => {
+                    messaging::on_msg(&mut client, &msg, &mut bot_state).await?
+                }
+                tmi::Message::Reconnect => {
+                    client.reconnect().await?;
+                    client.join_all(&channels).await?;
+                }
+                tmi::Message::Ping(ping) => client.pong(&ping).await?,
+                _ => {}
+            };
+          }
+          Some(text) = announcements.recv() => {
+            for channel in &channels {
+                client.privmsg(channel, &text).send().await?;
             }
-            tmi::Message::Ping(ping) => client.pong(&ping).await?,
-            _ => {}
-        };
+          }
+        }
     }
 }
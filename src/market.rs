@@ -0,0 +1,484 @@
+// The simulated stock market. Prices wander at random every tick while drifting
+// towards a target price, which gets picked again once the price gets there.
+//
+// What happens on stream moves prices too. The DUEL index goes up with the points
+// wagered on duels and the GAMBLE index with what the house makes off gamblers,
+// both settling back to their starting price when things are quiet, and mods can
+// !pump and !dump the other stocks.
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive};
+use rand::Rng;
+
+use crate::db;
+use crate::models::{MarketEvent, MarketEventKind, NewMarketEvent, NewStock, NewStockPrice};
+
+// Shares never get cheaper than this.
+pub const MIN_PRICE: f64 = 1.0;
+
+// Or dearer than this, prices are NUMERIC(12, 2) and enough !pump would overflow it.
+pub const MAX_PRICE: f64 = 1_000_000_000.0;
+
+// Largest random move a tick makes, as a fraction of the price.
+const VOLATILITY: f64 = 0.05;
+
+// How much of the way to the target price a tick covers.
+const DRIFT: f64 = 0.1;
+
+// The stocks a fresh install starts with: (name, symbol, price).
+pub const STARTER_STOCKS: &[(&str, &str, f64)] = &[
+    ("Ferris Foods", "FRS", 100.0),
+    ("Borrow Checker Inc", "BRW", 50.0),
+    ("Cargo Shipping", "CRGO", 25.0),
+    ("Tokio Time", "TKIO", 75.0),
+    ("Unsafe Ventures", "UNSF", 10.0),
+];
+
+pub const DUEL_INDEX: &str = "DUEL";
+pub const GAMBLE_INDEX: &str = "GAMBLE";
+
+// Stocks that follow what happens on stream, every install gets these.
+pub const INDEX_STOCKS: &[(&str, &str, f64)] = &[
+    ("Duel Index", DUEL_INDEX, 100.0),
+    ("Gamble Index", GAMBLE_INDEX, 100.0),
+];
+
+// Points wagered on duels, or won by the house, that move an index by 1%.
+const DUEL_POINTS_PER_PERCENT: i64 = 100;
+const GAMBLE_POINTS_PER_PERCENT: i64 = 100;
+
+// The most events can move a price in one tick, in percent.
+const MAX_SHOCK: f64 = 50.0;
+
+pub fn is_index(symbol: &str) -> bool {
+    INDEX_STOCKS.iter().any(|(_, index, _)| *index == symbol)
+}
+
+pub fn to_price(value: f64) -> BigDecimal {
+    BigDecimal::from_str(&format!("{:.2}", value)).expect("A formatted f64 is a valid decimal")
+}
+
+pub fn to_f64(value: &BigDecimal) -> f64 {
+    value.to_f64().unwrap_or(MIN_PRICE)
+}
+
+// Points it takes to buy the shares, rounded up.
+pub fn cost(price: &BigDecimal, shares: i32) -> i64 {
+    (price * BigDecimal::from(shares))
+        .with_scale_round(0, RoundingMode::Ceiling)
+        .to_i64()
+        .unwrap_or(i64::MAX)
+}
+
+// Points selling the shares brings in, rounded down.
+pub fn proceeds(price: &BigDecimal, shares: i32) -> i64 {
+    (price * BigDecimal::from(shares))
+        .with_scale_round(0, RoundingMode::Floor)
+        .to_i64()
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shares {
+    Count(i32),
+    All,
+}
+
+// Arguments of !buy and !sell: <symbol> <shares>, and !sell also takes "all".
+pub fn parse_trade(args: &str) -> Option<(String, Shares)> {
+    let mut words = args.split_whitespace();
+    let symbol = words.next()?.to_uppercase();
+    let shares = match words.next()? {
+        all if all.eq_ignore_ascii_case("all") => Shares::All,
+        count => Shares::Count(count.parse().ok().filter(|c| *c > 0)?),
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some((symbol, shares))
+}
+
+// Arguments of !pump and !dump: <symbol> [percent], 10% unless given.
+pub fn parse_nudge(args: &str) -> Option<(String, i64)> {
+    let mut words = args.split_whitespace();
+    let symbol = words.next()?.to_uppercase();
+    let percent = match words.next() {
+        Some(percent) => percent
+            .trim_end_matches('%')
+            .parse::<i64>()
+            .ok()
+            .filter(|p| (1..=MAX_SHOCK as i64).contains(p))?,
+        None => 10,
+    };
+    Some((symbol, percent))
+}
+
+// Arguments of !addstock: <symbol> <price> <name>
+pub fn parse_new_stock(args: &str) -> Option<(String, f64, String)> {
+    let mut words = args.split_whitespace();
+    let symbol = words.next()?.to_uppercase();
+    if symbol.len() > 6 || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let price = words
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|p| (MIN_PRICE..1_000_000.0).contains(p))?;
+    let name = words.collect::<Vec<&str>>().join(" ");
+    if name.is_empty() {
+        return None;
+    }
+    Some((symbol, price, name))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    pub price: f64,
+    pub target: f64,
+    // percent the price moved
+    pub change: f64,
+}
+
+pub fn tick<R: Rng>(price: f64, target: f64, rng: &mut R) -> Tick {
+    let drift = (target - price) / price * DRIFT;
+    let noise = rng.gen_range(-VOLATILITY..VOLATILITY);
+    let new_price = (price * (1.0 + drift + noise)).clamp(MIN_PRICE, MAX_PRICE);
+
+    // once the price gets near its target, and every so often anyway, pick a new one
+    let target = if (new_price - target).abs() / target < 0.02 || rng.gen_bool(0.05) {
+        (new_price * rng.gen_range(0.5..1.5)).clamp(MIN_PRICE, MAX_PRICE)
+    } else {
+        target
+    };
+    Tick {
+        price: new_price,
+        target,
+        change: (new_price - price) / price * 100.0,
+    }
+}
+
+// Same as tick, but for an index: it barely wanders on its own and its target is
+// where it settles when nothing happens.
+pub fn tick_index<R: Rng>(price: f64, target: f64, rng: &mut R) -> Tick {
+    let drift = (target - price) / price * DRIFT;
+    let noise = rng.gen_range(-VOLATILITY..VOLATILITY) / 5.0;
+    let new_price = (price * (1.0 + drift + noise)).clamp(MIN_PRICE, MAX_PRICE);
+    Tick {
+        price: new_price,
+        target,
+        change: (new_price - price) / price * 100.0,
+    }
+}
+
+// Percent the events move a price by.
+pub fn shock(events: &[&MarketEvent]) -> f64 {
+    let percent = events
+        .iter()
+        .map(|event| match event.kind.parse() {
+            Ok(MarketEventKind::Duel) => event.amount as f64 / DUEL_POINTS_PER_PERCENT as f64,
+            Ok(MarketEventKind::Gamble) => event.amount as f64 / GAMBLE_POINTS_PER_PERCENT as f64,
+            Ok(MarketEventKind::Pump) => event.amount as f64,
+            Ok(MarketEventKind::Dump) => -(event.amount as f64),
+            Err(_) => 0.0,
+        })
+        .sum::<f64>();
+    percent.clamp(-MAX_SHOCK, MAX_SHOCK)
+}
+
+// Moves the price by the shock. An index still settles back where it was, other
+// stocks keep the new level by moving their target along.
+pub fn apply_shock(tick: Tick, shock: f64, index: bool) -> Tick {
+    if shock == 0.0 {
+        return tick;
+    }
+    let before = tick.price / (1.0 + tick.change / 100.0);
+    let price = (tick.price * (1.0 + shock / 100.0)).clamp(MIN_PRICE, MAX_PRICE);
+    let target = if index {
+        tick.target
+    } else {
+        (tick.target * (1.0 + shock / 100.0)).clamp(MIN_PRICE, MAX_PRICE)
+    };
+    Tick {
+        price,
+        target,
+        change: (price - before) / before * 100.0,
+    }
+}
+
+// Queues an event for the stock, the next tick moves its price.
+pub fn record_event(symbol: &str, kind: MarketEventKind, amount: i64) {
+    if amount == 0 {
+        return;
+    }
+    if let Some(stock) = db::get_stock_by_symbol(symbol) {
+        db::add_market_event(&NewMarketEvent {
+            stock_id: stock.id,
+            kind: kind.as_str(),
+            amount,
+        });
+    }
+}
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub fn sparkline(prices: &[f64]) -> String {
+    let low = prices.iter().cloned().fold(f64::INFINITY, f64::min);
+    let high = prices.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    prices
+        .iter()
+        .map(|price| {
+            if high <= low {
+                SPARKS[SPARKS.len() / 2]
+            } else {
+                let level = (price - low) / (high - low) * (SPARKS.len() - 1) as f64;
+                SPARKS[level.round() as usize]
+            }
+        })
+        .collect()
+}
+
+pub fn seed_stocks() {
+    for (name, symbol, price) in STARTER_STOCKS {
+        db::create_stock(&NewStock {
+            name,
+            symbol,
+            ticket_price: to_price(*price),
+            future_value: to_price(*price),
+        });
+    }
+}
+
+// Adds the index stocks an install doesn't have yet.
+pub fn seed_index_stocks() {
+    for (name, symbol, price) in INDEX_STOCKS {
+        if db::get_stock_by_symbol(symbol).is_none() {
+            db::create_stock(&NewStock {
+                name,
+                symbol,
+                ticket_price: to_price(*price),
+                future_value: to_price(*price),
+            });
+        }
+    }
+}
+
+// Moves every stock's price once and saves it to the price history.
+pub fn tick_all() -> anyhow::Result<()> {
+    let mut rng = rand::thread_rng();
+    let stocks = db::get_stocks()?;
+    let events = db::take_market_events()?;
+    let mut events_by_stock: HashMap<i32, Vec<&MarketEvent>> = HashMap::new();
+    for event in events.iter() {
+        events_by_stock
+            .entry(event.stock_id)
+            .or_default()
+            .push(event);
+    }
+
+    for stock in stocks {
+        let (price, target) = (to_f64(&stock.ticket_price), to_f64(&stock.future_value));
+        let index = is_index(&stock.symbol);
+        let next = if index {
+            tick_index(price, target, &mut rng)
+        } else {
+            tick(price, target, &mut rng)
+        };
+        let shock = shock(events_by_stock.get(&stock.id).map_or(&[], |e| e.as_slice()));
+        let next = apply_shock(next, shock, index);
+
+        let new_price = to_price(next.price);
+        db::update_stock_price(
+            stock.id,
+            &new_price,
+            &to_price(next.target),
+            &to_price(next.change),
+        );
+        db::add_stock_price(&NewStockPrice {
+            stock_id: stock.id,
+            price: &new_price,
+        });
+    }
+    Ok(())
+}
+
+// STOCK_TICK_MINUTES is how often prices move, every 5 minutes by default.
+pub fn tick_interval() -> Duration {
+    let minutes: u64 = std::env::var("STOCK_TICK_MINUTES")
+        .map(|m| {
+            m.trim()
+                .parse()
+                .expect("STOCK_TICK_MINUTES should be a number of minutes")
+        })
+        .unwrap_or(5);
+    Duration::from_secs(minutes.max(1) * 60)
+}
+
+pub async fn run(interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    // the first tick is immediate, prices move after the first interval
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(err) = tick_all() {
+            println!("An error occurred while moving stock prices: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_cost_and_proceeds_round_in_the_houses_favour() {
+        let price = to_price(10.333);
+        assert_eq!("10.33", price.to_string());
+        assert_eq!(31, cost(&price, 3));
+        assert_eq!(30, proceeds(&price, 3));
+        assert_eq!(1033, cost(&price, 100));
+        assert_eq!(1033, proceeds(&price, 100));
+    }
+
+    #[test]
+    fn test_parse_trade() {
+        assert_eq!(
+            Some(("FRS".to_string(), Shares::Count(3))),
+            parse_trade("frs 3")
+        );
+        assert_eq!(
+            Some(("FRS".to_string(), Shares::All)),
+            parse_trade("FRS All")
+        );
+        assert_eq!(None, parse_trade("frs"));
+        assert_eq!(None, parse_trade("frs 0"));
+        assert_eq!(None, parse_trade("frs -2"));
+        assert_eq!(None, parse_trade("highlight look at this"));
+    }
+
+    fn event(kind: MarketEventKind, amount: i64) -> MarketEvent {
+        let now = chrono::Utc::now().naive_utc();
+        MarketEvent {
+            id: 1,
+            stock_id: 1,
+            kind: kind.as_str().to_string(),
+            amount,
+            applied_at: None,
+            created_at: now,
+        }
+    }
+
+    #[test]
+    fn test_shock() {
+        let duels = [
+            event(MarketEventKind::Duel, 300),
+            event(MarketEventKind::Duel, 200),
+        ];
+        assert_eq!(5.0, shock(&duels.iter().collect::<Vec<&MarketEvent>>()));
+        let gambles = [
+            event(MarketEventKind::Gamble, 500),
+            event(MarketEventKind::Gamble, -800),
+        ];
+        assert_eq!(-3.0, shock(&gambles.iter().collect::<Vec<&MarketEvent>>()));
+        let pumps = [
+            event(MarketEventKind::Pump, 40),
+            event(MarketEventKind::Pump, 40),
+        ];
+        assert_eq!(
+            MAX_SHOCK,
+            shock(&pumps.iter().collect::<Vec<&MarketEvent>>())
+        );
+        assert_eq!(0.0, shock(&[]));
+    }
+
+    #[test]
+    fn test_apply_shock() {
+        let tick = Tick {
+            price: 110.0,
+            target: 120.0,
+            change: 10.0,
+        };
+        let pumped = apply_shock(tick, 10.0, false);
+        assert!((pumped.price - 121.0).abs() < 1e-9);
+        assert!((pumped.target - 132.0).abs() < 1e-9);
+        assert!((pumped.change - 21.0).abs() < 1e-9);
+        // an index settles back where it was
+        assert_eq!(120.0, apply_shock(tick, 10.0, true).target);
+        assert_eq!(tick, apply_shock(tick, 0.0, false));
+    }
+
+    #[test]
+    fn test_index_settles_back() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut price = 150.0;
+        for _ in 0..100 {
+            price = tick_index(price, 100.0, &mut rng).price;
+        }
+        assert!((price - 100.0).abs() < 5.0, "{}", price);
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!("▁▅█▅▁", sparkline(&[1.0, 2.0, 3.0, 2.0, 1.0]));
+        assert_eq!("▅▅▅", sparkline(&[5.0, 5.0, 5.0]));
+        assert_eq!("", sparkline(&[]));
+    }
+
+    #[test]
+    fn test_parse_nudge_and_new_stock() {
+        assert_eq!(Some(("FRS".to_string(), 10)), parse_nudge("frs"));
+        assert_eq!(Some(("FRS".to_string(), 25)), parse_nudge("frs 25%"));
+        assert_eq!(None, parse_nudge("frs 0"));
+        assert_eq!(None, parse_nudge("frs 51"));
+        assert_eq!(
+            Some(("HYDR8".to_string(), 12.5, "Hydration Co".to_string())),
+            parse_new_stock("hydr8 12.5 Hydration Co")
+        );
+        assert_eq!(None, parse_new_stock("HYDR8 12.5"));
+        assert_eq!(None, parse_new_stock("HYDRATE 12.5 Hydration Co"));
+        assert_eq!(None, parse_new_stock("H-2 12.5 Hydration Co"));
+        assert_eq!(None, parse_new_stock("HYDR8 0.5 Hydration Co"));
+    }
+
+    #[test]
+    fn test_prices_stay_positive() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let (mut price, mut target) = (2.0, 1.0);
+        for _ in 0..10_000 {
+            let next = tick(price, target, &mut rng);
+            assert!(next.price >= MIN_PRICE && next.target >= MIN_PRICE);
+            (price, target) = (next.price, next.target);
+        }
+    }
+
+    #[test]
+    fn test_pumping_stops_at_the_max_price() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let (mut price, mut target) = (100.0, 100.0);
+        for _ in 0..1_000 {
+            let next = apply_shock(tick(price, target, &mut rng), MAX_SHOCK, false);
+            assert!(next.price <= MAX_PRICE && next.target <= MAX_PRICE);
+            assert!(to_price(next.price).digits() <= 12);
+            (price, target) = (next.price, next.target);
+        }
+        assert_eq!(MAX_PRICE, price);
+    }
+
+    #[test]
+    fn test_prices_drift_towards_target() {
+        // with the target far off, the noise averages out and the drift wins
+        let mut rng = StdRng::seed_from_u64(7);
+        let ups = (0..1000)
+            .filter(|_| tick(100.0, 200.0, &mut rng).change > 0.0)
+            .count();
+        assert!(ups > 900, "{}", ups);
+        let downs = (0..1000)
+            .filter(|_| tick(100.0, 50.0, &mut rng).change < 0.0)
+            .count();
+        assert!(downs > 900, "{}", downs);
+    }
+}
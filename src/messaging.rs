@@ -1,6 +1,9 @@
+use crate::casino::blackjack::Move;
 use crate::chatter::{add_points, unlurk};
 use crate::commands;
 use crate::db;
+use crate::models::MarketEventKind;
+use crate::order_book::Side;
 use crate::state::State;
 
 pub async fn send_duel_err(
@@ -29,21 +32,87 @@ pub async fn on_msg(
     println!("{}: {}", msg.sender().name(), msg.text());
     // dbg!(&msg);
     db::record_user_presence(client, msg).await;
-    add_points(msg.sender().id(), 5);
+    let earned = bot_state.earning.points_for(
+        msg.sender().id(),
+        &msg.sender().name(),
+        msg.text(),
+        chrono::Utc::now().naive_utc(),
+    );
+    if earned > 0 {
+        add_points(msg.sender().id(), earned);
+    }
 
     unlurk(client, msg);
 
     match msg.text().split_ascii_whitespace().next() {
         Some("!points") => commands::handle_points_command(client, msg).await,
+        Some("!daily") => commands::handle_daily_command(client, msg, bot_state).await,
+        Some("!streak") => commands::handle_streak_command(client, msg, bot_state).await,
+        Some("!shop") => commands::handle_shop_command(client, msg).await,
+        Some("!buy") => commands::handle_buy_command(client, msg).await,
+        Some("!stocks") => commands::stock::handle_stocks_command(client, msg).await,
+        Some("!sell") => commands::stock::handle_sell_command(client, msg).await,
+        Some("!portfolio") => commands::stock::handle_portfolio_command(client, msg).await,
+        Some("!bid") => commands::stock::handle_limit_order_command(client, msg, Side::Buy).await,
+        Some("!ask") => commands::stock::handle_limit_order_command(client, msg, Side::Sell).await,
+        Some("!cancel") => commands::stock::handle_cancel_order_command(client, msg).await,
+        Some("!orders") => commands::stock::handle_orders_command(client, msg).await,
+        Some("!book") => commands::stock::handle_book_command(client, msg).await,
+        Some("!trades") => commands::stock::handle_trades_command(client, msg).await,
+        Some("!chart") => commands::stock::handle_chart_command(client, msg).await,
+        Some("!pump") => {
+            commands::stock::handle_nudge_command(client, msg, MarketEventKind::Pump).await
+        }
+        Some("!dump") => {
+            commands::stock::handle_nudge_command(client, msg, MarketEventKind::Dump).await
+        }
+        Some("!addstock") => commands::stock::handle_addstock_command(client, msg).await,
+        Some("!additem") => commands::handle_additem_command(client, msg).await,
+        Some("!removeitem") => commands::handle_removeitem_command(client, msg).await,
+        Some("!redemptions") => commands::handle_redemptions_command(client, msg).await,
+        Some("!fulfill") => commands::handle_resolve_redemption_command(client, msg, true).await,
+        Some("!refund") => commands::handle_resolve_redemption_command(client, msg, false).await,
         Some("!commands") => commands::handle_commands_command(client, msg).await,
         Some("!help") => commands::handle_commands_command(client, msg).await,
-        Some("!gamble") => commands::handle_gamble_command(client, msg).await,
+        Some("!gamble") => commands::handle_gamble_command(client, msg, bot_state).await,
+        Some("!seed") => commands::handle_seed_command(client, msg).await,
+        Some("!verify") => commands::handle_verify_command(client, msg, bot_state).await,
+        Some("!casinostats") => commands::handle_casinostats_command(client, msg, bot_state).await,
+        Some("!gamblestats") => commands::handle_gamblestats_command(client, msg).await,
+        Some("!limits") => commands::handle_limits_command(client, msg, bot_state).await,
+        Some("!selfexclude") => commands::handle_selfexclude_command(client, msg).await,
+        Some("!setlimit") => commands::handle_setlimit_command(client, msg).await,
+        Some("!unexclude") => commands::handle_unexclude_command(client, msg).await,
+        Some("!blackjack") => {
+            commands::blackjack::handle_blackjack_command(client, msg, bot_state).await
+        }
+        Some("!hit") => {
+            commands::blackjack::handle_blackjack_move_command(client, msg, bot_state, Move::Hit)
+                .await
+        }
+        Some("!stand") => {
+            commands::blackjack::handle_blackjack_move_command(client, msg, bot_state, Move::Stand)
+                .await
+        }
+        Some("!double") => {
+            commands::blackjack::handle_blackjack_move_command(client, msg, bot_state, Move::Double)
+                .await
+        }
         Some("!github") => commands::handle_github_command(client, msg).await,
         Some("!botrepo") => commands::handle_botrepo_command(client, msg).await,
         Some("!yo") => commands::handle_yo_command(client, msg).await,
         Some("!listcategories") => commands::handle_listcategories_command(client, msg).await,
         Some("!addquestion") => commands::handle_addquestion_command(client, msg).await,
         Some("!addcategory") => commands::handle_addcategory_command(client, msg).await,
+        Some("!review") => commands::handle_review_command(client, msg).await,
+        Some("!approve") => commands::handle_approve_command(client, msg).await,
+        Some("!reject") => commands::handle_reject_command(client, msg).await,
+        Some("!report") => commands::handle_report_command(client, msg).await,
+        Some("!upvote") => commands::handle_vote_command(client, msg, true).await,
+        Some("!downvote") => commands::handle_vote_command(client, msg, false).await,
+        Some("!reports") => commands::handle_reports_command(client, msg).await,
+        Some("!confirmreport") => commands::handle_resolve_report_command(client, msg, true).await,
+        Some("!dismissreport") => commands::handle_resolve_report_command(client, msg, false).await,
         Some("!lurk") => commands::handle_lurk_command(client, msg).await,
         Some("!lurkers") => commands::handle_lurkers_command(client, msg).await,
         Some("!lurktime") => commands::handle_lurktime_command(client, msg).await,
@@ -60,7 +129,11 @@ pub async fn on_msg(
         Some("!setpoints") => commands::handle_setpoints_command(client, msg).await,
         Some("!gift") => commands::handle_gift_command(client, msg).await,
         Some("!pool") => commands::handle_pool_command(client, msg).await,
-        Some("!selectPoolWinner") => commands::handle_pool_draw_command(client, msg).await,
+        Some("!ticket") => commands::handle_ticket_command(client, msg, bot_state).await,
+        Some("!selectPoolWinner") => {
+            commands::handle_pool_draw_command(client, msg, bot_state).await
+        }
+        Some("!cancelPool") => commands::handle_pool_cancel_command(client, msg).await,
         _ => Ok(()),
     }
 }
@@ -1,10 +1,17 @@
 use crate::db;
+use crate::difficulty::{self, Difficulty, QuestionStats};
+use crate::generator::Topic;
 use crate::messaging::send_msg;
+use crate::question_source::{
+    self, Outcome, PickedQuestion, QuestionFilter, QuestionRef, QuestionSource, Repeats,
+};
+use crate::question_type::{self, QuestionType};
 use crate::schema::categories;
 use crate::schema::losers_pool;
 use crate::schema::lurkers;
 use crate::schema::questions;
 use crate::state::State;
+use bigdecimal::BigDecimal;
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
@@ -20,6 +27,9 @@ pub struct Chatter {
     pub losses: i32,
     pub last_seen: NaiveDateTime,
     pub lurk_time: i32,
+    pub last_daily_at: Option<NaiveDateTime>,
+    pub daily_streak: i32,
+    pub client_seed: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -42,6 +52,14 @@ pub struct Duel {
     pub challenged_id: Option<String>,
     pub challenger_guesses: i32,
     pub challenged_guesses: i32,
+    pub question_id: Option<i32>,
+    pub difficulty: Option<String>,
+    pub challenger_numeric_guess: Option<f64>,
+    pub challenged_numeric_guess: Option<f64>,
+    pub topic: Option<String>,
+    pub question_kind: Option<String>,
+    pub options: Vec<String>,
+    pub alternate_answers: Vec<String>,
 }
 
 #[allow(dead_code)]
@@ -59,32 +77,175 @@ impl Duel {
         challenger_id: &str,
         challenged_id: &str,
         points: i64,
+        difficulty: Option<Difficulty>,
+        topic: Option<Topic>,
     ) -> Duel {
-        db::create_duel(challenger, challenged, challenger_id, challenged_id, points)
+        db::create_duel(&NewDuel {
+            challenger,
+            challenged,
+            challenger_id,
+            challenged_id,
+            points,
+            difficulty: difficulty.as_ref().map(Difficulty::as_str),
+            topic: topic.as_ref().map(Topic::as_str),
+        })
     }
     pub fn accept_duel(&mut self) {
         db::accept_duel(self.id);
         db::create_accepted_duel(self.id, &self.challenger, &self.challenged);
     }
 
-    pub async fn ask_question(&mut self, client: &mut tmi::Client, msg: &tmi::Privmsg<'_>) -> () {
-        let question = match db::get_random_question() {
-            Some(q) => q,
+    // What to ask in the channel: the tier and topic the duel was started with, and
+    // nothing the channel or the players have seen recently.
+    pub fn question_filter(&self, channel: &str) -> QuestionFilter {
+        QuestionFilter {
+            difficulty: self.difficulty_tier(),
+            topic: self.generator_topic(),
+            seed: self.question_seed(),
+            channel: channel.to_string(),
+            player_ids: [&self.challenger_id, &self.challenged_id]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect(),
+            repeats: Repeats::Avoid,
+        }
+    }
+
+    pub async fn ask_question(
+        &mut self,
+        client: &mut tmi::Client,
+        msg: &tmi::Privmsg<'_>,
+        source: &mut dyn QuestionSource,
+    ) -> () {
+        let filter = self.question_filter(msg.channel());
+        let picked = match question_source::choose(source, &filter) {
+            Some(picked) => picked,
             None => {
                 let _ = send_msg(client, msg, "No questions in the database yet!").await;
                 return;
             }
         };
+        source.record_ask(&picked.question_ref(), &filter);
+
+        let question = match picked {
+            PickedQuestion::Stored(question) => question,
+            PickedQuestion::Generated(topic, question) => {
+                let options = question.options(self.question_seed());
+                let question_announcement = format!(
+                    "@{} @{} - format: {} - {} – {}: {}",
+                    self.challenger,
+                    self.challenged,
+                    question.kind.answer_format(),
+                    question.difficulty,
+                    question.category,
+                    question_type::prompt(&question.question, &options)
+                );
+                let _ = send_msg(client, msg, &question_announcement).await;
+                db::set_generated_question_duel(
+                    self.id,
+                    &question,
+                    topic,
+                    &options,
+                    question.kind.guesses_per_player(),
+                );
+                self.question = Some(question.question);
+                self.answer = Some(question.answer);
+                self.difficulty = Some(question.difficulty.as_str().to_string());
+                self.topic = Some(topic.as_str().to_string());
+                self.question_kind = Some(question.kind.as_str().to_string());
+                self.options = options;
+                self.alternate_answers = question.alternate_answers;
+                self.challenger_guesses = question.kind.guesses_per_player();
+                self.challenged_guesses = question.kind.guesses_per_player();
+                return;
+            }
+        };
+
+        let difficulty = question.difficulty();
+        let kind = question.question_type();
+        let options = question.options(self.question_seed());
+        question.reward_submitter();
 
         let question_announcement = format!(
-            "@{} @{} - format: '!a <answer>' - {}: {}",
+            "@{} @{} - format: {} - {} – {}: {}",
             self.challenger,
             self.challenged,
+            kind.answer_format(),
+            difficulty,
             question.display_question_kind(),
-            question.question
+            question_type::prompt(&question.question, &options)
         );
         let _ = send_msg(client, msg, &question_announcement).await;
-        db::set_question_duel(self.id, &question.question, &question.answer)
+        db::set_question_duel(
+            self.id,
+            &question,
+            difficulty.as_str(),
+            &options,
+            kind.guesses_per_player(),
+        );
+        self.challenger_guesses = kind.guesses_per_player();
+        self.challenged_guesses = kind.guesses_per_player();
+        self.question = Some(question.question);
+        self.answer = Some(question.answer);
+        self.question_id = Some(question.id);
+        self.difficulty = Some(difficulty.as_str().to_string());
+        self.question_kind = Some(kind.as_str().to_string());
+        self.options = options;
+        self.alternate_answers = question.alternate_answers;
+    }
+
+    pub fn generator_topic(&self) -> Option<Topic> {
+        self.topic.as_ref().and_then(|t| t.parse().ok())
+    }
+
+    // Multiple choice options are shuffled with this, so every message about the
+    // duel shows them in the same order.
+    pub fn question_seed(&self) -> u64 {
+        self.id as u64
+    }
+
+    // The kind of question that was asked, saved with its options when it was asked
+    // so edits to the question or the word list can't change it mid-duel. Duels from
+    // before there were kinds were all free text.
+    pub fn question_type(&self) -> QuestionType {
+        self.question_kind
+            .as_ref()
+            .and_then(|k| k.parse().ok())
+            .unwrap_or_default()
+    }
+
+    // How many multiple choice options the duelists were shown, if any.
+    pub fn option_count(&self) -> usize {
+        self.options.len()
+    }
+
+    // Before a question is asked this is the tier the challenger requested,
+    // afterwards it is the tier of the question that was actually asked.
+    pub fn difficulty_tier(&self) -> Option<Difficulty> {
+        self.difficulty.as_ref().and_then(|d| d.parse().ok())
+    }
+
+    pub fn payout(&self) -> i64 {
+        match self.difficulty_tier() {
+            Some(difficulty) => difficulty.scale_payout(self.points),
+            None => self.points,
+        }
+    }
+
+    // The question this duel asked, for recording how it went.
+    pub fn question_ref(&self) -> Option<QuestionRef> {
+        match (self.generator_topic(), self.question_id) {
+            (Some(topic), _) if self.question.is_some() => Some(QuestionRef::Generated(topic)),
+            (_, Some(question_id)) => Some(QuestionRef::Stored(question_id)),
+            _ => None,
+        }
+    }
+
+    pub fn record_wrong_guess(&self, source: &mut dyn QuestionSource) {
+        if let Some(question) = self.question_ref() {
+            source.record_outcome(&question, Outcome::WrongGuess);
+        }
     }
 
     pub async fn repeat_question(
@@ -93,38 +254,37 @@ impl Duel {
         msg: &tmi::Privmsg<'_>,
     ) -> () {
         dbg!(&self);
+        let question = question_type::prompt(self.question.as_deref().unwrap(), &self.options);
         let question_msg = format!(
             "@{} @{} your question is: {}",
-            self.challenger,
-            self.challenged,
-            self.question.as_ref().unwrap()
+            self.challenger, self.challenged, question
         );
         send_msg(client, msg, &question_msg).await.unwrap();
     }
 
     pub fn is_winner(&self, answer: &str) -> bool {
-        let binding = answer.to_string().to_lowercase();
-        let guess = binding.trim();
-        let binding = self.answer.as_ref().unwrap().to_lowercase();
-        let correct_answer = binding.trim();
-        dbg!(&guess);
-        dbg!(&correct_answer);
-        if dbg!(guess == correct_answer) {
-            true
-        } else {
-            false
-        }
+        question_type::accepts(
+            self.question_type(),
+            self.answer.as_deref().unwrap_or_default(),
+            &self.alternate_answers,
+            &self.options,
+            answer,
+        )
     }
 
     pub fn award_winner(
         &mut self,
+        source: &mut dyn QuestionSource,
         duel_winner: &str,
         duel_winner_id: &str,
         duel_loser_id: &str,
     ) -> () {
         use crate::chatter;
         self.winner = Some(duel_winner.to_string());
-        chatter::add_points(duel_winner_id, self.points);
+        if let Some(question) = self.question_ref() {
+            source.record_outcome(&question, Outcome::Correct);
+        }
+        chatter::add_points(duel_winner_id, self.payout());
         chatter::add_win(duel_winner_id);
         chatter::add_loss(duel_loser_id);
         chatter::subtract_points(duel_loser_id, self.points / 2);
@@ -141,7 +301,69 @@ impl Duel {
         db::decrement_guesses(self.id, false);
     }
 
+    fn winner_and_loser_ids(&self) -> Option<(String, String)> {
+        let (winner_id, loser_id) = match self.winner.as_deref() {
+            Some(w) if w == self.challenger => (&self.challenger_id, &self.challenged_id),
+            Some(w) if w == self.challenged => (&self.challenged_id, &self.challenger_id),
+            _ => return None,
+        };
+        Some((winner_id.clone()?, loser_id.clone()?))
+    }
+
+    // The points voiding the duel moves as (twitch id, points), negative ones taken
+    // back: the winner's payout, the loser's loss and the submitter's reward.
+    pub fn void_adjustments(&self, submitter: Option<String>) -> Vec<(String, i64)> {
+        let mut adjustments = vec![];
+        if let Some((winner_id, loser_id)) = self.winner_and_loser_ids() {
+            adjustments.push((winner_id, -self.payout()));
+            adjustments.push((loser_id, self.points / 2));
+        }
+        if let Some(submitter) = submitter {
+            adjustments.push((submitter, -QUESTION_USE_REWARD));
+        }
+        adjustments
+    }
+
+    // Undoes whatever the duel paid out. Returns false if it was already voided.
+    pub fn void(&mut self) -> bool {
+        use crate::chatter;
+        if self.status == "voided" {
+            return false;
+        }
+
+        if let Some((winner_id, loser_id)) = self.winner_and_loser_ids() {
+            chatter::subtract_win(&winner_id);
+            chatter::subtract_loss(&loser_id);
+        }
+        // the question was bad, so its submitter doesn't get paid for it either
+        let submitter = match self.question_ref() {
+            Some(QuestionRef::Stored(question_id)) => {
+                db::get_question(question_id).and_then(|q| q.rewarded_submitter())
+            }
+            _ => None,
+        };
+        for (twitch_id, points) in self.void_adjustments(submitter) {
+            if points > 0 {
+                chatter::add_points(&twitch_id, points);
+            } else {
+                chatter::subtract_points(&twitch_id, -points);
+            }
+        }
+
+        db::complete_duel(self.id, self.winner.as_deref().unwrap_or("tie"), "voided");
+        db::destroy_accepted_duel(self.id);
+        self.status = "voided".to_string();
+        true
+    }
+
+    // Ends the duel without a winner, either because both players ran out of
+    // guesses or because it went stale, so the question counts as not answered.
     pub fn complete_duel(&mut self, bot_state: &mut State) -> () {
+        if let Some(question) = self.question_ref() {
+            bot_state
+                .question_source
+                .record_outcome(&question, Outcome::NotAnswered);
+        }
         bot_state.clear_duel(self);
         db::complete_duel(self.id, "tie", "completed");
         db::destroy_accepted_duel(self.id);
@@ -167,6 +389,8 @@ pub struct NewDuel<'a> {
     pub challenger_id: &'a str,
     pub challenged_id: &'a str,
     pub points: i64,
+    pub difficulty: Option<&'a str>,
+    pub topic: Option<&'a str>,
 }
 
 use crate::schema::accepted_duels;
@@ -230,6 +454,34 @@ pub struct NewQuestion<'a> {
     pub answer: &'a str,
     pub category_id: i32,
     pub submitter_id: i32,
+    pub status: &'a str,
+    pub points_paid: i64,
+    pub alternate_answers: &'a [String],
+    pub authored_difficulty: Option<&'a str>,
+    pub source: Option<&'a str>,
+    pub incorrect_answers: &'a [String],
+    pub kind: &'a str,
+}
+
+// Chatter submitted questions wait in the review queue until a mod approves them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestionStatus {
+    Pending,
+    Approved,
+    Rejected,
+    // pulled from rotation after too many reports, waiting for a mod to decide
+    Quarantined,
+}
+
+impl QuestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionStatus::Pending => "pending",
+            QuestionStatus::Approved => "approved",
+            QuestionStatus::Rejected => "rejected",
+            QuestionStatus::Quarantined => "quarantined",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Queryable, Selectable, Associations, Identifiable)]
@@ -245,12 +497,162 @@ pub struct Question {
     pub times_not_answered: i32,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub times_answered_correctly: i32,
+    pub times_guessed_wrong: i32,
+    pub status: String,
+    pub review_note: Option<String>,
+    pub points_paid: i64,
+    pub alternate_answers: Vec<String>,
+    pub authored_difficulty: Option<String>,
+    pub source: Option<String>,
+    pub incorrect_answers: Vec<String>,
+    pub kind: String,
 }
 
+pub const QUESTION_COST: i64 = 5000;
+// Paid to the submitter every time one of their approved questions is asked.
+pub const QUESTION_USE_REWARD: i64 = 100;
+// Open reports needed before a question is pulled from rotation.
+pub const REPORT_QUARANTINE_THRESHOLD: i64 = 3;
+
 impl Question {
-    pub fn new(question: &str, answer: &str, category: &Category, submitter: &Chatter) -> Question {
+    pub fn new(
+        question: &str,
+        answer: &str,
+        category: &Category,
+        submitter: &Chatter,
+        status: QuestionStatus,
+        points_paid: i64,
+    ) -> Question {
         let twitch_id = submitter.twitch_id.parse().unwrap();
-        db::create_question(question, answer, twitch_id, category.id)
+        db::submit_question(
+            question,
+            answer,
+            twitch_id,
+            category.id,
+            status,
+            points_paid,
+        )
+    }
+
+    pub fn question_type(&self) -> QuestionType {
+        self.kind.parse().unwrap_or_default()
+    }
+
+    // Multiple choice options in the order they are shown for the given seed.
+    pub fn options(&self, seed: u64) -> Vec<String> {
+        match self.question_type() {
+            QuestionType::MultipleChoice => {
+                question_type::shuffled_options(&self.answer, &self.incorrect_answers, seed)
+            }
+            _ => vec![],
+        }
+    }
+
+    pub fn prompt(&self, seed: u64) -> String {
+        question_type::prompt(&self.question, &self.options(seed))
+    }
+
+    pub fn accepts(&self, guess: &str, seed: u64) -> bool {
+        question_type::accepts(
+            self.question_type(),
+            &self.answer,
+            &self.alternate_answers,
+            &self.options(seed),
+            guess,
+        )
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == QuestionStatus::Pending.as_str()
+    }
+
+    // False if someone else reviewed it first.
+    pub fn approve(&mut self) -> bool {
+        if !db::review_question(self.id, QuestionStatus::Approved, None) {
+            return false;
+        }
+        self.status = QuestionStatus::Approved.as_str().to_string();
+        true
+    }
+
+    // Rejected submissions get back whatever the submitter paid for them, once.
+    pub fn reject(&mut self, reason: &str) -> bool {
+        if !db::review_question(self.id, QuestionStatus::Rejected, Some(reason)) {
+            return false;
+        }
+        self.status = QuestionStatus::Rejected.as_str().to_string();
+        self.review_note = Some(reason.to_string());
+        true
+    }
+
+    // Returns true when this report pushed the question into quarantine.
+    pub fn report(
+        &mut self,
+        duel_id: Option<i32>,
+        reporter_id: &str,
+        reason: Option<&str>,
+    ) -> bool {
+        db::create_question_report(self.id, duel_id, reporter_id, reason);
+        let quarantine = self.status == QuestionStatus::Approved.as_str()
+            && db::count_open_reports(self.id) >= REPORT_QUARANTINE_THRESHOLD;
+        if quarantine {
+            db::update_question_status(self.id, QuestionStatus::Quarantined, Some("reported"));
+            self.status = QuestionStatus::Quarantined.as_str().to_string();
+        }
+        quarantine
+    }
+
+    pub fn vote(&self, voter_id: &str, upvote: bool) -> (i64, i64) {
+        db::upsert_question_vote(self.id, voter_id, if upvote { 1 } else { -1 });
+        db::get_question_votes(self.id)
+    }
+
+    // A mod agreed the question was wrong, so it is retired and every duel it was
+    // reported in is voided. Returns how many duels were voided.
+    pub fn confirm_reports(&mut self) -> usize {
+        let reports = db::get_open_reports(self.id);
+        let mut voided = 0;
+        let mut duel_ids = reports
+            .iter()
+            .filter_map(|r| r.duel_id)
+            .collect::<Vec<i32>>();
+        duel_ids.sort();
+        duel_ids.dedup();
+        for duel_id in duel_ids {
+            if let Some(mut duel) = db::get_duel(duel_id) {
+                if duel.void() {
+                    voided += 1;
+                }
+            }
+        }
+        db::update_question_status(self.id, QuestionStatus::Rejected, Some("confirmed wrong"));
+        db::resolve_reports(self.id);
+        self.status = QuestionStatus::Rejected.as_str().to_string();
+        voided
+    }
+
+    pub fn dismiss_reports(&mut self) {
+        if self.status == QuestionStatus::Quarantined.as_str() {
+            db::update_question_status(self.id, QuestionStatus::Approved, None);
+            self.status = QuestionStatus::Approved.as_str().to_string();
+        }
+        db::resolve_reports(self.id);
+    }
+
+    // The twitch id of who gets QUESTION_USE_REWARD when the question is asked, the
+    // broadcaster's own questions earn nothing.
+    pub fn rewarded_submitter(&self) -> Option<String> {
+        let broadcaster_id = std::env::var("BROADCASTER_ID").expect("BROADCASTER_ID must be set");
+        let submitter = self.submitter_id.to_string();
+        (submitter != broadcaster_id).then_some(submitter)
+    }
+
+    pub fn reward_submitter(&self) {
+        use crate::chatter;
+        if let Some(submitter) = self.rewarded_submitter() {
+            chatter::add_points(&submitter, QUESTION_USE_REWARD);
+        }
     }
 
     pub fn display_question_kind(&self) -> String {
@@ -260,10 +662,9 @@ impl Question {
         };
         cat.name
     }
-    // TODO: Make these actually increment the values in the database
-    pub fn increment_times_asked(&mut self) -> () {
-        let new_times_asked = self.times_asked + 1;
-        db::update_times_asked(self.id, new_times_asked);
+
+    pub fn difficulty(&self) -> Difficulty {
+        difficulty::difficulty(&QuestionStats::from(self))
     }
 
     pub fn increment_times_not_answered(&mut self) -> () {
@@ -271,6 +672,61 @@ impl Question {
     }
 }
 
+use crate::schema::question_reports;
+
+#[derive(Insertable)]
+#[diesel(table_name = question_reports)]
+pub struct NewQuestionReport<'a> {
+    pub question_id: i32,
+    pub duel_id: Option<i32>,
+    pub reporter_id: &'a str,
+    pub reason: Option<&'a str>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = question_reports)]
+pub struct QuestionReport {
+    pub id: i32,
+    pub question_id: i32,
+    pub duel_id: Option<i32>,
+    pub reporter_id: String,
+    pub reason: Option<String>,
+    pub resolved: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+use crate::schema::question_asks;
+
+#[derive(Insertable)]
+#[diesel(table_name = question_asks)]
+pub struct NewQuestionAsk<'a> {
+    pub question_id: i32,
+    pub channel: &'a str,
+    pub player_ids: &'a [String],
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = question_asks)]
+pub struct QuestionAsk {
+    pub id: i32,
+    pub question_id: i32,
+    pub channel: String,
+    pub player_ids: Vec<String>,
+    pub asked_at: NaiveDateTime,
+}
+
+use crate::schema::question_votes;
+
+#[derive(Insertable)]
+#[diesel(table_name = question_votes)]
+pub struct NewQuestionVote<'a> {
+    pub question_id: i32,
+    pub voter_id: &'a str,
+    pub value: i16,
+}
+
 #[derive(Insertable)]
 #[diesel(table_name = losers_pool)]
 pub struct NewPool {
@@ -285,4 +741,569 @@ pub struct LosersPool {
     pub winner: Option<i32>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    // cancelled pools refunded their tickets and never get a winner
+    pub cancelled_at: Option<NaiveDateTime>,
+    // when the scheduled draw happens, None until one is scheduled
+    pub draw_at: Option<NaiveDateTime>,
+    // what the draw left for the next pool and gave the broadcaster
+    pub rollover: i64,
+    pub broadcaster_cut: i64,
+}
+
+use crate::schema::pool_tickets;
+
+#[derive(Insertable)]
+#[diesel(table_name = pool_tickets)]
+pub struct NewPoolTicket {
+    pub pool_id: i32,
+    pub chatter_id: i32,
+    pub tickets: i64,
+    pub points: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = pool_tickets)]
+pub struct PoolTicket {
+    pub id: i32,
+    pub pool_id: i32,
+    pub chatter_id: i32,
+    pub tickets: i64,
+    // what the tickets cost, refunded if the pool is cancelled
+    pub points: i64,
+    pub refunded: bool,
+    pub created_at: NaiveDateTime,
+}
+
+use crate::schema::pool_winners;
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = pool_winners)]
+pub struct NewPoolWinner {
+    pub pool_id: i32,
+    pub chatter_id: i32,
+    pub place: i32,
+    pub amount: i64,
+    pub tickets: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = pool_winners)]
+pub struct PoolWinner {
+    pub id: i32,
+    pub pool_id: i32,
+    pub chatter_id: i32,
+    // 1 for the biggest prize
+    pub place: i32,
+    pub amount: i64,
+    pub tickets: i64,
+    pub created_at: NaiveDateTime,
+}
+
+use crate::schema::shop_items;
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = shop_items)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewShopItem<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub cost: i64,
+    pub stock: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub input_prompt: Option<&'a str>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = shop_items)]
+pub struct ShopItem {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub cost: i64,
+    pub stock: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub input_prompt: Option<String>,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+use crate::schema::redemptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedemptionStatus {
+    // bought, waiting for a mod to make it happen
+    Pending,
+    Fulfilled,
+    // points given back, and the stock too
+    Refunded,
+}
+
+impl RedemptionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedemptionStatus::Pending => "pending",
+            RedemptionStatus::Fulfilled => "fulfilled",
+            RedemptionStatus::Refunded => "refunded",
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = redemptions)]
+pub struct NewRedemption<'a> {
+    pub item_id: i32,
+    pub twitch_id: &'a str,
+    pub username: &'a str,
+    pub input: Option<&'a str>,
+    pub cost: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = redemptions)]
+pub struct Redemption {
+    pub id: i32,
+    pub item_id: i32,
+    pub twitch_id: String,
+    pub username: String,
+    pub input: Option<String>,
+    pub cost: i64,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Redemption {
+    pub fn is_pending(&self) -> bool {
+        self.status == RedemptionStatus::Pending.as_str()
+    }
+}
+
+use crate::schema::stocks;
+
+#[derive(Insertable)]
+#[diesel(table_name = stocks)]
+pub struct NewStock<'a> {
+    pub name: &'a str,
+    pub symbol: &'a str,
+    pub ticket_price: BigDecimal,
+    pub future_value: BigDecimal,
+}
+
+// ticket_price is what a share costs right now, future_value is the price the
+// market is drifting towards and roi_percentage is how much the last tick moved it.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = stocks)]
+pub struct Stock {
+    pub id: i32,
+    pub name: String,
+    pub symbol: String,
+    pub ticket_price: BigDecimal,
+    pub future_value: BigDecimal,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub roi_percentage: BigDecimal,
+}
+
+use crate::schema::orders;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    // a limit order waiting on the book
+    Open,
+    Filled,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Open => "open",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = orders)]
+pub struct NewOrder<'a> {
+    pub stock_id: i32,
+    pub owner_id: i32,
+    pub num_shares: i32,
+    pub strike_price: &'a BigDecimal,
+    pub status: &'a str,
+    pub remaining_shares: i32,
+    pub escrow: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = orders)]
+pub struct Order {
+    pub id: i32,
+    pub stock_id: i32,
+    pub owner_id: i32,
+    // negative for sells
+    pub num_shares: i32,
+    pub strike_price: BigDecimal,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub status: String,
+    // shares of a limit order not filled yet
+    pub remaining_shares: i32,
+    // points held back for the rest of a buy order
+    pub escrow: i64,
+}
+
+impl Order {
+    pub fn is_buy(&self) -> bool {
+        self.num_shares > 0
+    }
+}
+
+use crate::schema::holdings;
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = holdings)]
+pub struct Holding {
+    pub id: i32,
+    pub owner_id: i32,
+    pub stock_id: i32,
+    pub num_shares: i32,
+    pub cost_basis: i64,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+use crate::schema::trades;
+
+#[derive(Insertable)]
+#[diesel(table_name = trades)]
+pub struct NewTrade<'a> {
+    pub stock_id: i32,
+    pub buy_order_id: i32,
+    pub sell_order_id: i32,
+    pub buyer_id: i32,
+    pub seller_id: i32,
+    pub num_shares: i32,
+    pub price: &'a BigDecimal,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = trades)]
+pub struct Trade {
+    pub id: i32,
+    pub stock_id: i32,
+    pub buy_order_id: i32,
+    pub sell_order_id: i32,
+    pub buyer_id: i32,
+    pub seller_id: i32,
+    pub num_shares: i32,
+    pub price: BigDecimal,
+    pub value: i64,
+    pub created_at: NaiveDateTime,
+}
+
+use crate::schema::market_events;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketEventKind {
+    // points wagered on a duel
+    Duel,
+    // what the house won or lost on a gamble
+    Gamble,
+    // percent a mod pushed a stock up or down by
+    Pump,
+    Dump,
+}
+
+impl MarketEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketEventKind::Duel => "duel",
+            MarketEventKind::Gamble => "gamble",
+            MarketEventKind::Pump => "pump",
+            MarketEventKind::Dump => "dump",
+        }
+    }
+}
+
+impl std::str::FromStr for MarketEventKind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "duel" => Ok(MarketEventKind::Duel),
+            "gamble" => Ok(MarketEventKind::Gamble),
+            "pump" => Ok(MarketEventKind::Pump),
+            "dump" => Ok(MarketEventKind::Dump),
+            _ => Err(anyhow::anyhow!("Unknown market event {}", s)),
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = market_events)]
+pub struct NewMarketEvent<'a> {
+    pub stock_id: i32,
+    pub kind: &'a str,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = market_events)]
+pub struct MarketEvent {
+    pub id: i32,
+    pub stock_id: i32,
+    pub kind: String,
+    pub amount: i64,
+    // when a tick moved the price for it
+    pub applied_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+use crate::schema::stock_prices;
+
+#[derive(Insertable)]
+#[diesel(table_name = stock_prices)]
+pub struct NewStockPrice<'a> {
+    pub stock_id: i32,
+    pub price: &'a BigDecimal,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = stock_prices)]
+pub struct StockPrice {
+    pub id: i32,
+    pub stock_id: i32,
+    pub price: BigDecimal,
+    pub created_at: NaiveDateTime,
+}
+
+use crate::schema::blackjack_hands;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlackjackStatus {
+    // waiting for the player to !hit, !stand or !double
+    Playing,
+    // a natural 21, paid 3 to 2
+    Blackjack,
+    Won,
+    Push,
+    Lost,
+}
+
+impl BlackjackStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlackjackStatus::Playing => "playing",
+            BlackjackStatus::Blackjack => "blackjack",
+            BlackjackStatus::Won => "won",
+            BlackjackStatus::Push => "push",
+            BlackjackStatus::Lost => "lost",
+        }
+    }
+}
+
+impl std::str::FromStr for BlackjackStatus {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "playing" => Ok(BlackjackStatus::Playing),
+            "blackjack" => Ok(BlackjackStatus::Blackjack),
+            "won" => Ok(BlackjackStatus::Won),
+            "push" => Ok(BlackjackStatus::Push),
+            "lost" => Ok(BlackjackStatus::Lost),
+            _ => Err(anyhow::anyhow!("Unknown blackjack status {}", s)),
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = blackjack_hands)]
+pub struct NewBlackjackHand<'a> {
+    pub chatter_id: i32,
+    pub wager: i64,
+    pub player_cards: &'a str,
+    pub dealer_cards: &'a str,
+    pub deck: &'a str,
+    pub status: &'a str,
+    pub payout: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = blackjack_hands)]
+pub struct BlackjackHand {
+    pub id: i32,
+    pub chatter_id: i32,
+    pub wager: i64,
+    pub player_cards: String,
+    pub dealer_cards: String,
+    pub deck: String,
+    pub doubled: bool,
+    pub status: String,
+    pub payout: i64,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl BlackjackHand {
+    pub fn is_playing(&self) -> bool {
+        self.status == BlackjackStatus::Playing.as_str()
+    }
+}
+
+use crate::schema::casino_seeds;
+
+#[derive(Insertable)]
+#[diesel(table_name = casino_seeds)]
+pub struct NewCasinoSeed<'a> {
+    pub seed: &'a str,
+    pub seed_hash: &'a str,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = casino_seeds)]
+pub struct CasinoSeed {
+    pub id: i32,
+    pub seed: String,
+    pub seed_hash: String,
+    pub nonce: i32,
+    // the seed is kept secret until then
+    pub revealed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+use crate::schema::bets;
+
+#[derive(Insertable)]
+#[diesel(table_name = bets)]
+pub struct NewBet<'a> {
+    pub chatter_id: i32,
+    pub game: &'a str,
+    pub wager: i64,
+    pub bet: &'a str,
+    pub seed_id: Option<i32>,
+    pub client_seed: Option<&'a str>,
+    pub nonce: Option<i32>,
+    pub payout: i64,
+    pub result: &'a str,
+    pub pool_cut: i64,
+    pub broadcaster_cut: i64,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = bets)]
+pub struct Bet {
+    pub id: i32,
+    pub chatter_id: i32,
+    pub game: String,
+    pub wager: i64,
+    pub bet: String,
+    // None for games that don't draw from the seeds, like blackjack
+    pub seed_id: Option<i32>,
+    pub client_seed: Option<String>,
+    pub nonce: Option<i32>,
+    pub payout: i64,
+    pub result: String,
+    pub created_at: NaiveDateTime,
+    // what the bet lost, split between the losers pool and the broadcaster
+    pub pool_cut: i64,
+    pub broadcaster_cut: i64,
+}
+
+use crate::schema::gambling_limits;
+
+// None means the casino's default applies.
+#[derive(Debug, Clone, Default, Queryable, Selectable)]
+#[diesel(table_name = gambling_limits)]
+pub struct GamblingLimit {
+    pub chatter_id: i32,
+    pub max_wager: Option<i64>,
+    pub session_loss_limit: Option<i64>,
+    pub daily_loss_limit: Option<i64>,
+    // no gambling at all until then
+    pub excluded_until: Option<NaiveDateTime>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duel(winner: Option<&str>) -> Duel {
+        let now = chrono::Utc::now().naive_utc();
+        Duel {
+            id: 1,
+            accepted: true,
+            points: 100,
+            challenger: "alice".to_string(),
+            challenged: "bob".to_string(),
+            winner: winner.map(str::to_string),
+            status: "completed".to_string(),
+            created_at: now,
+            updated_at: now,
+            question: Some("What is 2 + 2?".to_string()),
+            answer: Some("4".to_string()),
+            challenger_id: Some("1".to_string()),
+            challenged_id: Some("2".to_string()),
+            challenger_guesses: 0,
+            challenged_guesses: 0,
+            question_id: Some(7),
+            difficulty: None,
+            challenger_numeric_guess: None,
+            challenged_numeric_guess: None,
+            topic: None,
+            question_kind: None,
+            options: vec![],
+            alternate_answers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_void_takes_back_the_payout_and_the_submitter_reward() {
+        assert_eq!(
+            vec![
+                ("2".to_string(), -100),
+                ("1".to_string(), 50),
+                ("3".to_string(), -QUESTION_USE_REWARD)
+            ],
+            duel(Some("bob")).void_adjustments(Some("3".to_string()))
+        );
+        assert_eq!(
+            vec![("1".to_string(), -100), ("2".to_string(), 50)],
+            duel(Some("alice")).void_adjustments(None)
+        );
+    }
+
+    #[test]
+    fn test_voiding_a_tie_only_takes_back_the_reward() {
+        assert_eq!(
+            vec![("3".to_string(), -QUESTION_USE_REWARD)],
+            duel(Some("tie")).void_adjustments(Some("3".to_string()))
+        );
+        assert!(duel(None).void_adjustments(None).is_empty());
+    }
+
+    #[test]
+    fn test_guesses_are_judged_against_what_was_asked() {
+        // a word the generator wouldn't hand back for this seed and tier
+        let mut scramble = duel(None);
+        scramble.topic = Some("scramble".to_string());
+        scramble.difficulty = Some("hard".to_string());
+        scramble.question = Some("lppae".to_string());
+        scramble.answer = Some("apple".to_string());
+        scramble.question_kind = Some("free_text".to_string());
+        assert!(scramble.is_winner(" Apple "));
+        assert_eq!(QuestionType::FreeText, scramble.question_type());
+        assert_eq!(0, scramble.option_count());
+
+        let mut choice = duel(None);
+        choice.question_kind = Some("multiple_choice".to_string());
+        choice.options = vec!["3".to_string(), "4".to_string(), "5".to_string()];
+        assert!(choice.is_winner("b"));
+        assert!(!choice.is_winner("a"));
+        assert_eq!(3, choice.option_count());
+    }
 }
@@ -0,0 +1,254 @@
+// Reads question dumps in the format served by the Open Trivia DB api
+// (https://opentdb.com/api_config.php) and turns them into question packs.
+// Only the default encoding is supported, where text comes HTML-entity encoded.
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::question_pack::PackQuestion;
+use crate::question_type::QuestionType;
+
+pub const SOURCE: &str = "Open Trivia DB";
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    response_code: i32,
+    results: Vec<OpenTdbQuestion>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenTdbQuestion {
+    // "multiple" or "boolean"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub difficulty: String,
+    pub category: String,
+    pub question: String,
+    pub correct_answer: String,
+    pub incorrect_answers: Vec<String>,
+}
+
+// Accepts either a whole api response or just its `results` array, since
+// people tend to save whichever they got.
+pub fn parse(contents: &str) -> Result<Vec<OpenTdbQuestion>> {
+    if contents.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(contents)?);
+    }
+    let response: Response = serde_json::from_str(contents)?;
+    if response.response_code != 0 {
+        return Err(anyhow!(
+            "api response has error code {}",
+            response.response_code
+        ));
+    }
+    Ok(response.results)
+}
+
+// Open Trivia DB names most categories "Group: Name", we only keep the name.
+// The one exception is General Knowledge which already has a home here.
+pub fn map_category(category: &str) -> String {
+    let category = decode_html(category);
+    let name = match category.split_once(':') {
+        Some((_, name)) => name.trim(),
+        None => category.trim(),
+    };
+    match name {
+        "General Knowledge" => "General".to_string(),
+        name => name.to_string(),
+    }
+}
+
+pub fn to_pack_question(question: &OpenTdbQuestion, category: Option<&str>) -> PackQuestion {
+    PackQuestion {
+        kind: Some(
+            match question.kind.as_str() {
+                "boolean" => QuestionType::TrueFalse,
+                _ => QuestionType::MultipleChoice,
+            }
+            .as_str()
+            .to_string(),
+        ),
+        question: decode_html(&question.question),
+        answers: vec![decode_html(&question.correct_answer)],
+        category: match category {
+            Some(category) => category.to_string(),
+            None => map_category(&question.category),
+        },
+        difficulty: Some(question.difficulty.clone()),
+        source: Some(SOURCE.to_string()),
+        incorrect_answers: question
+            .incorrect_answers
+            .iter()
+            .map(|a| decode_html(a))
+            .collect(),
+    }
+}
+
+fn named_entity(name: &str) -> Option<char> {
+    let c = match name {
+        "quot" => '"',
+        "amp" => '&',
+        "apos" => '\'',
+        "lt" => '<',
+        "gt" => '>',
+        "nbsp" => ' ',
+        "shy" => '\u{ad}',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "hellip" => '…',
+        "ndash" => '–',
+        "mdash" => '—',
+        "deg" => '°',
+        "pi" => 'π',
+        "micro" => 'µ',
+        "times" => '×',
+        "divide" => '÷',
+        "sup2" => '²',
+        "sup3" => '³',
+        "frac12" => '½',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "szlig" => 'ß',
+        "aacute" => 'á',
+        "Aacute" => 'Á',
+        "agrave" => 'à',
+        "acirc" => 'â',
+        "atilde" => 'ã',
+        "auml" => 'ä',
+        "Auml" => 'Ä',
+        "aring" => 'å',
+        "Aring" => 'Å',
+        "aelig" => 'æ',
+        "ccedil" => 'ç',
+        "eacute" => 'é',
+        "Eacute" => 'É',
+        "egrave" => 'è',
+        "ecirc" => 'ê',
+        "euml" => 'ë',
+        "iacute" => 'í',
+        "igrave" => 'ì',
+        "icirc" => 'î',
+        "iuml" => 'ï',
+        "ntilde" => 'ñ',
+        "oacute" => 'ó',
+        "Oacute" => 'Ó',
+        "ograve" => 'ò',
+        "ocirc" => 'ô',
+        "otilde" => 'õ',
+        "ouml" => 'ö',
+        "Ouml" => 'Ö',
+        "oslash" => 'ø',
+        "Oslash" => 'Ø',
+        "uacute" => 'ú',
+        "ugrave" => 'ù',
+        "ucirc" => 'û',
+        "uuml" => 'ü',
+        "Uuml" => 'Ü',
+        "yacute" => 'ý',
+        _ => return None,
+    };
+    Some(c)
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+        None => named_entity(entity),
+    }
+}
+
+// Entities we don't know are left as they are rather than dropped.
+pub fn decode_html(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .map(|end| &rest[1..end + 1])
+            .filter(|entity| !entity.is_empty() && entity.len() <= 10);
+        match entity.and_then(|entity| decode_entity(entity).map(|c| (entity, c))) {
+            Some((entity, c)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_html() {
+        assert_eq!(
+            "Who's the \"king\" of <rock & roll>?",
+            decode_html("Who&#039;s the &quot;king&quot; of &lt;rock &amp; roll&gt;?")
+        );
+        assert_eq!("Pokémon – Ω", decode_html("Pok&eacute;mon &ndash; &#x3A9;"));
+        assert_eq!(
+            "AT&T & co; &bogus; &",
+            decode_html("AT&T &amp; co; &bogus; &")
+        );
+    }
+
+    #[test]
+    fn test_map_category() {
+        assert_eq!("Video Games", map_category("Entertainment: Video Games"));
+        assert_eq!(
+            "Japanese Anime & Manga",
+            map_category("Entertainment: Japanese Anime &amp; Manga")
+        );
+        assert_eq!("General", map_category("General Knowledge"));
+        assert_eq!("History", map_category("History"));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let dump = r#"{"response_code":0,"results":[
+            {"type":"multiple","difficulty":"hard","category":"Science: Computers",
+             "question":"What does &quot;LAN&quot; stand for?","correct_answer":"Local Area Network",
+             "incorrect_answers":["Long Antenna Node","Light Access Node","Land Address Navigation"]},
+            {"type":"boolean","difficulty":"easy","category":"General Knowledge",
+             "question":"The sky is blue.","correct_answer":"True","incorrect_answers":["False"]}
+        ]}"#;
+        let questions = parse(dump)
+            .unwrap()
+            .iter()
+            .map(|q| to_pack_question(q, None))
+            .collect::<Vec<PackQuestion>>();
+
+        assert_eq!("What does \"LAN\" stand for?", questions[0].question);
+        assert_eq!("Computers", questions[0].category);
+        assert_eq!(Some("hard".to_string()), questions[0].difficulty);
+        assert_eq!(3, questions[0].incorrect_answers.len());
+        assert_eq!(Some("multiple_choice".to_string()), questions[0].kind);
+        assert_eq!(vec!["True"], questions[1].answers);
+        assert_eq!(Some("true_false".to_string()), questions[1].kind);
+        assert_eq!("General", questions[1].category);
+
+        assert!(parse(r#"{"response_code":1,"results":[]}"#).is_err());
+        assert_eq!(0, parse("[]").unwrap().len());
+    }
+}
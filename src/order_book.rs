@@ -0,0 +1,468 @@
+// Limit orders between chatters. Orders match on price-time priority: the best price
+// goes first and orders at the same price go in the order they were placed, which
+// the order ids give us. A match trades at the price of the order that was waiting.
+//
+// Prices are in hundredths of a point per share. Buyers have the points for their
+// whole order held back as escrow when they place it, rounded up, and a trade moves
+// its value, rounded down, from the buyer's escrow to the seller. Whatever is left
+// of the escrow goes back to the buyer once the order is filled or cancelled.
+use std::fmt;
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive};
+
+use crate::models::Order;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookOrder {
+    pub id: i32,
+    pub owner_id: i32,
+    pub side: Side,
+    // hundredths of a point per share
+    pub price: i64,
+    pub remaining: i32,
+    // points still held back, always 0 for sells
+    pub escrow: i64,
+}
+
+impl BookOrder {
+    pub fn from_order(order: &Order) -> BookOrder {
+        BookOrder {
+            id: order.id,
+            owner_id: order.owner_id,
+            side: if order.is_buy() {
+                Side::Buy
+            } else {
+                Side::Sell
+            },
+            price: to_cents(&order.strike_price),
+            remaining: order.remaining_shares,
+            escrow: order.escrow,
+        }
+    }
+
+    fn crosses(&self, resting: &BookOrder) -> bool {
+        match self.side {
+            Side::Buy => self.price >= resting.price,
+            Side::Sell => self.price <= resting.price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub buy_order_id: i32,
+    pub sell_order_id: i32,
+    pub buyer_id: i32,
+    pub seller_id: i32,
+    pub shares: i32,
+    pub price: i64,
+    // points the buyer pays the seller
+    pub value: i64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Execution {
+    pub fills: Vec<Fill>,
+    // orders that got filled completely, with the escrow that goes back to buyers
+    pub filled: Vec<BookOrder>,
+}
+
+// Points held back for a buy order, rounded up.
+pub fn escrow_for(price: i64, shares: i32) -> i64 {
+    ((price as i128 * shares as i128 + 99) / 100).min(i64::MAX as i128) as i64
+}
+
+// Points a trade moves from the buyer to the seller, rounded down so the buyer's
+// escrow always covers every trade of the order.
+pub fn value_of(price: i64, shares: i32) -> i64 {
+    (price as i128 * shares as i128 / 100).min(i64::MAX as i128) as i64
+}
+
+pub fn to_cents(price: &BigDecimal) -> i64 {
+    (price * BigDecimal::from(100))
+        .with_scale_round(0, RoundingMode::HalfUp)
+        .to_i64()
+        .unwrap_or(0)
+}
+
+pub fn from_cents(cents: i64) -> BigDecimal {
+    BigDecimal::new(cents.into(), 2)
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct OrderBook {
+    // best (highest) price first
+    bids: Vec<BookOrder>,
+    // best (lowest) price first
+    asks: Vec<BookOrder>,
+}
+
+impl OrderBook {
+    pub fn new(orders: impl IntoIterator<Item = BookOrder>) -> OrderBook {
+        let mut book = OrderBook::default();
+        for order in orders {
+            book.rest(order);
+        }
+        book
+    }
+
+    pub fn bids(&self) -> &[BookOrder] {
+        &self.bids
+    }
+
+    pub fn asks(&self) -> &[BookOrder] {
+        &self.asks
+    }
+
+    // (price, shares) at each of the best prices on one side.
+    pub fn depth(&self, side: Side, levels: usize) -> Vec<(i64, i32)> {
+        let orders = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let mut depth: Vec<(i64, i32)> = vec![];
+        for order in orders {
+            match depth.last_mut() {
+                Some((price, shares)) if *price == order.price => *shares += order.remaining,
+                _ => {
+                    if depth.len() == levels {
+                        break;
+                    }
+                    depth.push((order.price, order.remaining));
+                }
+            }
+        }
+        depth
+    }
+
+    pub fn get(&self, id: i32) -> Option<&BookOrder> {
+        self.bids
+            .iter()
+            .chain(self.asks.iter())
+            .find(|o| o.id == id)
+    }
+
+    fn rest(&mut self, order: BookOrder) {
+        let (side, sign) = match order.side {
+            Side::Buy => (&mut self.bids, -1),
+            Side::Sell => (&mut self.asks, 1),
+        };
+        // better prices first, then older orders
+        let at = side
+            .iter()
+            .position(|o| (sign * order.price, order.id) < (sign * o.price, o.id))
+            .unwrap_or(side.len());
+        side.insert(at, order);
+    }
+
+    // Matches the order against the other side of the book, whatever's left of it
+    // waits on the book. Chatters' orders never match their own.
+    pub fn submit(&mut self, mut order: BookOrder) -> Execution {
+        let mut execution = Execution::default();
+        let resting = match order.side {
+            Side::Buy => &mut self.asks,
+            Side::Sell => &mut self.bids,
+        };
+
+        let mut i = 0;
+        while order.remaining > 0 && i < resting.len() && order.crosses(&resting[i]) {
+            let other = &mut resting[i];
+            if other.owner_id == order.owner_id {
+                i += 1;
+                continue;
+            }
+
+            let price = other.price;
+            let shares = order.remaining.min(other.remaining);
+            let value = value_of(price, shares);
+            let (buy, sell) = match order.side {
+                Side::Buy => (&mut order, other),
+                Side::Sell => (other, &mut order),
+            };
+            buy.escrow -= value;
+            debug_assert!(buy.escrow >= 0, "escrow should cover every trade");
+            buy.remaining -= shares;
+            sell.remaining -= shares;
+            execution.fills.push(Fill {
+                buy_order_id: buy.id,
+                sell_order_id: sell.id,
+                buyer_id: buy.owner_id,
+                seller_id: sell.owner_id,
+                shares,
+                price,
+                value,
+            });
+
+            if resting[i].remaining == 0 {
+                execution.filled.push(resting.remove(i));
+            }
+        }
+
+        if order.remaining == 0 {
+            execution.filled.push(order);
+        } else {
+            self.rest(order);
+        }
+        execution
+    }
+
+    pub fn cancel(&mut self, id: i32) -> Option<BookOrder> {
+        for side in [&mut self.bids, &mut self.asks] {
+            if let Some(at) = side.iter().position(|o| o.id == id) {
+                return Some(side.remove(at));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaceError {
+    NotEnoughPoints { escrow: i64, balance: i64 },
+    NotEnoughShares { available: i32 },
+}
+
+impl fmt::Display for PlaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaceError::NotEnoughPoints { escrow, balance } => write!(
+                f,
+                "that order needs {} point(s) and you have {}",
+                escrow, balance
+            ),
+            PlaceError::NotEnoughShares { available } => write!(
+                f,
+                "you only have {} share(s) that aren't already for sale",
+                available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PlaceError {}
+
+// Arguments of !bid and !ask: <symbol> <shares> <price>
+pub fn parse_limit_order(args: &str) -> Option<(String, i32, i64)> {
+    let mut words = args.split_whitespace();
+    let symbol = words.next()?.to_uppercase();
+    let shares = words.next()?.parse::<i32>().ok().filter(|s| *s > 0)?;
+    let price = BigDecimal::from_str(words.next()?).ok()?;
+    if words.next().is_some() || price.fractional_digit_count() > 2 {
+        return None;
+    }
+    let price = to_cents(&price);
+    // anything bigger doesn't fit the price column
+    if !(1..10_i64.pow(12)).contains(&price) {
+        return None;
+    }
+    Some((symbol, shares, price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn order(id: i32, owner_id: i32, side: Side, price: i64, shares: i32) -> BookOrder {
+        BookOrder {
+            id,
+            owner_id,
+            side,
+            price,
+            remaining: shares,
+            escrow: match side {
+                Side::Buy => escrow_for(price, shares),
+                Side::Sell => 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_price_time_priority() {
+        let mut book = OrderBook::new([
+            order(1, 1, Side::Sell, 1010, 5),
+            order(2, 2, Side::Sell, 1000, 5),
+            order(3, 3, Side::Sell, 1000, 5),
+        ]);
+        assert_eq!(
+            vec![2, 3, 1],
+            book.asks().iter().map(|o| o.id).collect::<Vec<i32>>()
+        );
+
+        let execution = book.submit(order(4, 4, Side::Buy, 1010, 12));
+        let fills = execution
+            .fills
+            .iter()
+            .map(|f| (f.sell_order_id, f.shares, f.price, f.value))
+            .collect::<Vec<(i32, i32, i64, i64)>>();
+        assert_eq!(
+            vec![(2, 5, 1000, 50), (3, 5, 1000, 50), (1, 2, 1010, 20)],
+            fills
+        );
+
+        // order 1 is partly filled and stays, the buy got everything it wanted
+        assert_eq!(3, book.get(1).unwrap().remaining);
+        assert!(book.get(4).is_none());
+        let filled = execution.filled.iter().map(|o| o.id).collect::<Vec<i32>>();
+        assert_eq!(vec![2, 3, 4], filled);
+        // 12 shares at up to 10.10 held back 122, the trades cost 120
+        assert_eq!(2, execution.filled[2].escrow);
+    }
+
+    #[test]
+    fn test_orders_that_dont_cross_rest() {
+        let mut book = OrderBook::new([order(1, 1, Side::Sell, 1000, 5)]);
+        let execution = book.submit(order(2, 2, Side::Buy, 999, 5));
+        assert!(execution.fills.is_empty());
+        assert_eq!(5, book.get(2).unwrap().remaining);
+
+        // a sell below the best bid trades at the bid's price
+        let execution = book.submit(order(3, 3, Side::Sell, 900, 2));
+        assert_eq!(999, execution.fills[0].price);
+        assert_eq!(3, book.get(2).unwrap().remaining);
+    }
+
+    #[test]
+    fn test_own_orders_dont_match() {
+        let mut book = OrderBook::new([order(1, 1, Side::Sell, 1000, 5)]);
+        let execution = book.submit(order(2, 1, Side::Buy, 1000, 5));
+        assert!(execution.fills.is_empty());
+        let execution = book.submit(order(3, 2, Side::Buy, 1000, 5));
+        assert_eq!(1, execution.fills[0].sell_order_id);
+    }
+
+    #[test]
+    fn test_depth() {
+        let book = OrderBook::new([
+            order(1, 1, Side::Buy, 990, 5),
+            order(2, 2, Side::Buy, 1000, 1),
+            order(3, 3, Side::Buy, 990, 2),
+            order(4, 4, Side::Buy, 980, 2),
+            order(5, 5, Side::Sell, 1010, 3),
+        ]);
+        assert_eq!(vec![(1000, 1), (990, 7)], book.depth(Side::Buy, 2));
+        assert_eq!(vec![(1010, 3)], book.depth(Side::Sell, 2));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut book = OrderBook::new([order(1, 1, Side::Buy, 1000, 5)]);
+        assert_eq!(50, book.cancel(1).unwrap().escrow);
+        assert!(book.cancel(1).is_none());
+        assert!(book.bids().is_empty());
+    }
+
+    #[test]
+    fn test_parse_limit_order() {
+        assert_eq!(
+            Some(("FRS".to_string(), 3, 9950)),
+            parse_limit_order("frs 3 99.5")
+        );
+        assert_eq!(None, parse_limit_order("frs 3"));
+        assert_eq!(None, parse_limit_order("frs 0 99"));
+        assert_eq!(None, parse_limit_order("frs 3 0"));
+        assert_eq!(None, parse_limit_order("frs 3 -1"));
+        assert_eq!(None, parse_limit_order("frs 3 1.005"));
+        assert_eq!(None, parse_limit_order("frs 3 99 now"));
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Place {
+            owner: usize,
+            buy: bool,
+            price: i64,
+            shares: i32,
+        },
+        Cancel(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (0..4_usize, any::<bool>(), 1..300_i64, 1..20_i32).prop_map(
+                |(owner, buy, price, shares)| Op::Place { owner, buy, price, shares }
+            ),
+            1 => (0..50_usize).prop_map(Op::Cancel),
+        ]
+    }
+
+    proptest! {
+        // Plays random orders and cancels through the book, moving points and shares
+        // the way placing an order does, and checks nothing is made or lost.
+        #[test]
+        fn test_points_and_shares_are_conserved(ops in prop::collection::vec(op(), 1..200)) {
+            let mut points = [1000_i64; 4];
+            let mut shares = [50_i32; 4];
+            let total_points: i64 = points.iter().sum();
+            let total_shares: i32 = shares.iter().sum();
+            let mut book = OrderBook::default();
+            let mut next_id = 1;
+
+            for op in ops {
+                match op {
+                    Op::Place { owner, buy, price, shares: count } => {
+                        let side = if buy { Side::Buy } else { Side::Sell };
+                        let new = order(next_id, owner as i32, side, price, count);
+                        if buy {
+                            if points[owner] < new.escrow {
+                                continue;
+                            }
+                            points[owner] -= new.escrow;
+                        } else {
+                            let for_sale: i32 = book
+                                .asks()
+                                .iter()
+                                .filter(|o| o.owner_id == owner as i32)
+                                .map(|o| o.remaining)
+                                .sum();
+                            if shares[owner] - for_sale < count {
+                                continue;
+                            }
+                        }
+                        next_id += 1;
+
+                        let execution = book.submit(new);
+                        for fill in &execution.fills {
+                            prop_assert!(fill.shares > 0);
+                            points[fill.seller_id as usize] += fill.value;
+                            shares[fill.seller_id as usize] -= fill.shares;
+                            shares[fill.buyer_id as usize] += fill.shares;
+                        }
+                        for filled in &execution.filled {
+                            prop_assert_eq!(0, filled.remaining);
+                            points[filled.owner_id as usize] += filled.escrow;
+                        }
+                    }
+                    Op::Cancel(id) => {
+                        if let Some(cancelled) = book.cancel(id as i32) {
+                            points[cancelled.owner_id as usize] += cancelled.escrow;
+                        }
+                    }
+                }
+
+                let escrow: i64 = book.bids().iter().map(|o| o.escrow).sum();
+                prop_assert_eq!(total_points, points.iter().sum::<i64>() + escrow);
+                prop_assert_eq!(total_shares, shares.iter().sum::<i32>());
+                prop_assert!(points.iter().all(|p| *p >= 0));
+                prop_assert!(shares.iter().all(|s| *s >= 0));
+                // every bid can still pay for the rest of itself
+                for bid in book.bids() {
+                    prop_assert!(bid.escrow >= value_of(bid.price, bid.remaining));
+                }
+                // and only a chatter's own orders are left crossing each other
+                for bid in book.bids() {
+                    for ask in book.asks() {
+                        prop_assert!(bid.price < ask.price || bid.owner_id == ask.owner_id);
+                    }
+                }
+            }
+        }
+    }
+}
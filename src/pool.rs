@@ -0,0 +1,749 @@
+// The losers pool lottery. Whatever gamblers lose, and whatever chatters pay for
+// tickets with !ticket, goes into the pool, and the draw picks a winner with odds
+// in proportion to the tickets they hold. Cancelling a pool refunds every ticket and
+// carries the rest of the pool over to the next one.
+//
+// The broadcaster draws with !selectPoolWinner, and a schedule can draw on its own:
+// every so many hours, once the pool crosses a threshold or when the stream ends.
+// Chat gets a countdown before a scheduled draw.
+use std::time::Duration as StdDuration;
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDateTime};
+use rand::Rng;
+use tokio::sync::mpsc;
+
+use crate::db;
+use crate::models::{Chatter, LosersPool, PoolWinner};
+use crate::watch_time::HelixClient;
+
+// How often the schedule checks on the pool.
+const TICK: StdDuration = StdDuration::from_secs(30);
+
+// Points a ticket costs, POOL_TICKET_PRICE (default 10).
+pub fn ticket_price() -> i64 {
+    std::env::var("POOL_TICKET_PRICE")
+        .map(|p| {
+            p.trim()
+                .parse()
+                .ok()
+                .filter(|p| *p > 0)
+                .expect("POOL_TICKET_PRICE should be a number")
+        })
+        .unwrap_or(10)
+}
+
+// The argument of !ticket, how many tickets to buy.
+pub fn parse_tickets(arg: Option<&str>) -> Option<i64> {
+    match arg {
+        None => Some(1),
+        Some(arg) => arg.parse::<i64>().ok().filter(|t| *t > 0),
+    }
+}
+
+pub fn total_tickets(entries: &[(i32, i64)]) -> i64 {
+    entries.iter().map(|(_, tickets)| tickets).sum()
+}
+
+// The chance of winning with that many of the tickets, in percent.
+pub fn odds(tickets: i64, total: i64) -> f64 {
+    if total <= 0 {
+        return 0.0;
+    }
+    tickets as f64 * 100.0 / total as f64
+}
+
+// Picks who wins out of (chatter id, tickets), every ticket being as likely as
+// any other. None when nobody holds a ticket.
+pub fn pick_winner<R: Rng + ?Sized>(entries: &[(i32, i64)], rng: &mut R) -> Option<(i32, i64)> {
+    let total = total_tickets(entries);
+    if total <= 0 {
+        return None;
+    }
+    let mut ticket = rng.gen_range(0..total);
+    for (chatter_id, tickets) in entries {
+        if ticket < *tickets {
+            return Some((*chatter_id, *tickets));
+        }
+        ticket -= tickets;
+    }
+    None
+}
+
+// Picks up to `count` different winners, first place first, drawing again from the
+// tickets left after every pick.
+pub fn pick_winners<R: Rng + ?Sized>(
+    entries: &[(i32, i64)],
+    count: usize,
+    rng: &mut R,
+) -> Vec<(i32, i64)> {
+    let mut left = entries.to_vec();
+    let mut winners = vec![];
+    while winners.len() < count {
+        let picked = match pick_winner(&left, rng) {
+            Some(picked) => picked,
+            None => break,
+        };
+        left.retain(|(chatter_id, _)| *chatter_id != picked.0);
+        winners.push(picked);
+    }
+    winners
+}
+
+// Where a drawn pool's points go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Split {
+    // first place first
+    pub prizes: Vec<i64>,
+    pub rollover: i64,
+    pub broadcaster: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payouts {
+    // percent of the prize money each place gets, first place first
+    pub places: Vec<i64>,
+    // percent of the pool that starts the next one
+    pub rollover_percent: i64,
+    pub broadcaster_percent: i64,
+}
+
+impl Payouts {
+    pub fn new(
+        places: Vec<i64>,
+        rollover_percent: i64,
+        broadcaster_percent: i64,
+    ) -> Result<Payouts> {
+        if places.is_empty() || places.iter().any(|p| *p <= 0) {
+            return Err(anyhow!("every place needs a share"));
+        }
+        if places.iter().sum::<i64>() != 100 {
+            return Err(anyhow!("the places' shares should add up to 100"));
+        }
+        if rollover_percent < 0
+            || broadcaster_percent < 0
+            || rollover_percent + broadcaster_percent >= 100
+        {
+            return Err(anyhow!(
+                "the rollover and broadcaster cut should leave something to win"
+            ));
+        }
+        Ok(Payouts {
+            places,
+            rollover_percent,
+            broadcaster_percent,
+        })
+    }
+
+    // POOL_PAYOUTS, the shares of each place like "60/25/15" (default 100, one winner
+    // takes it all), POOL_ROLLOVER_PERCENT and POOL_BROADCASTER_PERCENT (both
+    // default 0).
+    pub fn from_env() -> Payouts {
+        let percent = |name: &str| {
+            std::env::var(name)
+                .map(|p| {
+                    p.trim()
+                        .parse()
+                        .unwrap_or_else(|_| panic!("{} should be a number", name))
+                })
+                .unwrap_or(0)
+        };
+        let places = std::env::var("POOL_PAYOUTS")
+            .map(|places| parse_places(&places).expect("POOL_PAYOUTS should look like 60/25/15"))
+            .unwrap_or_else(|_| vec![100]);
+        Payouts::new(
+            places,
+            percent("POOL_ROLLOVER_PERCENT"),
+            percent("POOL_BROADCASTER_PERCENT"),
+        )
+        .unwrap_or_else(|err| panic!("The pool payouts don't add up: {}", err))
+    }
+
+    // Splits the pool between that many winners. With fewer winners than places the
+    // shares of the places left are spread over the winners there are, and whatever
+    // rounding leaves over goes to first place.
+    pub fn split(&self, amount: i64, winners: usize) -> Split {
+        let broadcaster = amount * self.broadcaster_percent / 100;
+        let rollover = amount * self.rollover_percent / 100;
+        let prize_money = amount - broadcaster - rollover;
+        let shares = &self.places[..winners.min(self.places.len())];
+        let total_shares = shares.iter().sum::<i64>();
+        let mut prizes = shares
+            .iter()
+            .map(|share| prize_money * share / total_shares)
+            .collect::<Vec<i64>>();
+        if let Some(first) = prizes.first_mut() {
+            *first += prize_money
+                - shares
+                    .iter()
+                    .map(|s| prize_money * s / total_shares)
+                    .sum::<i64>();
+        }
+        Split {
+            prizes,
+            rollover,
+            broadcaster,
+        }
+    }
+}
+
+// Shares like "60/25/15" or "60,25,15".
+pub fn parse_places(places: &str) -> Option<Vec<i64>> {
+    places
+        .split(['/', ','])
+        .map(|p| p.trim().parse::<i64>().ok())
+        .collect()
+}
+
+// How a draw went.
+pub enum Drawn {
+    NoPool,
+    NoTickets(LosersPool),
+    Won {
+        pool: LosersPool,
+        // with what they won, first place first
+        winners: Vec<(Chatter, PoolWinner)>,
+        total: i64,
+    },
+}
+
+// Draws the current pool, paying out the winners and starting the next one.
+pub fn draw<R: Rng + ?Sized>(payouts: &Payouts, rng: &mut R) -> anyhow::Result<Drawn> {
+    let pool = match db::get_current_pool() {
+        Some(pool) => pool,
+        None => return Ok(Drawn::NoPool),
+    };
+    let entries = db::get_pool_tickets(pool.id)?;
+    let picked = pick_winners(&entries, payouts.places.len(), rng);
+    if picked.is_empty() {
+        return Ok(Drawn::NoTickets(pool));
+    }
+    let (pool, paid) = match db::draw_pool(pool.id, &picked, payouts) {
+        Some(drawn) => drawn,
+        // someone else drew it first
+        None => return Ok(Drawn::NoPool),
+    };
+    let winners = paid
+        .into_iter()
+        .filter_map(|winner| db::get_chatter_by_id(winner.chatter_id).map(|c| (c, winner)))
+        .collect();
+    Ok(Drawn::Won {
+        pool,
+        winners,
+        total: total_tickets(&entries),
+    })
+}
+
+// 1st, 2nd, 3rd, 4th...
+pub fn ordinal(place: i32) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", place, suffix)
+}
+
+pub fn won_message(pool: &LosersPool, winners: &[(Chatter, PoolWinner)], total: i64) -> String {
+    if let [(chatter, winner)] = winners {
+        if winner.amount == pool.amount {
+            return format!(
+                "@{} has won the pool of {} points with {} of {} tickets ({:.1}% odds)!",
+                chatter.username,
+                pool.amount,
+                winner.tickets,
+                total,
+                odds(winner.tickets, total)
+            );
+        }
+    }
+    let mut message = format!(
+        "The pool of {} points is drawn! {}",
+        pool.amount,
+        winners
+            .iter()
+            .map(|(chatter, winner)| format!(
+                "{} @{} wins {} points ({} of {} tickets)",
+                ordinal(winner.place),
+                chatter.username,
+                winner.amount,
+                winner.tickets,
+                total
+            ))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    if pool.rollover > 0 {
+        message.push_str(&format!(
+            ". {} points roll over to the next pool",
+            pool.rollover
+        ));
+    }
+    if pool.broadcaster_cut > 0 {
+        message.push_str(&format!(
+            ". {} points go to the broadcaster",
+            pool.broadcaster_cut
+        ));
+    }
+    message
+}
+
+// e.g. "10 minutes", "1 hour"
+pub fn duration_words(duration: Duration) -> String {
+    let (amount, unit) = if duration >= Duration::hours(1) && duration.num_minutes() % 60 == 0 {
+        (duration.num_hours(), "hour")
+    } else {
+        (duration.num_minutes().max(1), "minute")
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{} {}{}", amount, unit, plural)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub every: Option<Duration>,
+    pub threshold: Option<i64>,
+    pub at_stream_end: bool,
+    // how long before a draw chat hears about it, longest first
+    pub countdown: Vec<Duration>,
+}
+
+impl Schedule {
+    // POOL_DRAW_HOURS, POOL_DRAW_THRESHOLD and POOL_DRAW_AT_STREAM_END, all off by
+    // default, and POOL_COUNTDOWN_MINUTES (default "10,5,1").
+    pub fn from_env() -> Schedule {
+        let every = std::env::var("POOL_DRAW_HOURS").ok().map(|h| {
+            let hours: i64 = h
+                .trim()
+                .parse()
+                .ok()
+                .filter(|h| *h > 0)
+                .expect("POOL_DRAW_HOURS should be a number");
+            Duration::hours(hours)
+        });
+        let threshold = std::env::var("POOL_DRAW_THRESHOLD").ok().map(|t| {
+            t.trim()
+                .parse()
+                .ok()
+                .filter(|t| *t > 0)
+                .expect("POOL_DRAW_THRESHOLD should be a number")
+        });
+        let at_stream_end = std::env::var("POOL_DRAW_AT_STREAM_END")
+            .map(|s| s.trim() == "true" || s.trim() == "1")
+            .unwrap_or(false);
+        let countdown = parse_countdown(
+            &std::env::var("POOL_COUNTDOWN_MINUTES").unwrap_or_else(|_| "10,5,1".to_string()),
+        )
+        .expect("POOL_COUNTDOWN_MINUTES should look like 10,5,1");
+        Schedule {
+            every,
+            threshold,
+            at_stream_end,
+            countdown,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.every.is_some() || self.threshold.is_some() || self.at_stream_end
+    }
+
+    // A scheduled draw is never sooner than the whole countdown away.
+    fn lead(&self) -> Duration {
+        self.countdown
+            .first()
+            .copied()
+            .unwrap_or_else(Duration::zero)
+    }
+
+    // When the pool gets drawn: what's already scheduled, or after the interval, but
+    // sooner if it crossed the threshold. A threshold draw that found no tickets is
+    // spent until the next pool.
+    pub fn next_draw(
+        &self,
+        pool: &LosersPool,
+        now: NaiveDateTime,
+        threshold_spent: bool,
+    ) -> Option<NaiveDateTime> {
+        let soonest = now + self.lead();
+        let timed = match pool.draw_at {
+            Some(draw_at) => Some(draw_at),
+            None => self
+                .every
+                .map(|every| (pool.created_at + every).max(soonest)),
+        };
+        let crossed = self
+            .threshold
+            .filter(|threshold| !threshold_spent && pool.amount >= *threshold)
+            .map(|_| soonest);
+        match (timed, crossed) {
+            (Some(timed), Some(crossed)) => Some(timed.min(crossed)),
+            (timed, crossed) => timed.or(crossed),
+        }
+    }
+
+    // When to try again after a draw nobody had tickets for. Draws only at the end of
+    // the stream wait for the next one.
+    pub fn retry_at(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match (self.every, self.threshold) {
+            (Some(every), _) => Some(now + every),
+            (None, Some(_)) => Some(now + Duration::hours(1)),
+            (None, None) => None,
+        }
+    }
+
+    // The countdown step the draw just came within, if it wasn't announced yet. Only
+    // the shortest one when several went by at once.
+    pub fn countdown_step(
+        &self,
+        draw_at: NaiveDateTime,
+        now: NaiveDateTime,
+        announced: Option<Duration>,
+    ) -> Option<Duration> {
+        let left = draw_at - now;
+        if left <= Duration::zero() {
+            return None;
+        }
+        self.countdown
+            .iter()
+            .filter(|step| left <= **step)
+            .filter(|step| announced.is_none_or(|announced| **step < announced))
+            .min()
+            .copied()
+    }
+}
+
+// Minutes like "10,5,1".
+pub fn parse_countdown(minutes: &str) -> Option<Vec<Duration>> {
+    let mut countdown = minutes
+        .split(',')
+        .filter(|m| !m.trim().is_empty())
+        .map(|m| m.trim().parse::<i64>().ok().filter(|m| *m > 0))
+        .collect::<Option<Vec<i64>>>()?;
+    countdown.sort_unstable_by(|a, b| b.cmp(a));
+    countdown.dedup();
+    Some(countdown.into_iter().map(Duration::minutes).collect())
+}
+
+// Draws the pool on the schedule, sending what chat should hear to `announce`.
+pub async fn run(
+    schedule: Schedule,
+    payouts: Payouts,
+    helix: HelixClient,
+    broadcaster_id: String,
+    announce: mpsc::Sender<String>,
+) {
+    let mut ticker = tokio::time::interval(TICK);
+    let mut was_live: Option<bool> = None;
+    // (pool id, countdown step) last announced
+    let mut announced: Option<(i32, Duration)> = None;
+    // pool whose threshold draw found no tickets
+    let mut threshold_spent: Option<i32> = None;
+    loop {
+        ticker.tick().await;
+        let now = chrono::Utc::now().naive_utc();
+        let mut pool = match db::get_current_pool() {
+            Some(pool) => pool,
+            None => continue,
+        };
+
+        let mut stream_ended = false;
+        if schedule.at_stream_end {
+            match helix.is_live(&broadcaster_id).await {
+                Ok(live) => {
+                    stream_ended = was_live == Some(true) && !live;
+                    was_live = Some(live);
+                }
+                Err(err) => println!("An error occurred while checking the stream: {}", err),
+            }
+        }
+
+        let next = schedule.next_draw(&pool, now, threshold_spent == Some(pool.id));
+        if next != pool.draw_at {
+            pool.draw_at = next;
+            db::set_pool_draw_at(pool.id, next);
+        }
+        match pool.draw_at {
+            Some(draw_at) if stream_ended || draw_at <= now => {}
+            Some(draw_at) => {
+                let last = announced
+                    .filter(|(id, _)| *id == pool.id)
+                    .map(|(_, step)| step);
+                if let Some(step) = schedule.countdown_step(draw_at, now, last) {
+                    announced = Some((pool.id, step));
+                    let text = format!(
+                        "🎟️ The losers pool of {} points is drawn in {}! Get tickets with !ticket <n>",
+                        pool.amount,
+                        duration_words(step)
+                    );
+                    let _ = announce.send(text).await;
+                }
+                continue;
+            }
+            None if stream_ended => {}
+            None => continue,
+        }
+
+        let drawn = match draw(&payouts, &mut rand::thread_rng()) {
+            Ok(drawn) => drawn,
+            Err(err) => {
+                println!("An error occurred while drawing the pool: {}", err);
+                continue;
+            }
+        };
+        let text = match drawn {
+            Drawn::Won {
+                pool,
+                winners,
+                total,
+            } => won_message(&pool, &winners, total),
+            Drawn::NoTickets(pool) => {
+                threshold_spent = Some(pool.id);
+                let retry_at = schedule.retry_at(now);
+                db::set_pool_draw_at(pool.id, retry_at);
+                match retry_at {
+                    Some(retry_at) => format!(
+                        "No one had a ticket for the losers pool of {} points, the draw moves to {} UTC",
+                        pool.amount,
+                        retry_at.format("%H:%M")
+                    ),
+                    None => format!(
+                        "No one had a ticket for the losers pool of {} points, it's drawn at the end of the next stream",
+                        pool.amount
+                    ),
+                }
+            }
+            Drawn::NoPool => continue,
+        };
+        let _ = announce.send(text).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn at(minutes: i64) -> NaiveDateTime {
+        chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc() + Duration::minutes(minutes)
+    }
+
+    fn pool(amount: i64, draw_at: Option<NaiveDateTime>) -> LosersPool {
+        LosersPool {
+            id: 1,
+            amount,
+            winner: None,
+            created_at: at(0),
+            updated_at: at(0),
+            cancelled_at: None,
+            draw_at,
+            rollover: 0,
+            broadcaster_cut: 0,
+        }
+    }
+
+    fn schedule(every: Option<i64>, threshold: Option<i64>) -> Schedule {
+        Schedule {
+            every: every.map(Duration::hours),
+            threshold,
+            at_stream_end: false,
+            countdown: parse_countdown("10,5,1").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_tickets() {
+        assert_eq!(Some(1), parse_tickets(None));
+        assert_eq!(Some(5), parse_tickets(Some("5")));
+        assert_eq!(None, parse_tickets(Some("0")));
+        assert_eq!(None, parse_tickets(Some("-2")));
+        assert_eq!(None, parse_tickets(Some("lots")));
+    }
+
+    #[test]
+    fn test_odds() {
+        assert_eq!(25.0, odds(5, 20));
+        assert_eq!(100.0, odds(3, 3));
+        assert_eq!(0.0, odds(0, 0));
+    }
+
+    #[test]
+    fn test_pick_winner_is_weighted_by_tickets() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(None, pick_winner(&[], &mut rng));
+        assert_eq!(Some((2, 4)), pick_winner(&[(1, 0), (2, 4)], &mut rng));
+
+        let entries = [(1, 1), (2, 3)];
+        let mut wins = [0; 2];
+        for _ in 0..10_000 {
+            match pick_winner(&entries, &mut rng) {
+                Some((1, _)) => wins[0] += 1,
+                Some((2, _)) => wins[1] += 1,
+                winner => panic!("unexpected winner {:?}", winner),
+            }
+        }
+        // 25% and 75%
+        assert!((2300..2700).contains(&wins[0]), "{:?}", wins);
+        assert!((7300..7700).contains(&wins[1]), "{:?}", wins);
+    }
+
+    #[test]
+    fn test_next_draw() {
+        let hourly = schedule(Some(1), None);
+        assert_eq!(
+            Some(at(60)),
+            hourly.next_draw(&pool(100, None), at(5), false)
+        );
+        // never sooner than the countdown
+        assert_eq!(
+            Some(at(65)),
+            hourly.next_draw(&pool(100, None), at(55), false)
+        );
+        // what's scheduled stays
+        assert_eq!(
+            Some(at(30)),
+            hourly.next_draw(&pool(100, Some(at(30))), at(5), false)
+        );
+
+        let both = schedule(Some(24), Some(1000));
+        assert_eq!(
+            Some(at(24 * 60)),
+            both.next_draw(&pool(999, None), at(5), false)
+        );
+        assert_eq!(
+            Some(at(15)),
+            both.next_draw(&pool(1000, None), at(5), false)
+        );
+        assert_eq!(
+            Some(at(15)),
+            both.next_draw(&pool(1000, Some(at(15))), at(10), false)
+        );
+        assert_eq!(
+            Some(at(24 * 60)),
+            both.next_draw(&pool(1000, None), at(5), true)
+        );
+        assert_eq!(
+            None,
+            schedule(None, Some(1000)).next_draw(&pool(5, None), at(5), false)
+        );
+        assert!(!schedule(None, None).is_enabled());
+    }
+
+    #[test]
+    fn test_retry_at() {
+        assert_eq!(Some(at(5 + 120)), schedule(Some(2), None).retry_at(at(5)));
+        assert_eq!(Some(at(5 + 60)), schedule(None, Some(1000)).retry_at(at(5)));
+        let stream_end = Schedule {
+            at_stream_end: true,
+            ..schedule(None, None)
+        };
+        assert_eq!(None, stream_end.retry_at(at(5)));
+    }
+
+    #[test]
+    fn test_countdown_step() {
+        let s = schedule(Some(1), None);
+        assert_eq!(None, s.countdown_step(at(60), at(30), None));
+        assert_eq!(
+            Some(Duration::minutes(10)),
+            s.countdown_step(at(60), at(50), None)
+        );
+        assert_eq!(
+            None,
+            s.countdown_step(at(60), at(52), Some(Duration::minutes(10)))
+        );
+        // only the shortest of the steps that went by
+        assert_eq!(
+            Some(Duration::minutes(1)),
+            s.countdown_step(at(60), at(59), Some(Duration::minutes(10)))
+        );
+        assert_eq!(None, s.countdown_step(at(60), at(60), None));
+    }
+
+    #[test]
+    fn test_parse_countdown() {
+        assert_eq!(
+            Some(vec![Duration::minutes(10), Duration::minutes(2)]),
+            parse_countdown("2, 10,2")
+        );
+        assert_eq!(Some(vec![]), parse_countdown(""));
+        assert_eq!(None, parse_countdown("5,soon"));
+    }
+
+    #[test]
+    fn test_duration_words() {
+        assert_eq!("10 minutes", duration_words(Duration::minutes(10)));
+        assert_eq!("1 minute", duration_words(Duration::seconds(20)));
+        assert_eq!("2 hours", duration_words(Duration::hours(2)));
+        assert_eq!("90 minutes", duration_words(Duration::minutes(90)));
+    }
+
+    #[test]
+    fn test_split() {
+        let payouts = Payouts::new(vec![60, 25, 15], 10, 5).unwrap();
+        assert_eq!(
+            Split {
+                prizes: vec![511, 212, 127],
+                rollover: 100,
+                broadcaster: 50
+            },
+            payouts.split(1000, 3)
+        );
+        // two winners share what three places would have
+        assert_eq!(
+            Split {
+                prizes: vec![600, 250],
+                rollover: 100,
+                broadcaster: 50
+            },
+            payouts.split(1000, 2)
+        );
+        let split = payouts.split(997, 3);
+        assert_eq!(
+            997,
+            split.prizes.iter().sum::<i64>() + split.rollover + split.broadcaster
+        );
+        assert_eq!(
+            vec![1000],
+            Payouts::new(vec![100], 0, 0).unwrap().split(1000, 1).prizes
+        );
+    }
+
+    #[test]
+    fn test_payouts_add_up() {
+        assert!(Payouts::new(vec![60, 25], 0, 0).is_err());
+        assert!(Payouts::new(vec![], 0, 0).is_err());
+        assert!(Payouts::new(vec![100, 0], 0, 0).is_err());
+        assert!(Payouts::new(vec![100], 60, 40).is_err());
+        assert!(Payouts::new(vec![100], -5, 0).is_err());
+        assert_eq!(Some(vec![60, 25, 15]), parse_places("60/25/15"));
+        assert_eq!(Some(vec![50, 50]), parse_places("50, 50"));
+        assert_eq!(None, parse_places("60/most"));
+    }
+
+    #[test]
+    fn test_pick_winners_are_different_chatters() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let entries = [(1, 10), (2, 1), (3, 5)];
+        for _ in 0..100 {
+            let winners = pick_winners(&entries, 3, &mut rng);
+            let mut ids = winners.iter().map(|(id, _)| *id).collect::<Vec<i32>>();
+            ids.sort_unstable();
+            assert_eq!(vec![1, 2, 3], ids);
+        }
+        assert_eq!(1, pick_winners(&[(1, 2)], 3, &mut rng).len());
+        assert!(pick_winners(&[], 3, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_ordinal() {
+        assert_eq!("1st", ordinal(1));
+        assert_eq!("2nd", ordinal(2));
+        assert_eq!("3rd", ordinal(3));
+        assert_eq!("4th", ordinal(4));
+        assert_eq!("11th", ordinal(11));
+        assert_eq!("22nd", ordinal(22));
+    }
+}
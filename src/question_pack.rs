@@ -0,0 +1,459 @@
+// Question packs are how questions move in and out of the database in bulk.
+// A pack is a list of questions with their accepted answers and category name,
+// stored either as a JSON array or as a CSV file with the same columns.
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::difficulty::Difficulty;
+use crate::models::{NewQuestion, Question, QuestionStatus};
+use crate::question_type::{self, QuestionType};
+
+// Separates answers inside the single `answers` column of a CSV pack.
+const CSV_ANSWER_SEPARATOR: char = '|';
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackQuestion {
+    // free_text when left out, see QuestionType for the others
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    pub question: String,
+    // the first answer is the one shown when nobody gets it, the rest are also accepted
+    pub answers: Vec<String>,
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    // wrong options for multiple choice questions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub incorrect_answers: Vec<String>,
+}
+
+// CSV can't hold a list in a column, so answers are joined with CSV_ANSWER_SEPARATOR.
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    question: String,
+    answers: String,
+    category: String,
+    #[serde(default)]
+    difficulty: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    incorrect_answers: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackFormat {
+    Json,
+    Csv,
+}
+
+impl PackFormat {
+    pub fn from_path(path: &Path) -> Option<PackFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(PackFormat::Json),
+            "csv" => Some(PackFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+// Used to decide whether two questions are the same one: case, punctuation and
+// spacing differences don't count.
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+pub fn parse(contents: &str, format: PackFormat) -> Result<Vec<PackQuestion>> {
+    match format {
+        PackFormat::Json => parse_json(contents),
+        PackFormat::Csv => parse_csv(contents),
+    }
+}
+
+pub fn parse_json(contents: &str) -> Result<Vec<PackQuestion>> {
+    Ok(serde_json::from_str(contents)?)
+}
+
+fn split_answers(answers: &str) -> Vec<String> {
+    answers
+        .split(CSV_ANSWER_SEPARATOR)
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+pub fn parse_csv(contents: &str) -> Result<Vec<PackQuestion>> {
+    let mut reader = csv::Reader::from_reader(contents.as_bytes());
+    let mut questions = Vec::new();
+    for row in reader.deserialize::<CsvRow>() {
+        let row = row?;
+        questions.push(PackQuestion {
+            kind: row.kind.filter(|k| !k.is_empty()),
+            question: row.question,
+            answers: split_answers(&row.answers),
+            category: row.category,
+            difficulty: row.difficulty.filter(|d| !d.is_empty()),
+            source: row.source.filter(|s| !s.is_empty()),
+            incorrect_answers: split_answers(&row.incorrect_answers),
+        });
+    }
+    Ok(questions)
+}
+
+pub fn write(questions: &[PackQuestion], format: PackFormat) -> Result<String> {
+    match format {
+        PackFormat::Json => Ok(serde_json::to_string_pretty(questions)?),
+        PackFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for q in questions {
+                writer.serialize(CsvRow {
+                    kind: q.kind.clone(),
+                    question: q.question.clone(),
+                    answers: q.answers.join(&CSV_ANSWER_SEPARATOR.to_string()),
+                    category: q.category.clone(),
+                    difficulty: q.difficulty.clone(),
+                    source: q.source.clone(),
+                    incorrect_answers: q.incorrect_answers.join(&CSV_ANSWER_SEPARATOR.to_string()),
+                })?;
+            }
+            Ok(String::from_utf8(writer.into_inner()?)?)
+        }
+    }
+}
+
+fn validate(question: &PackQuestion) -> Result<()> {
+    if normalize(&question.question).is_empty() {
+        return Err(anyhow!("question is empty"));
+    }
+    if question.answers.iter().all(|a| a.trim().is_empty()) {
+        return Err(anyhow!("question has no answers"));
+    }
+    if question.category.trim().is_empty() {
+        return Err(anyhow!("question has no category"));
+    }
+    if let Some(difficulty) = &question.difficulty {
+        difficulty.parse::<Difficulty>()?;
+    }
+    let answer = question.answers.iter().find(|a| !a.trim().is_empty());
+    match question.question_type()? {
+        QuestionType::FreeText => {}
+        QuestionType::MultipleChoice => {
+            let options = 1 + question.incorrect_answers.len();
+            if !(2..=26).contains(&options) {
+                return Err(anyhow!(
+                    "multiple choice question needs between 1 and 25 incorrect answers"
+                ));
+            }
+        }
+        QuestionType::TrueFalse => {
+            if answer.and_then(|a| question_type::parse_bool(a)).is_none() {
+                return Err(anyhow!(
+                    "true/false question must be answered true or false"
+                ));
+            }
+        }
+        QuestionType::Numeric => {
+            if answer
+                .and_then(|a| question_type::parse_number(a))
+                .is_none()
+            {
+                return Err(anyhow!("numeric question must have a number as its answer"));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl PackQuestion {
+    pub fn question_type(&self) -> Result<QuestionType> {
+        match &self.kind {
+            Some(kind) => kind.parse(),
+            None => Ok(QuestionType::FreeText),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub question: String,
+    pub existing_id: Option<i32>,
+    pub existing_answer: String,
+    pub incoming_answer: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let existing = match self.existing_id {
+            Some(id) => format!("question #{}", id),
+            None => "an earlier entry in this pack".to_string(),
+        };
+        write!(
+            f,
+            "\"{}\" is already {} with answer \"{}\", pack says \"{}\"",
+            self.question, existing, self.existing_answer, self.incoming_answer
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub unchanged: usize,
+    pub categories_created: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+    // (position in the pack, what was wrong with it)
+    pub invalid: Vec<(usize, String)>,
+}
+
+// What an incoming question matched against, keyed by its normalized text.
+struct Known {
+    id: Option<i32>,
+    answer: String,
+}
+
+// Imports a pack without touching questions that are already there, so running the
+// same pack twice is a no-op. A question is "already there" when its normalized text
+// matches; if the answers disagree it is reported as a conflict and left alone.
+pub fn import(questions: &[PackQuestion], submitter_id: i32, dry_run: bool) -> ImportReport {
+    let mut report = ImportReport::default();
+
+    let mut categories = db::get_categories()
+        .into_iter()
+        .map(|c| (c.name.trim().to_lowercase(), c.id))
+        .collect::<HashMap<String, i32>>();
+    let mut known = db::get_questions()
+        .into_iter()
+        .map(|q| {
+            (
+                normalize(&q.question),
+                Known {
+                    id: Some(q.id),
+                    answer: q.answer,
+                },
+            )
+        })
+        .collect::<HashMap<String, Known>>();
+
+    for (i, pack_question) in questions.iter().enumerate() {
+        if let Err(err) = validate(pack_question) {
+            report.invalid.push((i + 1, err.to_string()));
+            continue;
+        }
+
+        let answers = pack_question
+            .answers
+            .iter()
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect::<Vec<String>>();
+        let key = normalize(&pack_question.question);
+
+        if let Some(existing) = known.get(&key) {
+            if answers
+                .iter()
+                .any(|a| normalize(a) == normalize(&existing.answer))
+            {
+                report.unchanged += 1;
+            } else {
+                report.conflicts.push(Conflict {
+                    question: pack_question.question.trim().to_string(),
+                    existing_id: existing.id,
+                    existing_answer: existing.answer.clone(),
+                    incoming_answer: answers[0].clone(),
+                });
+            }
+            continue;
+        }
+
+        let category_name = pack_question.category.trim();
+        let category_id = match categories.get(&category_name.to_lowercase()) {
+            Some(id) => *id,
+            None => {
+                let id = if dry_run {
+                    0
+                } else {
+                    db::create_category(category_name, submitter_id).id
+                };
+                categories.insert(category_name.to_lowercase(), id);
+                report.categories_created.push(category_name.to_string());
+                id
+            }
+        };
+
+        let authored_difficulty = pack_question
+            .difficulty
+            .as_ref()
+            .and_then(|d| d.parse::<Difficulty>().ok());
+        let id = if dry_run {
+            None
+        } else {
+            let question = db::insert_question(&NewQuestion {
+                question: pack_question.question.trim(),
+                answer: &answers[0],
+                category_id,
+                submitter_id,
+                status: QuestionStatus::Approved.as_str(),
+                points_paid: 0,
+                alternate_answers: &answers[1..],
+                authored_difficulty: authored_difficulty.as_ref().map(Difficulty::as_str),
+                source: pack_question.source.as_deref(),
+                incorrect_answers: &pack_question.incorrect_answers,
+                kind: pack_question.question_type().unwrap_or_default().as_str(),
+            });
+            Some(question.id)
+        };
+        known.insert(
+            key,
+            Known {
+                id,
+                answer: answers[0].clone(),
+            },
+        );
+        report.imported += 1;
+    }
+
+    report
+}
+
+pub fn from_question(question: &Question, category: &str) -> PackQuestion {
+    PackQuestion {
+        kind: match question.question_type() {
+            QuestionType::FreeText => None,
+            kind => Some(kind.as_str().to_string()),
+        },
+        question: question.question.clone(),
+        answers: std::iter::once(question.answer.clone())
+            .chain(question.alternate_answers.iter().cloned())
+            .collect(),
+        category: category.to_string(),
+        difficulty: question.authored_difficulty.clone(),
+        source: question.source.clone(),
+        incorrect_answers: question.incorrect_answers.clone(),
+    }
+}
+
+// Every approved question, ready to be written out as a pack.
+pub fn export() -> Vec<PackQuestion> {
+    let categories = db::get_categories()
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect::<HashMap<i32, String>>();
+
+    db::get_questions()
+        .iter()
+        .filter(|q| q.status == QuestionStatus::Approved.as_str())
+        .map(|q| {
+            let category = categories
+                .get(&q.category_id)
+                .map(String::as_str)
+                .unwrap_or("General");
+            from_question(q, category)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            "may the force be with you",
+            normalize("May the Force be with you!")
+        );
+        assert_eq!("if err nil", normalize("  if err != nil  "));
+        assert_eq!(normalize("I'll be back"), normalize("i ll  BE back."));
+        assert_eq!("", normalize("?!"));
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let pack = r#"[
+            {"question": "I'll be back", "answers": ["terminator", "the terminator"], "category": "Movies", "difficulty": "easy"},
+            {"question": "segfault", "answers": ["c"], "category": "Languages", "source": "stream"}
+        ]"#;
+        let questions = parse_json(pack).unwrap();
+        assert_eq!(2, questions.len());
+        assert_eq!(vec!["terminator", "the terminator"], questions[0].answers);
+        assert_eq!(None, questions[1].difficulty);
+
+        let written = write(&questions, PackFormat::Json).unwrap();
+        assert_eq!(questions, parse_json(&written).unwrap());
+    }
+
+    #[test]
+    fn test_csv_roundtrip() {
+        let pack = "question,answers,category,difficulty,source\n\
+                    \"if err != nil\",go|golang,Languages,,\n\
+                    \"It's alive!\",frankenstein,Movies,hard,classics\n";
+        let questions = parse_csv(pack).unwrap();
+        assert_eq!(vec!["go", "golang"], questions[0].answers);
+        assert_eq!(None, questions[0].difficulty);
+        assert_eq!(Some("hard".to_string()), questions[1].difficulty);
+
+        let written = write(&questions, PackFormat::Csv).unwrap();
+        assert_eq!(questions, parse_csv(&written).unwrap());
+    }
+
+    #[test]
+    fn test_validate() {
+        let question = PackQuestion {
+            kind: None,
+            question: "segfault".to_string(),
+            answers: vec!["c".to_string()],
+            category: "Languages".to_string(),
+            difficulty: None,
+            source: None,
+            incorrect_answers: vec![],
+        };
+        assert!(validate(&question).is_ok());
+        assert!(validate(&PackQuestion {
+            answers: vec![" ".to_string()],
+            ..question.clone()
+        })
+        .is_err());
+        assert!(validate(&PackQuestion {
+            difficulty: Some("impossible".to_string()),
+            ..question.clone()
+        })
+        .is_err());
+        assert!(validate(&PackQuestion {
+            kind: Some("multiple_choice".to_string()),
+            ..question.clone()
+        })
+        .is_err());
+        assert!(validate(&PackQuestion {
+            kind: Some("true_false".to_string()),
+            answers: vec!["True".to_string()],
+            ..question.clone()
+        })
+        .is_ok());
+        assert!(validate(&PackQuestion {
+            kind: Some("numeric".to_string()),
+            ..question.clone()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_starter_pack_parses() {
+        let questions = parse_json(crate::seed::STARTER_PACK).unwrap();
+        assert!(questions.iter().all(|q| validate(q).is_ok()));
+    }
+}
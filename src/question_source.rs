@@ -0,0 +1,685 @@
+// Where duels get their questions from. Duels only talk to a QuestionSource, so the
+// stored questions in Postgres, generated questions and the in-memory questions
+// used in tests are interchangeable, and can be mixed with a WeightedSource.
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::db;
+use crate::difficulty::Difficulty;
+use crate::generator::{self, GeneratedQuestion, Topic};
+use crate::models::{NewQuestionAsk, Question, QuestionAsk, QuestionStatus};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionFilter {
+    pub difficulty: Option<Difficulty>,
+    // only generated questions have a topic, asking for one rules out stored questions
+    pub topic: Option<Topic>,
+    // sources that pick at random pick the same way for the same seed where they can
+    pub seed: u64,
+    pub channel: String,
+    pub player_ids: Vec<String>,
+    pub repeats: Repeats,
+}
+
+// How hard to try not to ask something that was asked recently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Repeats {
+    // nothing asked recently in the channel or to either player
+    #[default]
+    Avoid,
+    // only what either player was asked recently
+    AvoidForPlayers,
+    Allow,
+}
+
+// How long a question stays out of rotation after it was asked, in the channel it
+// was asked in and for the players it was asked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepeatWindows {
+    pub channel: chrono::Duration,
+    pub player: chrono::Duration,
+}
+
+impl Default for RepeatWindows {
+    fn default() -> Self {
+        RepeatWindows {
+            channel: chrono::Duration::hours(12),
+            player: chrono::Duration::days(7),
+        }
+    }
+}
+
+impl RepeatWindows {
+    // NO_REPEAT_CHANNEL_HOURS and NO_REPEAT_PLAYER_HOURS, 0 turns a window off.
+    pub fn from_env() -> RepeatWindows {
+        let hours = |name: &str, default: chrono::Duration| match std::env::var(name) {
+            Ok(hours) => chrono::Duration::hours(
+                hours
+                    .trim()
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{} should be a number of hours", name)),
+            ),
+            Err(_) => default,
+        };
+        let defaults = RepeatWindows::default();
+        RepeatWindows {
+            channel: hours("NO_REPEAT_CHANNEL_HOURS", defaults.channel),
+            player: hours("NO_REPEAT_PLAYER_HOURS", defaults.player),
+        }
+    }
+
+    pub fn longest(&self) -> chrono::Duration {
+        self.channel.max(self.player)
+    }
+
+    // Ids of the questions the filter rules out, out of the given asks.
+    pub fn recently_asked(
+        &self,
+        asks: &[QuestionAsk],
+        filter: &QuestionFilter,
+        now: chrono::NaiveDateTime,
+    ) -> Vec<i32> {
+        let mut ids = asks
+            .iter()
+            .filter(|ask| {
+                let age = now - ask.asked_at;
+                let in_channel = filter.repeats == Repeats::Avoid
+                    && age < self.channel
+                    && ask.channel == filter.channel;
+                let by_players = filter.repeats != Repeats::Allow
+                    && age < self.player
+                    && ask.player_ids.iter().any(|p| filter.player_ids.contains(p));
+                in_channel || by_players
+            })
+            .map(|ask| ask.question_id)
+            .collect::<Vec<i32>>();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PickedQuestion {
+    Stored(Question),
+    Generated(Topic, GeneratedQuestion),
+}
+
+impl PickedQuestion {
+    pub fn question_ref(&self) -> QuestionRef {
+        match self {
+            PickedQuestion::Stored(question) => QuestionRef::Stored(question.id),
+            PickedQuestion::Generated(topic, _) => QuestionRef::Generated(*topic),
+        }
+    }
+}
+
+// Identifies a question that was asked, so its outcome can be recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestionRef {
+    Stored(i32),
+    Generated(Topic),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Asked,
+    Correct,
+    WrongGuess,
+    NotAnswered,
+}
+
+pub trait QuestionSource: fmt::Debug + Send {
+    fn pick(&mut self, filter: &QuestionFilter) -> Option<PickedQuestion>;
+
+    // Sources ignore outcomes for questions they didn't hand out.
+    fn record_outcome(&mut self, question: &QuestionRef, outcome: Outcome);
+
+    // Called once a picked question has been put to the players of the filter.
+    fn record_ask(&mut self, question: &QuestionRef, _filter: &QuestionFilter) {
+        self.record_outcome(question, Outcome::Asked);
+    }
+}
+
+// Picks a question for the filter. Gives up on the difficulty before it gives up on
+// fresh questions, and only repeats a recent one when everything has been asked.
+pub fn choose(source: &mut dyn QuestionSource, filter: &QuestionFilter) -> Option<PickedQuestion> {
+    let tiers = match filter.difficulty {
+        Some(_) => vec![filter.difficulty, None],
+        None => vec![None],
+    };
+    for repeats in [Repeats::Avoid, Repeats::AvoidForPlayers, Repeats::Allow] {
+        for difficulty in tiers.iter() {
+            let relaxed = QuestionFilter {
+                difficulty: *difficulty,
+                repeats,
+                ..filter.clone()
+            };
+            if let Some(question) = source.pick(&relaxed) {
+                return Some(question);
+            }
+        }
+    }
+    None
+}
+
+// Approved questions from the questions table, with asks logged to question_asks.
+#[derive(Debug, Default)]
+pub struct PostgresSource {
+    pub windows: RepeatWindows,
+}
+
+impl QuestionSource for PostgresSource {
+    fn pick(&mut self, filter: &QuestionFilter) -> Option<PickedQuestion> {
+        if filter.topic.is_some() {
+            return None;
+        }
+        let exclude = match filter.repeats {
+            Repeats::Allow => vec![],
+            _ => {
+                let now = chrono::Utc::now().naive_utc();
+                let asks = db::get_question_asks_since(now - self.windows.longest());
+                self.windows.recently_asked(&asks, filter, now)
+            }
+        };
+        let question = match filter.difficulty {
+            Some(difficulty) => db::get_random_question_with_difficulty(difficulty, &exclude),
+            None => db::get_random_question(&exclude),
+        };
+        question.map(PickedQuestion::Stored)
+    }
+
+    fn record_ask(&mut self, question: &QuestionRef, filter: &QuestionFilter) {
+        self.record_outcome(question, Outcome::Asked);
+        if let QuestionRef::Stored(id) = *question {
+            db::log_question_ask(&NewQuestionAsk {
+                question_id: id,
+                channel: &filter.channel,
+                player_ids: &filter.player_ids,
+            });
+        }
+    }
+
+    fn record_outcome(&mut self, question: &QuestionRef, outcome: Outcome) {
+        let QuestionRef::Stored(id) = *question else {
+            return;
+        };
+        match outcome {
+            Outcome::Asked => db::increment_times_asked(id),
+            Outcome::Correct => db::update_times_answered_correctly(id),
+            Outcome::WrongGuess => db::update_times_guessed_wrong(id),
+            Outcome::NotAnswered => db::update_times_not_answered(id),
+        }
+    }
+}
+
+// Keeps questions, their outcome counters and when they were asked in memory,
+// mostly for tests.
+#[derive(Debug, Default)]
+pub struct InMemorySource {
+    pub questions: Vec<Question>,
+    pub asks: Vec<QuestionAsk>,
+    pub windows: RepeatWindows,
+}
+
+impl InMemorySource {
+    pub fn new(questions: Vec<Question>) -> InMemorySource {
+        InMemorySource {
+            questions,
+            ..Default::default()
+        }
+    }
+}
+
+impl QuestionSource for InMemorySource {
+    fn pick(&mut self, filter: &QuestionFilter) -> Option<PickedQuestion> {
+        if filter.topic.is_some() {
+            return None;
+        }
+        let exclude =
+            self.windows
+                .recently_asked(&self.asks, filter, chrono::Utc::now().naive_utc());
+        let candidates = self
+            .questions
+            .iter()
+            .filter(|q| q.status == QuestionStatus::Approved.as_str())
+            .filter(|q| !exclude.contains(&q.id))
+            .filter(|q| filter.difficulty.is_none_or(|d| q.difficulty() == d))
+            .collect::<Vec<&Question>>();
+        candidates
+            .choose(&mut StdRng::seed_from_u64(filter.seed))
+            .map(|q| PickedQuestion::Stored((*q).clone()))
+    }
+
+    fn record_outcome(&mut self, question: &QuestionRef, outcome: Outcome) {
+        let QuestionRef::Stored(id) = *question else {
+            return;
+        };
+        if let Some(question) = self.questions.iter_mut().find(|q| q.id == id) {
+            match outcome {
+                Outcome::Asked => question.times_asked += 1,
+                Outcome::Correct => question.times_answered_correctly += 1,
+                Outcome::WrongGuess => question.times_guessed_wrong += 1,
+                Outcome::NotAnswered => question.times_not_answered += 1,
+            }
+        }
+    }
+
+    fn record_ask(&mut self, question: &QuestionRef, filter: &QuestionFilter) {
+        self.record_outcome(question, Outcome::Asked);
+        if let QuestionRef::Stored(id) = *question {
+            self.asks.push(QuestionAsk {
+                id: self.asks.len() as i32 + 1,
+                question_id: id,
+                channel: filter.channel.clone(),
+                player_ids: filter.player_ids.clone(),
+                asked_at: chrono::Utc::now().naive_utc(),
+            });
+        }
+    }
+}
+
+// Makes questions up on the spot from the given topics.
+#[derive(Debug)]
+pub struct GeneratorSource {
+    pub topics: Vec<Topic>,
+}
+
+impl Default for GeneratorSource {
+    fn default() -> Self {
+        GeneratorSource {
+            topics: Topic::ALL.to_vec(),
+        }
+    }
+}
+
+impl QuestionSource for GeneratorSource {
+    fn pick(&mut self, filter: &QuestionFilter) -> Option<PickedQuestion> {
+        let topic = match filter.topic {
+            Some(topic) if self.topics.contains(&topic) => topic,
+            Some(_) => return None,
+            None => *self
+                .topics
+                .choose(&mut StdRng::seed_from_u64(filter.seed))?,
+        };
+        let question = generator::generate(topic, filter.difficulty, filter.seed);
+        Some(PickedQuestion::Generated(topic, question))
+    }
+
+    fn record_outcome(&mut self, _question: &QuestionRef, _outcome: Outcome) {}
+}
+
+// Picks one of its sources at random by weight, moving on to the others when the
+// picked one has nothing that matches. Sources weighted 0 are only tried last and
+// only for duels asking for a topic, e.g. generated questions with the default weights.
+#[derive(Debug, Default)]
+pub struct WeightedSource {
+    pub sources: Vec<(u32, Box<dyn QuestionSource>)>,
+}
+
+impl WeightedSource {
+    pub fn new() -> WeightedSource {
+        WeightedSource::default()
+    }
+
+    pub fn with(mut self, weight: u32, source: impl QuestionSource + 'static) -> WeightedSource {
+        self.sources.push((weight, Box::new(source)));
+        self
+    }
+
+    // QUESTION_SOURCE_WEIGHTS looks like "stored=3,generated=1". Stored questions
+    // only is the default; generated ones are still there for duels asking for a topic.
+    pub fn from_env() -> WeightedSource {
+        let weights = std::env::var("QUESTION_SOURCE_WEIGHTS")
+            .map(|w| parse_weights(&w).expect("QUESTION_SOURCE_WEIGHTS is invalid"))
+            .unwrap_or_default();
+        WeightedSource::from_weights(
+            &weights,
+            PostgresSource {
+                windows: RepeatWindows::from_env(),
+            },
+        )
+    }
+
+    pub fn from_weights(
+        weights: &[(String, u32)],
+        stored: impl QuestionSource + 'static,
+    ) -> WeightedSource {
+        let weight = |name: &str, default: u32| {
+            weights
+                .iter()
+                .find(|(n, _)| n == name)
+                .map_or(default, |(_, w)| *w)
+        };
+        WeightedSource::new()
+            .with(weight("stored", 1), stored)
+            .with(weight("generated", 0), GeneratorSource::default())
+    }
+
+    // Indexes of the sources in the order they should be tried.
+    fn order(&self, seed: u64) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut weighted = (0..self.sources.len())
+            .filter(|i| self.sources[*i].0 > 0)
+            .collect::<Vec<usize>>();
+        let mut order = Vec::with_capacity(self.sources.len());
+        while !weighted.is_empty() {
+            let total = weighted
+                .iter()
+                .map(|i| self.sources[*i].0 as u64)
+                .sum::<u64>();
+            let mut roll = rng.gen_range(0..total);
+            let position = weighted
+                .iter()
+                .position(|i| {
+                    let weight = self.sources[*i].0 as u64;
+                    if roll < weight {
+                        true
+                    } else {
+                        roll -= weight;
+                        false
+                    }
+                })
+                .unwrap();
+            order.push(weighted.remove(position));
+        }
+        order.extend((0..self.sources.len()).filter(|i| self.sources[*i].0 == 0));
+        order
+    }
+}
+
+impl QuestionSource for WeightedSource {
+    fn pick(&mut self, filter: &QuestionFilter) -> Option<PickedQuestion> {
+        for i in self.order(filter.seed) {
+            if self.sources[i].0 == 0 && filter.topic.is_none() {
+                continue;
+            }
+            if let Some(question) = self.sources[i].1.pick(filter) {
+                return Some(question);
+            }
+        }
+        None
+    }
+
+    fn record_outcome(&mut self, question: &QuestionRef, outcome: Outcome) {
+        for (_, source) in self.sources.iter_mut() {
+            source.record_outcome(question, outcome);
+        }
+    }
+
+    fn record_ask(&mut self, question: &QuestionRef, filter: &QuestionFilter) {
+        for (_, source) in self.sources.iter_mut() {
+            source.record_ask(question, filter);
+        }
+    }
+}
+
+pub fn parse_weights(weights: &str) -> Result<Vec<(String, u32)>> {
+    weights
+        .split(',')
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let (name, weight) = w
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected <source>=<weight>, got {}", w))?;
+            let name = name.trim().to_lowercase();
+            if name != "stored" && name != "generated" {
+                return Err(anyhow!("unknown question source {}", name));
+            }
+            Ok((name, weight.trim().parse()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Duel;
+    use crate::question_type::QuestionType;
+
+    fn question(id: i32, asked: i32, correct: i32) -> Question {
+        let now = chrono::Utc::now().naive_utc();
+        Question {
+            id,
+            question: format!("question {}", id),
+            answer: format!("answer {}", id),
+            category_id: 1,
+            submitter_id: 1,
+            times_asked: asked,
+            times_not_answered: asked - correct,
+            created_at: now,
+            updated_at: now,
+            times_answered_correctly: correct,
+            times_guessed_wrong: 0,
+            status: QuestionStatus::Approved.as_str().to_string(),
+            review_note: None,
+            points_paid: 0,
+            alternate_answers: vec![],
+            authored_difficulty: None,
+            source: None,
+            incorrect_answers: vec![],
+            kind: QuestionType::FreeText.as_str().to_string(),
+        }
+    }
+
+    fn duel(id: i32, difficulty: Option<Difficulty>, topic: Option<Topic>) -> Duel {
+        let now = chrono::Utc::now().naive_utc();
+        Duel {
+            id,
+            accepted: true,
+            points: 100,
+            challenger: "a".to_string(),
+            challenged: "b".to_string(),
+            winner: None,
+            status: "accepted".to_string(),
+            created_at: now,
+            updated_at: now,
+            question: None,
+            answer: None,
+            challenger_id: Some("1".to_string()),
+            challenged_id: Some("2".to_string()),
+            challenger_guesses: 5,
+            challenged_guesses: 5,
+            question_id: None,
+            difficulty: difficulty.map(|d| d.as_str().to_string()),
+            challenger_numeric_guess: None,
+            challenged_numeric_guess: None,
+            topic: topic.map(|t| t.as_str().to_string()),
+            question_kind: None,
+            options: vec![],
+            alternate_answers: vec![],
+        }
+    }
+
+    fn filter(difficulty: Option<Difficulty>, topic: Option<Topic>, seed: u64) -> QuestionFilter {
+        QuestionFilter {
+            difficulty,
+            topic,
+            seed,
+            channel: "#ttb".to_string(),
+            player_ids: vec!["1".to_string(), "2".to_string()],
+            repeats: Repeats::Avoid,
+        }
+    }
+
+    fn ask(question_id: i32, channel: &str, player_id: &str, hours_ago: i64) -> QuestionAsk {
+        QuestionAsk {
+            id: question_id,
+            question_id,
+            channel: channel.to_string(),
+            player_ids: vec![player_id.to_string()],
+            asked_at: chrono::Utc::now().naive_utc() - chrono::Duration::hours(hours_ago),
+        }
+    }
+
+    fn stored_id(picked: Option<PickedQuestion>) -> i32 {
+        match picked {
+            Some(PickedQuestion::Stored(q)) => q.id,
+            other => panic!("expected a stored question, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_in_memory_source_filters_and_records() {
+        // 20/20 solved is easy, 0/20 solved is hard
+        let mut source = InMemorySource::new(vec![question(1, 20, 20), question(2, 20, 0)]);
+        let hard = filter(Some(Difficulty::Hard), None, 0);
+        assert_eq!(2, stored_id(source.pick(&hard)));
+        assert!(source.pick(&filter(None, Some(Topic::Math), 0)).is_none());
+
+        source.record_ask(&QuestionRef::Stored(2), &hard);
+        source.record_outcome(&QuestionRef::Stored(2), Outcome::Correct);
+        source.record_outcome(&QuestionRef::Generated(Topic::Math), Outcome::Correct);
+        assert_eq!(21, source.questions[1].times_asked);
+        assert_eq!(1, source.questions[1].times_answered_correctly);
+        assert!(source.pick(&hard).is_none());
+    }
+
+    #[test]
+    fn test_recently_asked_windows() {
+        let windows = RepeatWindows {
+            channel: chrono::Duration::hours(12),
+            player: chrono::Duration::hours(48),
+        };
+        let asks = vec![
+            ask(1, "#ttb", "9", 1),
+            ask(2, "#ttb", "9", 13),
+            ask(3, "#other", "1", 24),
+            ask(4, "#other", "2", 72),
+        ];
+        let now = chrono::Utc::now().naive_utc();
+        let mut f = filter(None, None, 0);
+        assert_eq!(vec![1, 3], windows.recently_asked(&asks, &f, now));
+        f.repeats = Repeats::AvoidForPlayers;
+        assert_eq!(vec![3], windows.recently_asked(&asks, &f, now));
+        f.repeats = Repeats::Allow;
+        assert!(windows.recently_asked(&asks, &f, now).is_empty());
+    }
+
+    #[test]
+    fn test_choose_falls_back_gracefully() {
+        let mut source = InMemorySource::new(vec![question(1, 20, 20), question(2, 20, 0)]);
+        let hard = filter(Some(Difficulty::Hard), None, 0);
+        // the only hard question was just asked in the channel, so an easy one it is
+        source.asks.push(ask(2, "#ttb", "9", 1));
+        assert_eq!(1, stored_id(choose(&mut source, &hard)));
+        // everything was asked recently, the players haven't seen question 2 though
+        source.asks.push(ask(1, "#ttb", "1", 1));
+        assert_eq!(2, stored_id(choose(&mut source, &hard)));
+        // the players have seen everything, so something gets repeated
+        source.asks.push(ask(2, "#ttb", "2", 1));
+        assert_eq!(2, stored_id(choose(&mut source, &hard)));
+    }
+
+    #[test]
+    fn test_choose_relaxes_stored_questions_before_generating() {
+        // wired like from_env, so the generator is there with weight 0
+        let source = |asks: Vec<QuestionAsk>| {
+            let mut stored = InMemorySource::new(vec![question(1, 20, 20), question(2, 20, 0)]);
+            stored.asks = asks;
+            WeightedSource::from_weights(&[], stored)
+        };
+        let hard = filter(Some(Difficulty::Hard), None, 0);
+        assert_eq!(2, stored_id(choose(&mut source(vec![]), &hard)));
+        let asked_in_channel = vec![ask(2, "#ttb", "9", 1)];
+        assert_eq!(1, stored_id(choose(&mut source(asked_in_channel), &hard)));
+        let seen_by_players = vec![ask(1, "#ttb", "1", 1), ask(2, "#ttb", "2", 1)];
+        assert_eq!(2, stored_id(choose(&mut source(seen_by_players), &hard)));
+    }
+
+    #[test]
+    fn test_weighted_source_falls_back() {
+        let mut source = WeightedSource::new()
+            .with(1, InMemorySource::default())
+            .with(0, GeneratorSource::default());
+        // the empty in-memory source has nothing, so the generator answers when
+        // a topic was asked for and nothing does otherwise
+        for seed in 0..10 {
+            assert!(matches!(
+                source.pick(&filter(None, Some(Topic::Bits), seed)),
+                Some(PickedQuestion::Generated(Topic::Bits, _))
+            ));
+            assert!(source.pick(&filter(None, None, seed)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_default_weights_only_generate_for_a_topic() {
+        let mut source = WeightedSource::from_weights(&[], InMemorySource::default());
+        for seed in 0..10 {
+            assert!(source.pick(&filter(None, None, seed)).is_none());
+            assert!(matches!(
+                source.pick(&filter(None, Some(Topic::Math), seed)),
+                Some(PickedQuestion::Generated(Topic::Math, _))
+            ));
+        }
+
+        // weighted above 0 the generator answers any duel
+        let mut source = WeightedSource::from_weights(
+            &parse_weights("generated=1").unwrap(),
+            InMemorySource::default(),
+        );
+        assert!(matches!(
+            source.pick(&filter(None, None, 1)),
+            Some(PickedQuestion::Generated(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_weighted_source_respects_weights() {
+        let source = WeightedSource::new()
+            .with(3, InMemorySource::default())
+            .with(1, InMemorySource::default())
+            .with(0, InMemorySource::default());
+        let firsts = (0..1000)
+            .map(|seed| source.order(seed))
+            .inspect(|order| assert_eq!(2, order[2]))
+            .filter(|order| order[0] == 0)
+            .count();
+        assert!((650..850).contains(&firsts), "{}", firsts);
+    }
+
+    #[test]
+    fn test_duel_asks_from_any_source() {
+        let mut stored = InMemorySource::new(vec![question(1, 20, 20), question(2, 20, 0)]);
+        let mut generated = GeneratorSource::default();
+
+        // a hard duel with only an easy question falls back to it
+        let mut easy_only = InMemorySource::new(vec![question(1, 20, 20)]);
+        let hard_duel = duel(7, Some(Difficulty::Hard), None);
+        let filter = hard_duel.question_filter("#ttb");
+        assert_eq!(vec!["1".to_string(), "2".to_string()], filter.player_ids);
+        assert_eq!(1, stored_id(choose(&mut easy_only, &filter)));
+
+        let easy_duel = duel(7, Some(Difficulty::Easy), None);
+        assert_eq!(
+            1,
+            stored_id(choose(&mut stored, &easy_duel.question_filter("#ttb")))
+        );
+        let math_duel = duel(7, Some(Difficulty::Hard), Some(Topic::Math));
+        match choose(&mut generated, &math_duel.question_filter("#ttb")) {
+            Some(PickedQuestion::Generated(topic, q)) => {
+                assert_eq!(Topic::Math, topic);
+                assert_eq!(Difficulty::Hard, q.difficulty);
+            }
+            other => panic!("expected a math question, got {:?}", other),
+        }
+        assert!(choose(&mut stored, &math_duel.question_filter("#ttb")).is_none());
+    }
+
+    #[test]
+    fn test_parse_weights() {
+        assert_eq!(
+            vec![("stored".to_string(), 3), ("generated".to_string(), 1)],
+            parse_weights("stored=3, Generated=1").unwrap()
+        );
+        assert!(parse_weights("stored").is_err());
+        assert!(parse_weights("trivia=1").is_err());
+        assert!(parse_weights("stored=-1").is_err());
+    }
+}
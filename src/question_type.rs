@@ -0,0 +1,234 @@
+// The ways a question can be asked and answered in a duel.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+// Free text questions have always given each duelist 5 guesses.
+const FREE_TEXT_GUESSES: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuestionType {
+    #[default]
+    FreeText,
+    // the answer plus the question's incorrect answers, picked by letter
+    MultipleChoice,
+    TrueFalse,
+    // both duelists guess a number and the closest one wins
+    Numeric,
+}
+
+impl QuestionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionType::FreeText => "free_text",
+            QuestionType::MultipleChoice => "multiple_choice",
+            QuestionType::TrueFalse => "true_false",
+            QuestionType::Numeric => "numeric",
+        }
+    }
+
+    // With only a handful of possible answers, one guess each keeps it from
+    // being a matter of trying them all.
+    pub fn guesses_per_player(&self) -> i32 {
+        match self {
+            QuestionType::FreeText => FREE_TEXT_GUESSES,
+            QuestionType::MultipleChoice | QuestionType::TrueFalse | QuestionType::Numeric => 1,
+        }
+    }
+
+    pub fn answer_format(&self) -> &'static str {
+        match self {
+            QuestionType::FreeText => "'!a <answer>'",
+            QuestionType::MultipleChoice => "'!a <letter>'",
+            QuestionType::TrueFalse => "'!a true' or '!a false'",
+            QuestionType::Numeric => "'!a <number>', closest guess wins",
+        }
+    }
+
+    // Guesses that can't possibly be an answer don't cost the player a guess.
+    pub fn is_valid_guess(&self, guess: &str, option_count: usize) -> bool {
+        match self {
+            QuestionType::FreeText => !guess.trim().is_empty(),
+            QuestionType::MultipleChoice => letter_index(guess).is_some_and(|i| i < option_count),
+            QuestionType::TrueFalse => parse_bool(guess).is_some(),
+            QuestionType::Numeric => parse_number(guess).is_some(),
+        }
+    }
+}
+
+impl fmt::Display for QuestionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for QuestionType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace(['-', ' '], "_").as_str() {
+            "free_text" | "text" => Ok(QuestionType::FreeText),
+            "multiple_choice" | "multiple" => Ok(QuestionType::MultipleChoice),
+            "true_false" | "boolean" => Ok(QuestionType::TrueFalse),
+            "numeric" | "number" => Ok(QuestionType::Numeric),
+            _ => Err(anyhow!(
+                "question type must be one of free_text, multiple_choice, true_false or numeric"
+            )),
+        }
+    }
+}
+
+pub fn option_letter(index: usize) -> char {
+    (b'A' + index as u8) as char
+}
+
+pub fn letter_index(guess: &str) -> Option<usize> {
+    let guess = guess.trim().trim_end_matches(')');
+    let mut chars = guess.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => {
+            Some((c.to_ascii_uppercase() as u8 - b'A') as usize)
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_bool(guess: &str) -> Option<bool> {
+    match guess.trim().to_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" => Some(true),
+        "false" | "f" | "no" | "n" => Some(false),
+        _ => None,
+    }
+}
+
+// Allows thousands separators, people like typing 1,000,000.
+pub fn parse_number(guess: &str) -> Option<f64> {
+    guess
+        .trim()
+        .replace([',', '_'], "")
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+}
+
+// Less when `a` is closer to the answer than `b`, Equal when they are as close.
+pub fn closest(answer: f64, a: f64, b: f64) -> Ordering {
+    (a - answer)
+        .abs()
+        .partial_cmp(&(b - answer).abs())
+        .unwrap_or(Ordering::Equal)
+}
+
+// Options come out in the same order for the same seed, so repeating the
+// question or judging a guess later sees the letters the duelists saw.
+pub fn shuffled_options(answer: &str, incorrect_answers: &[String], seed: u64) -> Vec<String> {
+    let mut options = std::iter::once(answer.to_string())
+        .chain(incorrect_answers.iter().cloned())
+        .collect::<Vec<String>>();
+    options.shuffle(&mut StdRng::seed_from_u64(seed));
+    options
+}
+
+pub fn format_options(options: &[String]) -> String {
+    options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| format!("{}) {}", option_letter(i), option))
+        .collect::<Vec<String>>()
+        .join(" | ")
+}
+
+// The question text as it is shown in chat, options included.
+pub fn prompt(question: &str, options: &[String]) -> String {
+    if options.is_empty() {
+        question.to_string()
+    } else {
+        format!("{} {}", question, format_options(options))
+    }
+}
+
+// Judges a guess the same way for stored and generated questions. `options` are
+// the multiple choice options in the order they were shown.
+pub fn accepts(
+    kind: QuestionType,
+    answer: &str,
+    alternate_answers: &[String],
+    options: &[String],
+    guess: &str,
+) -> bool {
+    match kind {
+        QuestionType::FreeText => {
+            let guess = guess.trim().to_lowercase();
+            std::iter::once(answer)
+                .chain(alternate_answers.iter().map(String::as_str))
+                .any(|answer| answer.trim().to_lowercase() == guess)
+        }
+        QuestionType::MultipleChoice => {
+            letter_index(guess).is_some_and(|i| options.get(i).map(String::as_str) == Some(answer))
+        }
+        QuestionType::TrueFalse => {
+            parse_bool(guess).is_some() && parse_bool(guess) == parse_bool(answer)
+        }
+        QuestionType::Numeric => match (parse_number(guess), parse_number(answer)) {
+            (Some(guess), Some(answer)) => guess == answer,
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_type() {
+        assert_eq!(QuestionType::MultipleChoice, "multiple".parse().unwrap());
+        assert_eq!(QuestionType::TrueFalse, "True-False".parse().unwrap());
+        assert_eq!(QuestionType::Numeric, "numeric".parse().unwrap());
+        assert!("essay".parse::<QuestionType>().is_err());
+    }
+
+    #[test]
+    fn test_guess_parsing() {
+        assert_eq!(Some(1), letter_index(" b "));
+        assert_eq!(Some(2), letter_index("C)"));
+        assert_eq!(None, letter_index("cd"));
+        assert_eq!(Some(false), parse_bool("F"));
+        assert_eq!(None, parse_bool("maybe"));
+        assert_eq!(Some(1000000.0), parse_number("1,000,000"));
+        assert_eq!(Some(-2.5), parse_number("-2.5"));
+        assert_eq!(None, parse_number("inf"));
+
+        assert!(QuestionType::MultipleChoice.is_valid_guess("d", 4));
+        assert!(!QuestionType::MultipleChoice.is_valid_guess("e", 4));
+        assert!(!QuestionType::Numeric.is_valid_guess("lots", 0));
+    }
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(Ordering::Less, closest(100.0, 90.0, 120.0));
+        assert_eq!(Ordering::Greater, closest(100.0, 80.0, 110.0));
+        assert_eq!(Ordering::Equal, closest(100.0, 95.0, 105.0));
+    }
+
+    #[test]
+    fn test_shuffled_options_are_stable() {
+        let wrong = vec![
+            "sega".to_string(),
+            "capcom".to_string(),
+            "atari".to_string(),
+        ];
+        let options = shuffled_options("nintendo", &wrong, 42);
+        assert_eq!(options, shuffled_options("nintendo", &wrong, 42));
+        assert_eq!(4, options.len());
+        assert!(options.contains(&"nintendo".to_string()));
+        assert_eq!(
+            "A) x | B) y",
+            format_options(&["x".to_string(), "y".to_string()])
+        );
+    }
+}
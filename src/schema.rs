@@ -13,6 +13,60 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    bets (id) {
+        id -> Int4,
+        chatter_id -> Int4,
+        #[max_length = 255]
+        game -> Varchar,
+        wager -> Int8,
+        #[max_length = 255]
+        bet -> Varchar,
+        seed_id -> Nullable<Int4>,
+        #[max_length = 255]
+        client_seed -> Nullable<Varchar>,
+        nonce -> Nullable<Int4>,
+        payout -> Int8,
+        result -> Text,
+        created_at -> Timestamp,
+        pool_cut -> Int8,
+        broadcaster_cut -> Int8,
+    }
+}
+
+diesel::table! {
+    blackjack_hands (id) {
+        id -> Int4,
+        chatter_id -> Int4,
+        wager -> Int8,
+        #[max_length = 255]
+        player_cards -> Varchar,
+        #[max_length = 255]
+        dealer_cards -> Varchar,
+        #[max_length = 255]
+        deck -> Varchar,
+        doubled -> Bool,
+        #[max_length = 255]
+        status -> Varchar,
+        payout -> Int8,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    casino_seeds (id) {
+        id -> Int4,
+        #[max_length = 255]
+        seed -> Varchar,
+        #[max_length = 255]
+        seed_hash -> Varchar,
+        nonce -> Int4,
+        revealed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     categories (id) {
         id -> Int4,
@@ -33,6 +87,10 @@ diesel::table! {
         losses -> Int4,
         last_seen -> Timestamp,
         lurk_time -> Int4,
+        last_daily_at -> Nullable<Timestamp>,
+        daily_streak -> Int4,
+        #[max_length = 255]
+        client_seed -> Nullable<Varchar>,
     }
 }
 
@@ -58,6 +116,40 @@ diesel::table! {
         challenged_id -> Nullable<Varchar>,
         challenger_guesses -> Int4,
         challenged_guesses -> Int4,
+        question_id -> Nullable<Int4>,
+        #[max_length = 255]
+        difficulty -> Nullable<Varchar>,
+        challenger_numeric_guess -> Nullable<Float8>,
+        challenged_numeric_guess -> Nullable<Float8>,
+        #[max_length = 255]
+        topic -> Nullable<Varchar>,
+        #[max_length = 255]
+        question_kind -> Nullable<Varchar>,
+        options -> Array<Text>,
+        alternate_answers -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    gambling_limits (chatter_id) {
+        chatter_id -> Int4,
+        max_wager -> Nullable<Int8>,
+        session_loss_limit -> Nullable<Int8>,
+        daily_loss_limit -> Nullable<Int8>,
+        excluded_until -> Nullable<Timestamp>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    holdings (id) {
+        id -> Int4,
+        owner_id -> Int4,
+        stock_id -> Int4,
+        num_shares -> Int4,
+        cost_basis -> Int8,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -68,6 +160,10 @@ diesel::table! {
         winner -> Nullable<Int4>,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        cancelled_at -> Nullable<Timestamptz>,
+        draw_at -> Nullable<Timestamptz>,
+        rollover -> Int8,
+        broadcaster_cut -> Int8,
     }
 }
 
@@ -83,6 +179,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    market_events (id) {
+        id -> Int4,
+        stock_id -> Int4,
+        #[max_length = 255]
+        kind -> Varchar,
+        amount -> Int8,
+        applied_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     orders (id) {
         id -> Int4,
@@ -92,6 +200,71 @@ diesel::table! {
         strike_price -> Numeric,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        #[max_length = 255]
+        status -> Varchar,
+        remaining_shares -> Int4,
+        escrow -> Int8,
+    }
+}
+
+diesel::table! {
+    pool_tickets (id) {
+        id -> Int4,
+        pool_id -> Int4,
+        chatter_id -> Int4,
+        tickets -> Int8,
+        points -> Int8,
+        refunded -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    pool_winners (id) {
+        id -> Int4,
+        pool_id -> Int4,
+        chatter_id -> Int4,
+        place -> Int4,
+        amount -> Int8,
+        tickets -> Int8,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    question_asks (id) {
+        id -> Int4,
+        question_id -> Int4,
+        #[max_length = 255]
+        channel -> Varchar,
+        player_ids -> Array<Text>,
+        asked_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    question_reports (id) {
+        id -> Int4,
+        question_id -> Int4,
+        duel_id -> Nullable<Int4>,
+        #[max_length = 255]
+        reporter_id -> Varchar,
+        reason -> Nullable<Text>,
+        resolved -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    question_votes (id) {
+        id -> Int4,
+        question_id -> Int4,
+        #[max_length = 255]
+        voter_id -> Varchar,
+        value -> Int2,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -106,6 +279,61 @@ diesel::table! {
         times_not_answered -> Int4,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        times_answered_correctly -> Int4,
+        times_guessed_wrong -> Int4,
+        #[max_length = 255]
+        status -> Varchar,
+        review_note -> Nullable<Text>,
+        points_paid -> Int8,
+        alternate_answers -> Array<Text>,
+        #[max_length = 255]
+        authored_difficulty -> Nullable<Varchar>,
+        source -> Nullable<Text>,
+        incorrect_answers -> Array<Text>,
+        #[max_length = 255]
+        kind -> Varchar,
+    }
+}
+
+diesel::table! {
+    redemptions (id) {
+        id -> Int4,
+        item_id -> Int4,
+        #[max_length = 255]
+        twitch_id -> Varchar,
+        #[max_length = 255]
+        username -> Varchar,
+        input -> Nullable<Text>,
+        cost -> Int8,
+        #[max_length = 255]
+        status -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    shop_items (id) {
+        id -> Int4,
+        #[max_length = 255]
+        name -> Varchar,
+        description -> Text,
+        cost -> Int8,
+        stock -> Nullable<Int4>,
+        per_user_limit -> Nullable<Int4>,
+        input_prompt -> Nullable<Text>,
+        enabled -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    stock_prices (id) {
+        id -> Int4,
+        stock_id -> Int4,
+        price -> Numeric,
+        created_at -> Timestamp,
     }
 }
 
@@ -124,18 +352,67 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    trades (id) {
+        id -> Int4,
+        stock_id -> Int4,
+        buy_order_id -> Int4,
+        sell_order_id -> Int4,
+        buyer_id -> Int4,
+        seller_id -> Int4,
+        num_shares -> Int4,
+        price -> Numeric,
+        value -> Int8,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(bets -> casino_seeds (seed_id));
+diesel::joinable!(bets -> chatters (chatter_id));
+diesel::joinable!(blackjack_hands -> chatters (chatter_id));
+diesel::joinable!(duels -> questions (question_id));
+diesel::joinable!(gambling_limits -> chatters (chatter_id));
+diesel::joinable!(holdings -> chatters (owner_id));
+diesel::joinable!(holdings -> stocks (stock_id));
+diesel::joinable!(market_events -> stocks (stock_id));
 diesel::joinable!(orders -> chatters (owner_id));
 diesel::joinable!(orders -> stocks (stock_id));
+diesel::joinable!(pool_tickets -> chatters (chatter_id));
+diesel::joinable!(pool_tickets -> losers_pool (pool_id));
+diesel::joinable!(pool_winners -> chatters (chatter_id));
+diesel::joinable!(pool_winners -> losers_pool (pool_id));
+diesel::joinable!(question_asks -> questions (question_id));
+diesel::joinable!(question_reports -> duels (duel_id));
+diesel::joinable!(question_reports -> questions (question_id));
+diesel::joinable!(question_votes -> questions (question_id));
 diesel::joinable!(questions -> categories (category_id));
+diesel::joinable!(redemptions -> shop_items (item_id));
+diesel::joinable!(stock_prices -> stocks (stock_id));
+diesel::joinable!(trades -> stocks (stock_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     accepted_duels,
+    bets,
+    blackjack_hands,
+    casino_seeds,
     categories,
     chatters,
     duels,
+    gambling_limits,
+    holdings,
     losers_pool,
     lurkers,
+    market_events,
     orders,
+    pool_tickets,
+    pool_winners,
+    question_asks,
+    question_reports,
+    question_votes,
     questions,
+    redemptions,
+    shop_items,
+    stock_prices,
     stocks,
+    trades,
 );
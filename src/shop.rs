@@ -0,0 +1,255 @@
+// The point shop: chatters spend points on items with !buy, and the purchases wait
+// in a queue as redemptions until a mod fulfills or refunds them.
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+
+use crate::models::{NewShopItem, ShopItem};
+
+// What a fresh install stocks, mods can change them with !additem and !removeitem.
+pub const STARTER_ITEMS: &[NewShopItem<'static>] = &[
+    NewShopItem {
+        name: "highlight",
+        description: "Highlight my message",
+        cost: 500,
+        stock: None,
+        per_user_limit: None,
+        input_prompt: Some("the message to highlight"),
+        enabled: true,
+    },
+    NewShopItem {
+        name: "vip",
+        description: "VIP for a day request",
+        cost: 20000,
+        stock: None,
+        per_user_limit: Some(1),
+        input_prompt: None,
+        enabled: true,
+    },
+    NewShopItem {
+        name: "category",
+        description: "Pick the next trivia category",
+        cost: 2000,
+        stock: None,
+        per_user_limit: None,
+        input_prompt: Some("a trivia category"),
+        enabled: true,
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurchaseError {
+    Disabled,
+    SoldOut,
+    LimitReached(i32),
+    NotEnoughPoints { cost: i64, balance: i64 },
+    MissingInput(String),
+}
+
+impl fmt::Display for PurchaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PurchaseError::Disabled => write!(f, "that item isn't for sale right now"),
+            PurchaseError::SoldOut => write!(f, "that item is sold out"),
+            PurchaseError::LimitReached(limit) => {
+                write!(f, "you can only buy that {} time(s)", limit)
+            }
+            PurchaseError::NotEnoughPoints { cost, balance } => {
+                write!(f, "that costs {} point(s) and you have {}", cost, balance)
+            }
+            PurchaseError::MissingInput(prompt) => write!(f, "add {} after the item", prompt),
+        }
+    }
+}
+
+// Checks a purchase against everything but the stock, which is taken when the
+// redemption is saved so two buyers can't get the last one.
+pub fn check_purchase(
+    item: &ShopItem,
+    balance: i64,
+    already_bought: i64,
+    input: Option<&str>,
+) -> Result<(), PurchaseError> {
+    if !item.enabled {
+        return Err(PurchaseError::Disabled);
+    }
+    if item.stock == Some(0) {
+        return Err(PurchaseError::SoldOut);
+    }
+    if let Some(limit) = item.per_user_limit {
+        if already_bought >= limit as i64 {
+            return Err(PurchaseError::LimitReached(limit));
+        }
+    }
+    if let Some(prompt) = &item.input_prompt {
+        if input.is_none_or(|i| i.trim().is_empty()) {
+            return Err(PurchaseError::MissingInput(prompt.clone()));
+        }
+    }
+    if balance < item.cost {
+        return Err(PurchaseError::NotEnoughPoints {
+            cost: item.cost,
+            balance,
+        });
+    }
+    Ok(())
+}
+
+pub fn describe(item: &ShopItem) -> String {
+    let mut description = format!("{} ({} pts): {}", item.name, item.cost, item.description);
+    if let Some(stock) = item.stock {
+        description.push_str(&format!(" [{} left]", stock));
+    }
+    description
+}
+
+// Arguments of !additem: <name> <cost> [stock=N] [limit=N] <description> [| <input prompt>]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemArgs {
+    pub name: String,
+    pub cost: i64,
+    pub stock: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub description: String,
+    pub input_prompt: Option<String>,
+}
+
+impl ItemArgs {
+    pub fn as_new_item(&self) -> NewShopItem<'_> {
+        NewShopItem {
+            name: &self.name,
+            description: &self.description,
+            cost: self.cost,
+            stock: self.stock,
+            per_user_limit: self.per_user_limit,
+            input_prompt: self.input_prompt.as_deref(),
+            enabled: true,
+        }
+    }
+}
+
+pub fn parse_item_args(args: &str) -> Result<ItemArgs> {
+    let usage =
+        "use !additem <name> <cost> [stock=N] [limit=N] <description> [| <what buyers add>]";
+    let (args, input_prompt) = match args.split_once('|') {
+        Some((args, prompt)) if !prompt.trim().is_empty() => {
+            (args, Some(prompt.trim().to_string()))
+        }
+        Some((args, _)) => (args, None),
+        None => (args, None),
+    };
+    let mut words = args.split_whitespace().peekable();
+    let name = words.next().ok_or_else(|| anyhow!(usage))?.to_lowercase();
+    let cost = words
+        .next()
+        .and_then(|c| c.parse::<i64>().ok())
+        .filter(|c| *c >= 0)
+        .ok_or_else(|| anyhow!(usage))?;
+
+    let mut stock = None;
+    let mut per_user_limit = None;
+    while let Some((key, value)) = words.peek().and_then(|w| w.split_once('=')) {
+        let value = value
+            .parse::<i32>()
+            .map_err(|_| anyhow!("{} should be a number", key))?;
+        match key {
+            "stock" if value >= 0 => stock = Some(value),
+            "limit" if value > 0 => per_user_limit = Some(value),
+            _ => return Err(anyhow!(usage)),
+        }
+        words.next();
+    }
+
+    let description = words.collect::<Vec<&str>>().join(" ");
+    if description.is_empty() {
+        return Err(anyhow!(usage));
+    }
+    Ok(ItemArgs {
+        name,
+        cost,
+        stock,
+        per_user_limit,
+        description,
+        input_prompt,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(stock: Option<i32>, limit: Option<i32>, prompt: Option<&str>) -> ShopItem {
+        let now = chrono::Utc::now().naive_utc();
+        ShopItem {
+            id: 1,
+            name: "thing".to_string(),
+            description: "A thing".to_string(),
+            cost: 100,
+            stock,
+            per_user_limit: limit,
+            input_prompt: prompt.map(str::to_string),
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_check_purchase() {
+        assert_eq!(
+            Ok(()),
+            check_purchase(&item(None, None, None), 100, 0, None)
+        );
+        assert_eq!(
+            Err(PurchaseError::NotEnoughPoints {
+                cost: 100,
+                balance: 99
+            }),
+            check_purchase(&item(None, None, None), 99, 0, None)
+        );
+        assert_eq!(
+            Err(PurchaseError::SoldOut),
+            check_purchase(&item(Some(0), None, None), 100, 0, None)
+        );
+        assert_eq!(
+            Err(PurchaseError::LimitReached(1)),
+            check_purchase(&item(None, Some(1), None), 100, 1, None)
+        );
+        assert_eq!(
+            Err(PurchaseError::MissingInput("a message".to_string())),
+            check_purchase(&item(None, None, Some("a message")), 100, 0, Some(" "))
+        );
+        let mut disabled = item(None, None, None);
+        disabled.enabled = false;
+        assert_eq!(
+            Err(PurchaseError::Disabled),
+            check_purchase(&disabled, 100, 0, None)
+        );
+    }
+
+    #[test]
+    fn test_parse_item_args() {
+        assert_eq!(
+            ItemArgs {
+                name: "shoutout".to_string(),
+                cost: 1000,
+                stock: Some(5),
+                per_user_limit: Some(1),
+                description: "Shoutout on stream".to_string(),
+                input_prompt: Some("who to shout out".to_string()),
+            },
+            parse_item_args("Shoutout 1000 stock=5 limit=1 Shoutout on stream | who to shout out")
+                .unwrap()
+        );
+        let plain = parse_item_args("hydrate 50 Make me drink water").unwrap();
+        assert_eq!(
+            (None, None, None),
+            (plain.stock, plain.per_user_limit, plain.input_prompt)
+        );
+        assert!(parse_item_args("hydrate").is_err());
+        assert!(parse_item_args("hydrate -5 Drink").is_err());
+        assert!(parse_item_args("hydrate 50").is_err());
+        assert!(parse_item_args("hydrate 50 limit=0 Drink").is_err());
+        assert!(parse_item_args("hydrate 50 stock=lots Drink").is_err());
+    }
+}
@@ -1,10 +1,20 @@
+use crate::casino::Casino;
+use crate::daily::DailyConfig;
+use crate::earning::{EarningConfig, EarningTracker};
 use crate::models;
+use crate::pool::Payouts;
+use crate::question_source::{QuestionSource, WeightedSource};
 use models::Duel;
 use std::collections::{HashMap, VecDeque};
 
 #[derive(Debug)]
 pub struct State {
     pub duel_cache: HashMap<String, VecDeque<Duel>>,
+    pub question_source: Box<dyn QuestionSource>,
+    pub earning: EarningTracker,
+    pub daily: DailyConfig,
+    pub casino: Casino,
+    pub pool_payouts: Payouts,
 }
 
 impl State {
@@ -13,6 +23,11 @@ impl State {
 
         return State {
             duel_cache: duel_cache,
+            question_source: Box::new(WeightedSource::from_env()),
+            earning: EarningTracker::new(EarningConfig::from_env()),
+            daily: DailyConfig::from_env(),
+            casino: Casino::from_env(),
+            pool_payouts: Payouts::from_env(),
         };
     }
 
@@ -0,0 +1,357 @@
+// Passive points for watching. Every interval the stream is live, the viewer list is
+// fetched from the Helix chatters endpoint, everyone present earns points, and anyone
+// who hasn't chatted during the interval has it added to their lurk time.
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::chatter;
+use crate::db;
+use crate::models::Chatter;
+
+pub const DEFAULT_HELIX_URL: &str = "https://api.twitch.tv/helix";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchConfig {
+    pub interval: Duration,
+    pub points: i64,
+}
+
+impl WatchConfig {
+    // Watch time is off unless WATCH_TIME_MINUTES is set, WATCH_TIME_POINTS is what
+    // being there for one interval earns.
+    pub fn from_env() -> Option<WatchConfig> {
+        let minutes: u64 = std::env::var("WATCH_TIME_MINUTES")
+            .ok()?
+            .trim()
+            .parse()
+            .expect("WATCH_TIME_MINUTES should be a number of minutes");
+        assert!(minutes > 0, "WATCH_TIME_MINUTES should be at least 1");
+        let points = std::env::var("WATCH_TIME_POINTS")
+            .map(|p| {
+                p.trim()
+                    .parse()
+                    .expect("WATCH_TIME_POINTS should be a number")
+            })
+            .unwrap_or(10);
+        Some(WatchConfig {
+            interval: Duration::from_secs(minutes * 60),
+            points,
+        })
+    }
+}
+
+// While watch time is on, it's what keeps track of lurk time.
+pub fn is_enabled() -> bool {
+    std::env::var("WATCH_TIME_MINUTES").is_ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HelixChatter {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChattersPage {
+    data: Vec<HelixChatter>,
+    #[serde(default)]
+    pagination: Pagination,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Pagination {
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamsPage {
+    data: Vec<serde::de::IgnoredAny>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HelixClient {
+    http: reqwest::Client,
+    base_url: String,
+    client_id: String,
+    token: String,
+}
+
+impl HelixClient {
+    pub fn new(base_url: &str, client_id: &str, token: &str) -> HelixClient {
+        HelixClient {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    // HELIX_BASE_URL can point somewhere other than twitch, e.g. the twitch cli's mock api.
+    pub fn from_env() -> HelixClient {
+        let base_url =
+            std::env::var("HELIX_BASE_URL").unwrap_or_else(|_| DEFAULT_HELIX_URL.to_string());
+        let client_id = std::env::var("TWITCH_CLIENT_ID").expect("TWITCH_CLIENT_ID must be set.");
+        let token = std::env::var("BOT_OAUTH_TOKEN").expect("BOT_OAUTH_TOKEN must be set.");
+        HelixClient::new(&base_url, &client_id, &token)
+    }
+
+    // The token has to belong to a moderator of the channel and have the
+    // moderator:read:chatters scope.
+    pub async fn get_chatters(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+    ) -> Result<Vec<HelixChatter>> {
+        let mut chatters = vec![];
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", moderator_id),
+                ("first", "1000"),
+            ];
+            if let Some(after) = cursor.as_deref() {
+                query.push(("after", after));
+            }
+            let response = self
+                .http
+                .get(format!("{}/chat/chatters", self.base_url))
+                .query(&query)
+                .header("Client-Id", &self.client_id)
+                .bearer_auth(&self.token)
+                .send()
+                .await?;
+            let status = response.status();
+            let body = response.text().await?;
+            if !status.is_success() {
+                return Err(anyhow!("helix returned {}: {}", status, body));
+            }
+
+            let page: ChattersPage = serde_json::from_str(&body)?;
+            chatters.extend(page.data);
+            match page.pagination.cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => return Ok(chatters),
+            }
+        }
+    }
+
+    // Whether the broadcaster is streaming right now.
+    pub async fn is_live(&self, broadcaster_id: &str) -> Result<bool> {
+        let response = self
+            .http
+            .get(format!("{}/streams", self.base_url))
+            .query(&[("user_id", broadcaster_id)])
+            .header("Client-Id", &self.client_id)
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("helix returned {}: {}", status, body));
+        }
+
+        let page: StreamsPage = serde_json::from_str(&body)?;
+        Ok(!page.data.is_empty())
+    }
+}
+
+// Twitch ids of the chatters that are lurking, i.e. haven't said anything this interval.
+pub fn lurking(present: &[Chatter], now: chrono::NaiveDateTime, interval: Duration) -> Vec<String> {
+    let interval = chrono::Duration::from_std(interval).expect("Interval should fit a duration");
+    present
+        .iter()
+        .filter(|c| now - c.last_seen >= interval)
+        .map(|c| c.twitch_id.clone())
+        .collect()
+}
+
+// Pays everyone present for the interval. Viewers who have never chatted don't have
+// a chatter yet, they get their welcome points the first time they say something.
+pub fn award(present: &[HelixChatter], config: &WatchConfig, bot_id: &str) -> Result<()> {
+    let ids = present
+        .iter()
+        .filter(|c| c.user_id != bot_id)
+        .map(|c| c.user_id.clone())
+        .collect::<Vec<String>>();
+    let chatters = db::get_chatters_by_twitch_ids(&ids)?;
+    for present_chatter in chatters.iter() {
+        chatter::add_points(&present_chatter.twitch_id, config.points);
+    }
+
+    let lurkers = lurking(&chatters, chrono::Utc::now().naive_utc(), config.interval);
+    db::add_lurk_time(&lurkers, config.interval.as_secs() as i32)
+}
+
+// One interval of watch time. Nobody is watching while the stream is offline, so
+// nothing is paid and no lurk time adds up. Whether it paid out.
+pub async fn tick(
+    helix: &HelixClient,
+    config: &WatchConfig,
+    broadcaster_id: &str,
+    bot_id: &str,
+) -> Result<bool> {
+    if !helix.is_live(broadcaster_id).await? {
+        return Ok(false);
+    }
+    let present = helix.get_chatters(broadcaster_id, bot_id).await?;
+    award(&present, config, bot_id)?;
+    Ok(true)
+}
+
+pub async fn run(helix: HelixClient, config: WatchConfig, broadcaster_id: String, bot_id: String) {
+    let mut ticker = tokio::time::interval(config.interval);
+    // the first tick is immediate, nobody has watched for an interval yet
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(err) = tick(&helix, &config, &broadcaster_id, &bot_id).await {
+            println!("An error occurred while awarding watch time: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Serves the given (status, body) responses, one per connection, and hands back
+    // the requests it got.
+    async fn mock_helix(
+        responses: Vec<(u16, &'static str)>,
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/helix", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = vec![];
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                requests.push(String::from_utf8(request).unwrap().to_lowercase());
+                let response = format!(
+                    "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn chatter(twitch_id: &str, minutes_since_seen: i64, now: chrono::NaiveDateTime) -> Chatter {
+        Chatter {
+            id: 1,
+            twitch_id: twitch_id.to_string(),
+            username: twitch_id.to_string(),
+            points: 0,
+            wins: 0,
+            losses: 0,
+            last_seen: now - chrono::Duration::minutes(minutes_since_seen),
+            lurk_time: 0,
+            last_daily_at: None,
+            daily_streak: 0,
+            client_seed: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_chatters_follows_pages() {
+        let (url, server) = mock_helix(vec![
+            (
+                200,
+                r#"{"data":[{"user_id":"1","user_login":"a","user_name":"A"}],"pagination":{"cursor":"next"},"total":2}"#,
+            ),
+            (
+                200,
+                r#"{"data":[{"user_id":"2","user_login":"b","user_name":"B"}],"pagination":{},"total":2}"#,
+            ),
+        ])
+        .await;
+        let helix = HelixClient::new(&url, "client", "token");
+        let chatters = helix.get_chatters("10", "20").await.unwrap();
+        assert_eq!(
+            vec!["1", "2"],
+            chatters
+                .iter()
+                .map(|c| c.user_id.as_str())
+                .collect::<Vec<&str>>()
+        );
+
+        let requests = server.await.unwrap();
+        assert!(requests[0]
+            .starts_with("get /helix/chat/chatters?broadcaster_id=10&moderator_id=20&first=1000 "));
+        assert!(requests[0].contains("client-id: client"));
+        assert!(requests[0].contains("authorization: bearer token"));
+        assert!(requests[1].contains("&after=next "));
+    }
+
+    #[tokio::test]
+    async fn test_get_chatters_errors() {
+        let (url, _server) = mock_helix(vec![(
+            401,
+            r#"{"error":"Unauthorized","status":401,"message":"Missing scope: moderator:read:chatters"}"#,
+        )])
+        .await;
+        let helix = HelixClient::new(&url, "client", "token");
+        let err = helix.get_chatters("10", "20").await.unwrap_err();
+        assert!(err.to_string().contains("moderator:read:chatters"));
+    }
+
+    #[tokio::test]
+    async fn test_is_live() {
+        let (url, server) = mock_helix(vec![
+            (
+                200,
+                r#"{"data":[{"id":"1","user_id":"10","type":"live"}],"pagination":{}}"#,
+            ),
+            (200, r#"{"data":[],"pagination":{}}"#),
+        ])
+        .await;
+        let helix = HelixClient::new(&url, "client", "token");
+        assert!(helix.is_live("10").await.unwrap());
+        assert!(!helix.is_live("10").await.unwrap());
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("get /helix/streams?user_id=10 "));
+    }
+
+    #[tokio::test]
+    async fn test_tick_pays_nothing_while_offline() {
+        let (url, server) = mock_helix(vec![(200, r#"{"data":[],"pagination":{}}"#)]).await;
+        let helix = HelixClient::new(&url, "client", "token");
+        let config = WatchConfig {
+            interval: Duration::from_secs(60),
+            points: 10,
+        };
+        assert!(!tick(&helix, &config, "10", "20").await.unwrap());
+
+        // offline, so the chatters were never fetched
+        let requests = server.await.unwrap();
+        assert_eq!(1, requests.len());
+        assert!(requests[0].starts_with("get /helix/streams?user_id=10 "));
+    }
+
+    #[test]
+    fn test_lurking() {
+        let now = chrono::Utc::now().naive_utc();
+        let present = vec![chatter("talker", 2, now), chatter("lurker", 30, now)];
+        assert_eq!(
+            vec!["lurker".to_string()],
+            lurking(&present, now, Duration::from_secs(5 * 60))
+        );
+    }
+}